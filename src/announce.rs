@@ -0,0 +1,21 @@
+//! Announcing the created merge requests in a team channel through a Slack or Mattermost
+//! incoming webhook, configured as `announce_webhook`.
+use crate::error::Result;
+use crate::utils;
+
+/// The message listing the merge requests titled `title`, one line per repo and its URL.
+/// Both Slack and Mattermost turn the URLs into links.
+pub(crate) fn message(title: &str, created: &[(String, String)]) -> String {
+    let mut text = format!("New merge requests: *{}*", title);
+    for (repo, url) in created {
+        text.push_str(&format!("\n• {}: {}", repo, url));
+    }
+    text
+}
+
+/// POST `text` to the incoming webhook at `url`. The URL is the webhook's secret, so curl
+/// reads it from stdin and errors name only its host.
+pub(crate) fn send(url: &str, text: &str) -> Result<()> {
+    let body = serde_json::json!({ "text": text }).to_string();
+    utils::post_json(url, &[], &body)
+}
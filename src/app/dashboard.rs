@@ -0,0 +1,262 @@
+//! `multimr status`: a read-only overview of my open merge requests across the workspace,
+//! with what decides whether each one can be merged. Fetched again with `r`.
+use std::sync::mpsc;
+
+use color_eyre::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::{
+    DefaultTerminal, Frame,
+    layout::{Constraint, Layout},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem, Paragraph, Wrap},
+};
+
+use super::TICK_RATE;
+use crate::batch_merge::{self, Candidate};
+use crate::config::Config;
+use crate::gitlab::{self, Approvals};
+use crate::utils;
+
+/// One of my open merge requests, with its approvals.
+#[derive(Debug, Clone)]
+pub(crate) struct DashboardRow {
+    pub(crate) candidate: Candidate,
+    /// `None` if they could not be fetched
+    pub(crate) approvals: Option<Approvals>,
+}
+
+impl DashboardRow {
+    /// The approvals given, out of those required when any are, and who gave them.
+    pub(crate) fn approvals(&self) -> String {
+        let Some(approvals) = &self.approvals else {
+            return "?".to_string();
+        };
+        let mut text = approvals.approved_by.len().to_string();
+        if approvals.approvals_required > 0 {
+            text.push_str(&format!("/{}", approvals.approvals_required));
+        }
+        for approver in &approvals.approved_by {
+            text.push_str(&format!(" {}", approver.user.username));
+        }
+        text
+    }
+
+    /// Whether GitLab would merge it, or what blocks it.
+    pub(crate) fn mergeability(&self) -> String {
+        match &self.candidate.mr.detailed_merge_status {
+            Some(status) => status.replace('_', " "),
+            None => "unknown".to_string(),
+        }
+    }
+
+    fn pipeline(&self) -> &str {
+        self.candidate
+            .mr
+            .head_pipeline
+            .as_ref()
+            .map_or("none", |p| p.status.as_str())
+    }
+}
+
+/// My open merge requests in the workspace with their approvals, see [`batch_merge::collect`].
+/// Also returns what could not be fetched.
+pub(crate) fn fetch(config: &Config) -> (Vec<DashboardRow>, Vec<String>) {
+    let (candidates, mut errors) = batch_merge::collect(config);
+    let rows = candidates
+        .into_iter()
+        .map(|candidate| {
+            let approvals = gitlab::merge_request_approvals(
+                &candidate.glab,
+                &candidate.repo_dir,
+                candidate.project.as_ref(),
+                candidate.mr.iid,
+            )
+            .map_err(|e| errors.push(format!("{}!{}: {}", candidate.name, candidate.mr.iid, e)))
+            .ok();
+            DashboardRow {
+                candidate,
+                approvals,
+            }
+        })
+        .collect();
+    (rows, errors)
+}
+
+/// The dashboard, rows are fetched in the background so it stays responsive.
+#[derive(Debug)]
+pub(crate) struct Dashboard {
+    config: Config,
+    rows: Vec<DashboardRow>,
+    errors: Vec<String>,
+    index: usize,
+    /// Receives the rows, `Some` while fetching
+    fetch: Option<mpsc::Receiver<(Vec<DashboardRow>, Vec<String>)>>,
+    running: bool,
+}
+
+impl Dashboard {
+    pub(crate) fn new(config: Config) -> Self {
+        Self {
+            config,
+            rows: Vec::new(),
+            errors: Vec::new(),
+            index: 0,
+            fetch: None,
+            running: true,
+        }
+    }
+
+    /// Show the dashboard until the user quits.
+    pub(crate) fn run(mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        self.refresh();
+        while self.running {
+            terminal.draw(|frame| self.render(frame))?;
+            if event::poll(TICK_RATE)?
+                && let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+            {
+                self.on_key_event(key);
+            }
+            self.poll_fetch();
+        }
+        Ok(())
+    }
+
+    /// Fetch the rows again, unless they are being fetched already.
+    fn refresh(&mut self) {
+        if self.fetch.is_some() {
+            return;
+        }
+        let config = self.config.clone();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(fetch(&config));
+        });
+        self.fetch = Some(rx);
+    }
+
+    fn poll_fetch(&mut self) {
+        let Some(rx) = &self.fetch else {
+            return;
+        };
+        match rx.try_recv() {
+            Ok((rows, errors)) => {
+                self.rows = rows;
+                self.errors = errors;
+                self.index = self.index.min(self.rows.len().saturating_sub(1));
+                self.fetch = None;
+            }
+            Err(mpsc::TryRecvError::Disconnected) => self.fetch = None,
+            Err(mpsc::TryRecvError::Empty) => {}
+        }
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let error_height = if self.errors.is_empty() {
+            0
+        } else {
+            self.errors.len().min(5) as u16 + 2
+        };
+        let [window, errors, footer] = Layout::vertical([
+            Constraint::Min(0),
+            Constraint::Length(error_height),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let mut title = String::from("Multi MR - Status");
+        if self.fetch.is_some() {
+            title.push_str(" (refreshing...)");
+        }
+        let title = Line::from(title).bold().blue().centered();
+
+        let name_width = self
+            .rows
+            .iter()
+            .map(|row| row.candidate.name.len() + row.candidate.mr.iid.to_string().len() + 1)
+            .max()
+            .unwrap_or_default();
+        let header = ListItem::new(format!(
+            "{:<name_width$}  {:<10}  {:<16}  {:<24}  Title",
+            "MR", "Pipeline", "Approvals", "Mergeability"
+        ))
+        .bold();
+        let items: Vec<ListItem> = std::iter::once(header)
+            .chain(self.rows.iter().enumerate().map(|(i, row)| {
+                let pipeline_color = match row.pipeline() {
+                    "success" => Color::Green,
+                    "failed" | "canceled" => Color::Red,
+                    _ => Color::Yellow,
+                };
+                let mergeability = row.mergeability();
+                let mergeability_color = if mergeability == "mergeable" {
+                    Color::Green
+                } else {
+                    Color::Yellow
+                };
+                let name = format!("{}!{}", row.candidate.name, row.candidate.mr.iid);
+                let mut item = ListItem::new(Line::from(vec![
+                    Span::raw(format!("{:<name_width$}  ", name)),
+                    Span::styled(
+                        format!("{:<10}", row.pipeline()),
+                        Style::default().fg(pipeline_color),
+                    ),
+                    Span::raw(format!("  {:<16}  ", row.approvals())),
+                    Span::styled(
+                        format!("{:<24}", mergeability),
+                        Style::default().fg(mergeability_color),
+                    ),
+                    Span::raw(format!("  {}", row.candidate.mr.title)),
+                ]));
+                if i == self.index {
+                    item = item.style(Style::default().bg(Color::Blue));
+                }
+                item
+            }))
+            .collect();
+        let list = if self.rows.is_empty() && self.fetch.is_none() {
+            List::new(["You have no open MRs in the workspace."])
+        } else {
+            List::new(items)
+        };
+        frame.render_widget(list.block(Block::bordered().title(title)), window);
+
+        if !self.errors.is_empty() {
+            frame.render_widget(
+                Paragraph::new(self.errors.join("\n"))
+                    .wrap(Wrap { trim: false })
+                    .block(
+                        Block::bordered()
+                            .title("Could not fetch")
+                            .border_style(Style::default().fg(Color::Red)),
+                    ),
+                errors,
+            );
+        }
+        frame.render_widget(
+            Paragraph::new("↑/↓/j/k: Move  o: Open MR  r: Refresh  q/Esc: Quit").centered(),
+            footer,
+        );
+    }
+
+    pub(crate) fn on_key_event(&mut self, key: KeyEvent) {
+        let count = self.rows.len();
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') if count > 0 => {
+                self.index = (self.index + 1) % count
+            }
+            KeyCode::Up | KeyCode::Char('k') if count > 0 => {
+                self.index = (self.index + count - 1) % count;
+            }
+            KeyCode::Char('o') | KeyCode::Enter => {
+                if let Some(row) = self.rows.get(self.index) {
+                    let _ = utils::open_in_browser(&row.candidate.mr.web_url);
+                }
+            }
+            KeyCode::Char('r') => self.refresh(),
+            KeyCode::Esc | KeyCode::Char('q') => self.running = false,
+            _ => {}
+        }
+    }
+}
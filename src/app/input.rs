@@ -1,4 +1,6 @@
 //! Handle the input events for the application.
+use std::path::PathBuf;
+
 use color_eyre::Result;
 use crossterm::event;
 use crossterm::event::Event;
@@ -7,15 +9,25 @@ use crossterm::event::KeyEvent;
 use crossterm::event::KeyEventKind;
 use crossterm::event::KeyModifiers;
 
-use crate::merge_request;
+use crate::branch_name;
+use crate::config::AssigneeMode;
+use crate::merge_request::Uncommitted;
+use crate::script;
 
 use super::App;
 use super::InputFocus;
 use super::Screens;
+use super::TICK_RATE;
+use super::page_step;
+use super::toast::ToastLevel;
 
 impl App {
     /// Reads the crossterm events and updates the state of [`App`].
+    /// Waits at most [`TICK_RATE`] so background results get picked up without a key press.
     pub(crate) fn handle_crossterm_events(&mut self) -> Result<()> {
+        if !event::poll(TICK_RATE)? {
+            return Ok(());
+        }
         match event::read()? {
             // it's important to check KeyEventKind::Press to avoid handling key release events
             Event::Key(key) if key.kind == KeyEventKind::Press => self.on_key_event(key),
@@ -30,140 +42,365 @@ impl App {
     pub(crate) fn on_key_event(&mut self, key: KeyEvent) {
         // Handle global key events first
         match key.code {
-            KeyCode::Char('c') | KeyCode::Char('C') => {
-                if key.modifiers == KeyModifiers::CONTROL {
-                    self.quit();
-                }
+            KeyCode::Char('c') | KeyCode::Char('C') if key.modifiers == KeyModifiers::CONTROL => {
+                self.quit();
             }
             _ => {}
         }
 
+        if self.config.reduced_motion {
+            self.toasts.clear();
+        }
+
+        if key.code == KeyCode::F(2) {
+            if self.capabilities.take().is_none() {
+                self.open_capabilities();
+            }
+            return;
+        }
+        if self.capabilities.is_some() {
+            if matches!(key.code, KeyCode::Esc | KeyCode::Char('q')) {
+                self.capabilities = None;
+            }
+            return;
+        }
+
+        // An open error dialog takes all input until it is dismissed
+        if self.error_dialog.is_some() {
+            if matches!(key.code, KeyCode::Enter | KeyCode::Esc) {
+                self.error_dialog = None;
+            }
+            return;
+        }
+
+        // So does the prompt for uncommitted changes, one repo at a time
+        if !self.uncommitted_prompt.is_empty() {
+            match key.code {
+                KeyCode::Char('c') => self.answer_uncommitted(Some(Uncommitted::Commit)),
+                KeyCode::Char('t') => self.answer_uncommitted(Some(Uncommitted::Stash)),
+                KeyCode::Char('s') => self.answer_uncommitted(None),
+                KeyCode::Esc => self.uncommitted_prompt.clear(),
+                _ => {}
+            }
+            return;
+        }
+
+        if let Some(step) = self.current_step() {
+            step.handle_key(self, key);
+            return;
+        }
         match self.screen {
-            Screens::RepoSelection => self.on_key_event_selection(key),
-            Screens::CreateMR => self.on_key_event_create_mr(key),
-            Screens::ReviewerSelection => self.on_key_event_select_reviewers(key),
-            Screens::Finalize => self.on_key_event_overview(key),
+            Screens::Plan => self.on_key_event_plan(key),
+            Screens::Executing => self.on_key_event_execution(key),
+            Screens::Results => self.on_key_event_results(key),
+            Screens::History => self.on_key_event_history(key),
+            _ => {}
         }
     }
 
     pub(crate) fn on_key_event_selection(&mut self, key: KeyEvent) {
+        if let Some(preview) = self.diff_preview.as_mut() {
+            let max_scroll = preview.text.lines().count().saturating_sub(1) as u16;
+            match key.code {
+                KeyCode::Down | KeyCode::Char('j') => {
+                    preview.scroll = (preview.scroll + 1).min(max_scroll);
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    preview.scroll = preview.scroll.saturating_sub(1);
+                }
+                KeyCode::PageDown => preview.scroll = (preview.scroll + 10).min(max_scroll),
+                KeyCode::PageUp => preview.scroll = preview.scroll.saturating_sub(10),
+                KeyCode::Char('p') | KeyCode::Char('q') | KeyCode::Esc => self.diff_preview = None,
+                _ => {}
+            }
+            return;
+        }
+        if self.select_branch_popup.is_some() {
+            self.on_key_event_select_branch_popup(key);
+            return;
+        }
+
         match key.code {
+            KeyCode::Esc if self.scan.is_some() => self.cancel_scan(),
             KeyCode::Esc | KeyCode::Char('q') => {
                 self.quit();
             }
-            KeyCode::Down | KeyCode::Char('j') => {
-                if !self.dirs.is_empty() {
-                    self.selected_index = (self.selected_index + 1) % self.dirs.len();
-                }
+            KeyCode::Down | KeyCode::Char('j') if !self.dirs.is_empty() => {
+                self.move_repo_cursor(true);
             }
-            KeyCode::Up | KeyCode::Char('k') => {
-                if !self.dirs.is_empty() {
-                    if self.selected_index == 0 {
-                        self.selected_index = self.dirs.len() - 1;
-                    } else {
-                        self.selected_index -= 1;
-                    }
-                }
+            KeyCode::Up | KeyCode::Char('k') if !self.dirs.is_empty() => {
+                self.move_repo_cursor(false);
             }
-            KeyCode::Char(' ') => {
+            KeyCode::Char(' ') if self.is_listed(self.selected_index) => {
+                if self.config.is_disabled(&self.dirs[self.selected_index]) {
+                    self.toasts.push(
+                        ToastLevel::Error,
+                        format!(
+                            "{} is disabled in its repo_overrides",
+                            self.dirs[self.selected_index]
+                        ),
+                    );
+                    return;
+                }
+                if let Some(problem) = self.repo_problem(self.selected_index)
+                    && !self.selected_repos.contains(&self.selected_index)
+                {
+                    self.toasts.push(
+                        ToastLevel::Error,
+                        format!(
+                            "{}: {}, press ! to include it anyway",
+                            self.dirs[self.selected_index], problem
+                        ),
+                    );
+                    return;
+                }
                 if self.selected_repos.contains(&self.selected_index) {
                     self.selected_repos.remove(&self.selected_index);
                 } else {
                     self.selected_repos.insert(self.selected_index);
                 }
             }
+            KeyCode::Char('!') => self.toggle_repo_anyway(self.selected_index),
+            KeyCode::Char('r') => {
+                self.rescan_repos();
+            }
+            KeyCode::Char('g') => {
+                self.select_next_repo_group();
+            }
+            KeyCode::Char('t') => {
+                self.cycle_tag_filter();
+            }
+            KeyCode::Char('a') => {
+                self.toggle_listed_repos();
+            }
+            KeyCode::Char('b') => {
+                // Start from the branch of the highlighted repo, likely one of those wanted
+                let branch = self.branches.get(self.selected_index).cloned();
+                self.select_branch_popup = Some(branch.unwrap_or_default());
+            }
+            KeyCode::Char('h') => {
+                self.open_history();
+            }
+            KeyCode::Char('p') => {
+                self.open_diff_preview();
+            }
+            KeyCode::Enter => {
+                self.next_step();
+            }
+            _ => {}
+        }
+    }
+
+    fn on_key_event_select_branch_popup(&mut self, key: KeyEvent) {
+        let Some(input) = self.select_branch_popup.as_mut() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
             KeyCode::Enter => {
-                if !self.selected_repos.is_empty() {
-                    self.screen = Screens::CreateMR;
+                let branch = input.trim().to_string();
+                if branch.is_empty() {
+                    return;
+                }
+                self.select_branch_popup = None;
+                match self.select_repos_on_branch(&branch) {
+                    0 => self.toasts.push(
+                        ToastLevel::Error,
+                        format!("No repository is on branch '{}'", branch),
+                    ),
+                    n => self.toasts.push(
+                        ToastLevel::Info,
+                        format!("Selected {} repositories on branch '{}'", n, branch),
+                    ),
                 }
             }
+            KeyCode::Esc => {
+                self.select_branch_popup = None;
+            }
             _ => {}
         }
     }
 
     pub(crate) fn on_key_event_create_mr(&mut self, key: KeyEvent) {
         match key.code {
+            KeyCode::Char('o') if key.modifiers == KeyModifiers::CONTROL => {
+                self.toggle_advanced();
+            }
             KeyCode::Tab => {
                 self.input_focus = match self.input_focus {
                     InputFocus::Title => InputFocus::Description,
                     InputFocus::Description => InputFocus::Label,
+                    InputFocus::Label if self.show_advanced => InputFocus::Draft,
                     InputFocus::Label => InputFocus::Title,
+                    InputFocus::Draft => InputFocus::Squash,
+                    InputFocus::Squash => InputFocus::RemoveSourceBranch,
+                    InputFocus::RemoveSourceBranch => InputFocus::AutoMerge,
+                    InputFocus::AutoMerge => InputFocus::TargetBranch,
+                    InputFocus::TargetBranch => InputFocus::Milestone,
+                    InputFocus::Milestone => InputFocus::DueDate,
+                    InputFocus::DueDate => InputFocus::Issues,
+                    InputFocus::Issues => InputFocus::PushOptions,
+                    InputFocus::PushOptions => InputFocus::Title,
                 };
             }
-            KeyCode::Backspace => match self.input_focus {
-                InputFocus::Title => {
-                    self.mr_title.pop();
-                }
-                InputFocus::Description => {
-                    self.mr_description.pop();
+            // The description takes editing keys including Enter, Tab and Esc still navigate
+            _ if self.input_focus == InputFocus::Description
+                && self.mr_description.handle_key(key) => {}
+            KeyCode::Backspace => {
+                if let Some(text) = self.focused_text_mut() {
+                    text.pop();
                 }
-                InputFocus::Label => {}
-            },
+            }
             KeyCode::Char(c) => match self.input_focus {
-                InputFocus::Title => self.mr_title.push(c),
-                InputFocus::Description => self.mr_description.push(c),
+                InputFocus::Draft if c == ' ' => self.advanced.draft = !self.advanced.draft,
+                InputFocus::Squash if c == ' ' => self.advanced.squash = !self.advanced.squash,
+                InputFocus::RemoveSourceBranch if c == ' ' => {
+                    self.advanced.remove_source_branch = !self.advanced.remove_source_branch;
+                }
+                InputFocus::AutoMerge if c == ' ' => {
+                    self.advanced.auto_merge = !self.advanced.auto_merge;
+                }
+                InputFocus::Draft
+                | InputFocus::Squash
+                | InputFocus::RemoveSourceBranch
+                | InputFocus::AutoMerge => {}
                 InputFocus::Label => match c {
-                    'j' => {
-                        if !self.config.labels.is_empty() {
-                            let idx = self.selected_label;
-                            self.selected_label = (idx + 1) % self.config.labels.len();
-                        }
+                    'j' if !self.labels.is_empty() => {
+                        let idx = self.selected_label;
+                        self.selected_label = (idx + 1) % self.labels.len();
                     }
-                    'k' => {
-                        if !self.config.labels.is_empty() {
-                            let idx = self.selected_label;
-                            self.selected_label = if idx == 0 {
-                                self.config.labels.len() - 1
-                            } else {
-                                idx - 1
-                            };
-                        }
+                    'k' if !self.labels.is_empty() => {
+                        let idx = self.selected_label;
+                        self.selected_label = if idx == 0 {
+                            self.labels.len() - 1
+                        } else {
+                            idx - 1
+                        };
                     }
                     _ => {}
                 },
-            },
-            KeyCode::Down => {
-                if self.input_focus == InputFocus::Label && !self.config.labels.is_empty() {
-                    let idx = self.selected_label;
-                    self.selected_label = (idx + 1) % self.config.labels.len();
+                _ => {
+                    if let Some(text) = self.focused_text_mut() {
+                        text.push(c);
+                    }
                 }
+            },
+            KeyCode::PageDown | KeyCode::PageUp if self.input_focus == InputFocus::Label => {
+                self.selected_label = page_step(
+                    self.selected_label,
+                    self.labels.len(),
+                    self.label_page_size,
+                    key.code == KeyCode::PageDown,
+                );
             }
-            KeyCode::Up => {
-                if self.input_focus == InputFocus::Label && !self.config.labels.is_empty() {
-                    let idx = self.selected_label;
-                    self.selected_label = if idx == 0 {
-                        self.config.labels.len() - 1
-                    } else {
-                        idx - 1
-                    };
-                }
+            KeyCode::Down if self.input_focus == InputFocus::Label && !self.labels.is_empty() => {
+                let idx = self.selected_label;
+                self.selected_label = (idx + 1) % self.labels.len();
+            }
+            KeyCode::Up if self.input_focus == InputFocus::Label && !self.labels.is_empty() => {
+                let idx = self.selected_label;
+                self.selected_label = if idx == 0 {
+                    self.labels.len() - 1
+                } else {
+                    idx - 1
+                };
             }
             KeyCode::Enter => {
-                self.screen = Screens::ReviewerSelection;
+                self.next_step();
             }
             KeyCode::Esc => {
-                self.screen = Screens::RepoSelection;
+                self.previous_step();
             }
             _ => {}
         }
     }
 
-    pub(crate) fn on_key_event_select_reviewers(&mut self, key: KeyEvent) {
+    pub(crate) fn on_key_event_template(&mut self, key: KeyEvent) {
+        if self.template_form.is_some() {
+            self.on_key_event_template_form(key);
+            return;
+        }
+        // The first entry is "no template"
+        let entries = self.templates.len() + 1;
         match key.code {
             KeyCode::Down | KeyCode::Char('j') => {
-                if !self.config.reviewers.is_empty() {
-                    self.reviewer_index = (self.reviewer_index + 1) % self.config.reviewers.len();
+                self.template_index = (self.template_index + 1) % entries;
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                if self.template_index == 0 {
+                    self.template_index = entries - 1;
+                } else {
+                    self.template_index -= 1;
                 }
             }
-            KeyCode::Up | KeyCode::Char('h') => {
-                if !self.config.reviewers.is_empty() {
-                    if self.reviewer_index == 0 {
-                        self.reviewer_index = self.config.reviewers.len() - 1;
-                    } else {
-                        self.reviewer_index -= 1;
-                    }
+            KeyCode::Enter => {
+                self.apply_template();
+            }
+            KeyCode::Esc => {
+                self.previous_step();
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn on_key_event_template_form(&mut self, key: KeyEvent) {
+        let Some(form) = self.template_form.as_mut() else {
+            return;
+        };
+        let fields = form.fields.len();
+        match key.code {
+            KeyCode::Tab | KeyCode::Down => form.index = (form.index + 1) % fields,
+            KeyCode::BackTab | KeyCode::Up => form.index = (form.index + fields - 1) % fields,
+            KeyCode::Enter if form.index + 1 < fields => form.index += 1,
+            KeyCode::Enter => self.submit_template_form(),
+            KeyCode::Char(c) => form.fields[form.index].1.push(c),
+            KeyCode::Backspace => {
+                form.fields[form.index].1.pop();
+            }
+            KeyCode::Esc => self.template_form = None,
+            _ => {}
+        }
+    }
+
+    pub(crate) fn on_key_event_select_reviewers(&mut self, key: KeyEvent) {
+        if self.reviewer_input.is_some() {
+            self.on_key_event_reviewer_input(key);
+            return;
+        }
+        if key.code == KeyCode::Char('+') {
+            self.reviewer_input = Some(String::new());
+            return;
+        }
+        if key.code == KeyCode::Tab && self.config.assignee_mode != AssigneeMode::Maintainer {
+            self.assignee_focus = !self.assignee_focus;
+            return;
+        }
+        if self.assignee_focus {
+            self.on_key_event_select_assignees(key);
+            return;
+        }
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') if !self.config.reviewers.is_empty() => {
+                self.reviewer_index = (self.reviewer_index + 1) % self.config.reviewers.len();
+            }
+            KeyCode::Up | KeyCode::Char('h') if !self.config.reviewers.is_empty() => {
+                if self.reviewer_index == 0 {
+                    self.reviewer_index = self.config.reviewers.len() - 1;
+                } else {
+                    self.reviewer_index -= 1;
                 }
             }
+            KeyCode::PageDown | KeyCode::PageUp => {
+                self.reviewer_index = page_step(
+                    self.reviewer_index,
+                    self.config.reviewers.len(),
+                    self.reviewer_page_size,
+                    key.code == KeyCode::PageDown,
+                );
+            }
             KeyCode::Char(' ') => {
                 if self.selected_reviewers.contains(&self.reviewer_index) {
                     self.selected_reviewers.remove(&self.reviewer_index);
@@ -172,40 +409,417 @@ impl App {
                 }
             }
             KeyCode::Enter => {
-                self.screen = Screens::Finalize;
+                self.next_step();
             }
             KeyCode::Esc => {
-                self.screen = Screens::CreateMR;
+                self.previous_step();
+            }
+            _ => {}
+        }
+    }
+
+    fn on_key_event_reviewer_input(&mut self, key: KeyEvent) {
+        let completion = self.reviewer_completion().map(str::to_string);
+        let Some(input) = self.reviewer_input.as_mut() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Tab => {
+                if let Some(completion) = completion {
+                    *input = completion;
+                }
+            }
+            KeyCode::Enter => self.add_typed_reviewer(),
+            KeyCode::Esc => self.reviewer_input = None,
+            _ => {}
+        }
+    }
+
+    fn on_key_event_select_assignees(&mut self, key: KeyEvent) {
+        let candidates = self.assignee_candidates().len();
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') if candidates > 0 => {
+                self.assignee_index = (self.assignee_index + 1) % candidates;
+            }
+            KeyCode::Up | KeyCode::Char('h') if candidates > 0 => {
+                self.assignee_index = (self.assignee_index + candidates - 1) % candidates;
+            }
+            KeyCode::PageDown | KeyCode::PageUp => {
+                self.assignee_index = page_step(
+                    self.assignee_index,
+                    candidates,
+                    self.assignee_page_size,
+                    key.code == KeyCode::PageDown,
+                );
+            }
+            KeyCode::Char(' ') => self.toggle_assignee(),
+            KeyCode::Enter => self.next_step(),
+            KeyCode::Esc => self.previous_step(),
+            _ => {}
+        }
+    }
+
+    pub(crate) fn on_key_event_preflight(&mut self, key: KeyEvent) {
+        let repo_count = self.selected_repos.len();
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') if repo_count > 0 => {
+                self.preflight_index = (self.preflight_index + 1) % repo_count;
+            }
+            KeyCode::Up | KeyCode::Char('k') if repo_count > 0 => {
+                self.preflight_index = (self.preflight_index + repo_count - 1) % repo_count;
             }
+            KeyCode::Char(' ') => {
+                if let Some(dir) = self.selected_dirs().get(self.preflight_index)
+                    && !self.skipped_dirs.remove(dir)
+                {
+                    self.skipped_dirs.insert(dir.clone());
+                }
+            }
+            KeyCode::Char('r') if self.preflight_run.is_none() => self.start_preflight(),
+            KeyCode::Enter => self.next_step(),
+            KeyCode::Esc => self.previous_step(),
             _ => {}
         }
     }
 
     pub(crate) fn on_key_event_overview(&mut self, key: KeyEvent) {
+        if self.target_branch_popup.is_some() {
+            self.on_key_event_target_branch_popup(key);
+            return;
+        }
+        if self.branch_popup.is_some() {
+            self.on_key_event_branch_popup(key);
+            return;
+        }
+        if self.confirm_popup.is_some() {
+            self.on_key_event_confirm_popup(key);
+            return;
+        }
+        if self.script_popup.is_some() {
+            self.on_key_event_script_popup(key);
+            return;
+        }
+
+        let selected_dirs = self.selected_dirs();
         match key.code {
-            KeyCode::Char('y') | KeyCode::Enter => {
-                self.mr = Some(merge_request::MergeRequest {
-                    title: self.mr_title.clone(),
-                    description: self.mr_description.clone(),
-                    reviewers: self
-                        .selected_reviewers
-                        .iter()
-                        .map(|&i| self.config.reviewers[i].clone())
-                        .collect(),
-                    labels: self
+            KeyCode::Char('o') if key.modifiers == KeyModifiers::CONTROL => {
+                self.toggle_advanced();
+            }
+            KeyCode::Down | KeyCode::Char('j') if !selected_dirs.is_empty() => {
+                self.finalize_index = (self.finalize_index + 1) % selected_dirs.len();
+            }
+            KeyCode::Up | KeyCode::Char('k') if !selected_dirs.is_empty() => {
+                if self.finalize_index == 0 {
+                    self.finalize_index = selected_dirs.len() - 1;
+                } else {
+                    self.finalize_index -= 1;
+                }
+            }
+            KeyCode::Char('b') => {
+                self.branch_popup = Some(self.branch_name());
+            }
+            KeyCode::Char('e') => {
+                if let Some(dir) = selected_dirs.get(self.finalize_index) {
+                    let current = self
                         .config
-                        .labels
-                        .keys()
-                        .nth(self.selected_label)
-                        .map(|k| vec![k.clone()])
-                        .unwrap_or_default(),
-                    assignee: self.config.assignee.clone(),
-                });
+                        .repo_override(dir)
+                        .and_then(|o| o.target_branch.clone())
+                        .unwrap_or_default();
+                    self.target_branch_popup = Some(current);
+                }
+            }
+            KeyCode::Char('s') => {
+                if let Some(dir) = selected_dirs.get(self.finalize_index) {
+                    let overrides = self.config.repo_overrides.entry(dir.clone()).or_default();
+                    overrides.skip_ci = Some(!overrides.skip_ci.unwrap_or_default());
+                }
+            }
+            KeyCode::Char('c') => {
+                if let Some(dir) = selected_dirs.get(self.finalize_index) {
+                    let overrides = self.config.repo_overrides.entry(dir.clone()).or_default();
+                    overrides.no_checkout = Some(!overrides.no_checkout.unwrap_or_default());
+                }
+            }
+            KeyCode::Char('S') => self.advanced.squash = !self.advanced.squash,
+            KeyCode::Char('R') => {
+                self.advanced.remove_source_branch = !self.advanced.remove_source_branch;
+            }
+            KeyCode::Char('m') => self.advanced.auto_merge = !self.advanced.auto_merge,
+            KeyCode::Char('d') => {
+                self.screen = Screens::Plan;
+            }
+            KeyCode::Char('w') => {
+                let path = self
+                    .config
+                    .emit_script
+                    .clone()
+                    .unwrap_or_else(|| PathBuf::from(script::DEFAULT_PATH));
+                self.script_popup = Some(path.display().to_string());
+            }
+            // Enter is deliberately not accepted, so pressing it once too often can't start the batch
+            KeyCode::Char('y') => {
+                self.confirm_execution();
+            }
+            KeyCode::Char('n') | KeyCode::Esc => {
+                self.previous_step();
+            }
+            _ => {}
+        }
+    }
 
-                self.quit_completed();
+    pub(crate) fn on_key_event_plan(&mut self, key: KeyEvent) {
+        let repo_count = self.selected_repos.len();
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') if repo_count > 0 => {
+                self.finalize_index = (self.finalize_index + 1) % repo_count;
+            }
+            KeyCode::Up | KeyCode::Char('k') if repo_count > 0 => {
+                if self.finalize_index == 0 {
+                    self.finalize_index = repo_count - 1;
+                } else {
+                    self.finalize_index -= 1;
+                }
+            }
+            KeyCode::Char('x') => {
+                if let Some(dir) = self.selected_dirs().get(self.finalize_index) {
+                    self.toggle_excluded_suspicious(dir);
+                }
+            }
+            KeyCode::Char('c') => self.copy_plan(false),
+            KeyCode::Char('C') => self.copy_plan(true),
+            KeyCode::Char(' ') => {
+                if let Some(dir) = self.selected_dirs().get(self.finalize_index)
+                    && !self.skipped_dirs.remove(dir)
+                {
+                    self.skipped_dirs.insert(dir.clone());
+                }
+            }
+            KeyCode::Char('y') => {
+                // Large batches ask for the typed confirmation on the Finalize screen
+                self.screen = Screens::Finalize;
+                self.confirm_execution();
             }
             KeyCode::Char('n') | KeyCode::Esc => {
-                self.screen = Screens::ReviewerSelection;
+                self.show_finalize();
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn on_key_event_execution(&mut self, key: KeyEvent) {
+        let Some(execution) = &self.execution else {
+            return;
+        };
+        let repo_count = execution.repos.len();
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') if repo_count > 0 => {
+                self.execution_index = (self.execution_index + 1) % repo_count;
+            }
+            KeyCode::Up | KeyCode::Char('k') if repo_count > 0 => {
+                if self.execution_index == 0 {
+                    self.execution_index = repo_count - 1;
+                } else {
+                    self.execution_index -= 1;
+                }
+            }
+            KeyCode::Char('p') if !execution.is_finished() => {
+                execution.toggle_pause();
+            }
+            KeyCode::Char('y') if execution.awaiting_canary() => {
+                execution.toggle_pause();
+            }
+            KeyCode::Char('q') | KeyCode::Esc if execution.is_paused() => {
+                execution.stop();
+            }
+            KeyCode::Char('q') | KeyCode::Esc | KeyCode::Enter if execution.is_finished() => {
+                self.quit();
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn on_key_event_results(&mut self, key: KeyEvent) {
+        let repo_count = self.execution.as_ref().map_or(0, |e| e.repos.len());
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') if repo_count > 0 => {
+                self.execution_index = (self.execution_index + 1) % repo_count;
+            }
+            KeyCode::Up | KeyCode::Char('k') if repo_count > 0 => {
+                if self.execution_index == 0 {
+                    self.execution_index = repo_count - 1;
+                } else {
+                    self.execution_index -= 1;
+                }
+            }
+            KeyCode::Char('o') => self.open_results(false),
+            KeyCode::Char('O') => self.open_results(true),
+            KeyCode::Char('y') => self.copy_results(),
+            KeyCode::Char('r') => self.retry_failed(),
+            KeyCode::Char('A') => self.start_batch_merge(true, false),
+            KeyCode::Char('M') => self.start_batch_merge(false, true),
+            KeyCode::Char('p') => self.start_pipeline_watch(),
+            KeyCode::Char('R') => self.run_for_real(),
+            KeyCode::Char('q') | KeyCode::Esc | KeyCode::Enter => self.quit(),
+            _ => {}
+        }
+    }
+
+    pub(crate) fn on_key_event_history(&mut self, key: KeyEvent) {
+        let run_count = self.history.len();
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') if run_count > 0 => {
+                self.history_index = (self.history_index + 1) % run_count;
+            }
+            KeyCode::Up | KeyCode::Char('k') if run_count > 0 => {
+                self.history_index = (self.history_index + run_count - 1) % run_count;
+            }
+            KeyCode::Char('o') | KeyCode::Enter => self.reopen_run(false),
+            KeyCode::Char('y') => self.reopen_run(true),
+            KeyCode::Esc | KeyCode::Char('q') => self.screen = Screens::RepoSelection,
+            _ => {}
+        }
+    }
+
+    /// The text input that currently has focus on the CreateMR screen, if any.
+    fn focused_text_mut(&mut self) -> Option<&mut String> {
+        match self.input_focus {
+            InputFocus::Title => Some(&mut self.mr_title),
+            InputFocus::TargetBranch => Some(&mut self.advanced.target_branch),
+            InputFocus::Milestone => Some(&mut self.advanced.milestone),
+            InputFocus::DueDate => Some(&mut self.advanced.due_date),
+            InputFocus::Issues => Some(&mut self.advanced.issues),
+            InputFocus::PushOptions => Some(&mut self.advanced.push_options),
+            InputFocus::Description
+            | InputFocus::Label
+            | InputFocus::Draft
+            | InputFocus::Squash
+            | InputFocus::RemoveSourceBranch
+            | InputFocus::AutoMerge => None,
+        }
+    }
+
+    /// Show or hide the advanced options, moving focus out of them when they get hidden.
+    fn toggle_advanced(&mut self) {
+        self.show_advanced = !self.show_advanced;
+        if !self.show_advanced && self.input_focus.is_advanced() {
+            self.input_focus = InputFocus::Title;
+        }
+    }
+
+    /// Edits the target branch override of the repo highlighted on the Finalize screen.
+    pub(crate) fn on_key_event_target_branch_popup(&mut self, key: KeyEvent) {
+        let Some(input) = self.target_branch_popup.as_mut() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => {
+                let branch = input.trim().to_string();
+                if let Some(dir) = self.selected_dirs().get(self.finalize_index) {
+                    let message = if branch.is_empty() {
+                        format!("{} targets the default branch", dir)
+                    } else {
+                        format!("{} targets {}", dir, branch)
+                    };
+                    let overrides = self.config.repo_overrides.entry(dir.clone()).or_default();
+                    overrides.target_branch = (!branch.is_empty()).then_some(branch);
+                    self.toasts.push(ToastLevel::Info, message);
+                }
+                self.target_branch_popup = None;
+                self.restart_preflight();
+            }
+            KeyCode::Esc => {
+                self.target_branch_popup = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Edits the path the batch's script is written to.
+    pub(crate) fn on_key_event_script_popup(&mut self, key: KeyEvent) {
+        let Some(input) = self.script_popup.as_mut() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter if !input.trim().is_empty() => {
+                let path = PathBuf::from(input.trim());
+                self.script_popup = None;
+                self.export_script(&path);
+            }
+            KeyCode::Esc => {
+                self.script_popup = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Edits the name of the branch created in every repo.
+    pub(crate) fn on_key_event_branch_popup(&mut self, key: KeyEvent) {
+        let Some(input) = self.branch_popup.as_mut() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => {
+                let branch = input.trim().to_string();
+                if branch.is_empty() {
+                    self.branch_name = None;
+                } else if branch_name::is_valid(&branch) {
+                    self.branch_name = Some(branch);
+                } else {
+                    self.toasts.push(
+                        ToastLevel::Error,
+                        format!("'{}' is not a valid branch name", branch),
+                    );
+                    return;
+                }
+                self.branch_popup = None;
+                self.restart_preflight();
+            }
+            KeyCode::Esc => {
+                self.branch_popup = None;
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn on_key_event_confirm_popup(&mut self, key: KeyEvent) {
+        let Some(input) = self.confirm_popup.as_mut() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => {
+                let input = std::mem::take(input);
+                if self.is_confirmation(&input) {
+                    self.confirm_popup = None;
+                    self.start_execution();
+                } else {
+                    self.toasts.push(
+                        ToastLevel::Error,
+                        "Type the number of repositories or 'yes' to confirm",
+                    );
+                }
+            }
+            KeyCode::Esc => {
+                self.confirm_popup = None;
             }
             _ => {}
         }
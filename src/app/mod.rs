@@ -1,52 +1,179 @@
 //! Holds main application and rendering logic for the Multi MR CLI tool.
-use std::fs;
-use std::{collections::HashSet, process::Stdio};
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
 
 use color_eyre::Result;
 
 use ratatui::{
     DefaultTerminal, Frame,
     buffer::Buffer,
-    layout::{Constraint, Layout, Rect},
-    style::{Color, Style, Stylize},
-    text::Line,
-    widgets::{Block, List, ListItem, Paragraph, Widget},
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Clear, LineGauge, List, ListItem, Paragraph, Widget, Wrap},
 };
 
-use crate::config::Config;
-use crate::merge_request;
+pub(crate) use dashboard::Dashboard;
+pub(crate) use profile::{ProfilePick, ProfilePicker};
+pub(crate) use prune::Prune;
 
+use crate::announce;
+use crate::batch_merge;
+use crate::branch_detection;
+use crate::branch_name;
+use crate::capabilities::{self, Capability};
+use crate::clipboard::Clipboard;
+use crate::config::{AssigneeMode, Config, LabelFetch, LabelOrder, LabelPreset, ReviewerStrategy};
+use crate::due_date;
+use crate::execution::{Execution, RepoState};
+use crate::gitlab;
+use crate::hyperlinks;
+use crate::label_usage::{self, LabelUsage};
+use crate::last_reviewers::{self, LastReviewers};
+use crate::merge_queue;
+use crate::merge_request::{self, Uncommitted};
+use crate::notification;
+use crate::pipeline_watch::{self, PipelineStatus};
+use crate::plan::{self, Plan, PlannedRepo};
+use crate::preflight::{self, CheckResult, Outcome};
+use crate::report;
+use crate::runs::{self, RunRecord};
+use crate::script;
+use crate::templates;
+use crate::utils;
+use crate::warnings::{self, Severity, Warning};
+
+pub(crate) mod dashboard;
 mod input;
+mod profile;
+mod prune;
+mod scan;
+mod steps;
+mod text_area;
+mod toast;
 
-#[derive(Debug, Default)]
+use scan::RepoScan;
+use text_area::TextArea;
+use toast::{ToastLevel, Toasts};
+
+/// How long to wait for input before checking on background work again.
+pub(crate) const TICK_RATE: Duration = Duration::from_millis(100);
+/// Frames of the spinner shown while scanning, one per tick.
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+/// The narrowest a column of a long reviewer or label list gets before it is paged instead.
+const MIN_COLUMN_WIDTH: u16 = 24;
+/// The batch can only be confirmed this long after the Finalize screen is shown, so a
+/// repeated key press on the previous screen can't start it.
+pub(crate) const ARMING_DELAY: Duration = Duration::from_millis(700);
+
+#[derive(Debug, Default, PartialEq, Eq)]
 pub(crate) enum Screens {
     #[default]
     RepoSelection,
+    Template,
     CreateMR,
     ReviewerSelection,
+    Preflight,
     Finalize,
+    Plan,
+    Executing,
+    Results,
+    History,
 }
 
 impl Screens {
     pub(crate) fn help(&self) -> &'static str {
         match self {
-            Screens::RepoSelection => "↑/↓/j/k: Move  Space: Select  Enter: Next  q/Esc: Quit",
-            Screens::CreateMR => "Tab: Switch field  ↑/↓/j/k: Select Label  Enter: Next  Esc: Back",
-            Screens::ReviewerSelection => "↑/↓/j/k: Move   Space:  Select  Enter: Next  Esc: Back",
-            Screens::Finalize => "y/Enter: Confirm  n/Esc: Back",
+            Screens::RepoSelection => {
+                "↑/↓/j/k: Move  Space: Select  !: Include anyway  p: Preview changes  g: Select next group  t: Filter by tag  a: Select all listed  b: Select by branch  r: Rescan  h: History  F2: Capabilities  Enter: Next  q/Esc: Quit"
+            }
+            Screens::Template => "↑/↓/j/k: Move  Enter: Use template  Esc: Back",
+            Screens::CreateMR => {
+                "Tab: Switch field  ↑/↓/j/k/PgUp/PgDn: Select Label  Ctrl+O: Advanced  Enter: Next (new line in Description)  Esc: Back"
+            }
+            Screens::ReviewerSelection => {
+                "↑/↓/j/k/PgUp/PgDn: Move  Space: Select  +: Add reviewer  Tab: Reviewers/Assignees  Enter: Next  Esc: Back"
+            }
+            Screens::Preflight => {
+                "↑/↓/j/k: Move  Space: Skip/Include repo  r: Check again  Enter: Next  Esc: Back"
+            }
+            Screens::Finalize => {
+                "↑/↓/j/k: Move  b: Edit branch name  e: Edit target branch  s: Toggle skip CI  c: Toggle no checkout  S/R/m: Toggle squash/delete branch/auto-merge  d: Dry run  w: Write script  Ctrl+O: Advanced  y: Confirm  n/Esc: Back"
+            }
+            Screens::Plan => {
+                "↑/↓/j/k: Move  Space: Skip/Include repo  x: Exclude suspicious paths  c/C: Copy script of repo/all  y: Confirm  n/Esc: Back"
+            }
+            Screens::Executing => {
+                "↑/↓/j/k: Move  p: Pause/Resume  q: Stop when paused, exit when done"
+            }
+            Screens::Results => {
+                "↑/↓/j/k: Move  o: Open MR  O: Open all  y: Copy URLs  r: Retry failed  A/M: Approve/Merge those with passed pipelines  p: Watch pipelines  R: Run for real after a dry run  q/Esc/Enter: Exit"
+            }
+            Screens::History => {
+                "↑/↓/j/k: Move  o/Enter: Open all MRs of the run  y: Copy URLs  Esc: Back"
+            }
+        }
+    }
+
+    /// What the screen is for, shown below it during `multimr tour`.
+    pub(crate) fn tour(&self) -> &'static str {
+        match self {
+            Screens::RepoSelection => {
+                "Every git repo in the working directory is listed with its current branch. Select the ones that should get the same change with Space, here all three sandbox repos have an edited README. Press Enter to continue."
+            }
+            Screens::Template => {
+                "Repos can bring merge request templates, pick one to start the description from it."
+            }
+            Screens::CreateMR => {
+                "Type the title and description shared by every merge request, Tab moves between the fields. The branch name is derived from the title. Press Enter in the title to continue."
+            }
+            Screens::ReviewerSelection => {
+                "Reviewers come from multimr.toml and, outside the tour, the members of the projects. Select some with Space, Tab switches to the assignees. Press Enter to continue."
+            }
+            Screens::Preflight => {
+                "Each repo is checked for what would make it fail halfway: glab login, origin, a detached HEAD, conflicts with the target branch and an existing branch. Fix the problems and press r, or skip the repo with Space."
+            }
+            Screens::Finalize => {
+                "Everything that will happen, with warnings about what looks off. Press d to see the exact commands per repo, or y to run the batch. The tour always runs dry: the commands are only logged."
+            }
+            Screens::Plan => {
+                "The commands multimr would run in each repo. Space leaves a repo out of the batch, y runs it."
+            }
+            Screens::Executing => {
+                "The repos are worked on in parallel, each line shows how far one got. Nothing is pushed during the tour."
+            }
+            Screens::Results => {
+                "The outcome per repo, and outside the tour the URLs of the merge requests to open or copy. That's the tour, press q to leave: the sandbox repos are removed again."
+            }
+            Screens::History => {
+                "Every batch is recorded, here you can reopen or copy the merge requests of an earlier one."
+            }
         }
     }
 
     pub(crate) fn title(&self) -> &'static str {
         match self {
             Screens::RepoSelection => "Select Repos",
+            Screens::Template => "Pick Template",
             Screens::CreateMR => "Describe",
             Screens::ReviewerSelection => "Add Reviewers",
+            Screens::Preflight => "Preflight",
             Screens::Finalize => "Finalize",
+            Screens::Plan => "Plan (dry run)",
+            Screens::Executing => "Creating MRs",
+            Screens::Results => "Results",
+            Screens::History => "History",
         }
     }
 }
 
+/// What [`App::inspect_changes`] found in a repo: its changed lines if they could be counted
+/// and its suspicious paths.
+type Inspected = (String, Option<usize>, Vec<String>);
+
 /// The main application which holds the state and logic of the application.
 #[derive(Debug, Default)]
 pub struct App {
@@ -58,6 +185,12 @@ pub struct App {
     pub(crate) dirs: Vec<String>,
     /// List of current branches in the selected directories.
     pub(crate) branches: Vec<String>,
+    /// State of the working tree of each directory, found while scanning
+    pub(crate) statuses: Vec<RepoStatus>,
+    /// Default branch of each directory found by the configured `[branch_detection]` command
+    pub(crate) default_branches: HashMap<String, String>,
+    /// The scan of the working directory, `Some` while repos are still being inspected
+    pub(crate) scan: Option<RepoScan>,
     /// Indices of selected directories
     pub(crate) selected_repos: HashSet<usize>,
     /// Currently highlighted directory index
@@ -67,11 +200,29 @@ pub struct App {
     /// Title of the merge requests to be created
     pub(crate) mr_title: String,
     /// Description of the merge requests to be created
-    pub(crate) mr_description: String,
+    pub(crate) mr_description: TextArea,
     /// Indices of selected reviewers
     pub(crate) selected_reviewers: HashSet<usize>,
+    /// Labels offered in the label picker, configured ones first in key order, or the most
+    /// used first, see [`LabelOrder`]
+    pub(crate) labels: Vec<(String, String)>,
+    /// How often each label was picked before, to order the label picker by
+    pub(crate) label_usage: LabelUsage,
     /// Currently selected label index
     pub(crate) selected_label: usize,
+    /// How many labels fit on one page of the label picker, as last rendered
+    pub(crate) label_page_size: usize,
+    /// Merge request templates of the selected repos, with the number of repos having each
+    pub(crate) templates: Vec<(String, usize)>,
+    /// Highlighted entry on the Template screen, 0 is "no template"
+    pub(crate) template_index: usize,
+    /// The picked template and the description it filled in, the description is only taken
+    /// from each repo's own template as long as it is not edited
+    pub(crate) template: Option<(String, String)>,
+    /// Values typed for the placeholders of the picked template, filled in per repo
+    pub(crate) template_values: HashMap<String, String>,
+    /// Asks for the placeholder values of the template being picked, `Some` while open
+    pub(crate) template_form: Option<TemplateForm>,
 
     /// Whether the user has completed the input process and did not quit early
     pub(crate) user_input_completed: bool,
@@ -81,10 +232,266 @@ pub struct App {
     pub(crate) input_focus: InputFocus,
     /// Currently highlighted reviewer index
     pub(crate) reviewer_index: usize,
+    /// How many reviewers fit on one page, as last rendered
+    pub(crate) reviewer_page_size: usize,
+    /// Username typed on the ReviewerSelection screen to add as reviewer, `Some` while typing
+    pub(crate) reviewer_input: Option<String>,
+    /// Members of the selected projects, completed to when typing a reviewer
+    pub(crate) project_members: Vec<String>,
+    /// Usernames the merge requests are assigned to, in the order they were picked
+    pub(crate) selected_assignees: Vec<String>,
+    /// Currently highlighted entry of [`App::assignee_candidates`]
+    pub(crate) assignee_index: usize,
+    /// How many assignee candidates fit on one page, as last rendered
+    pub(crate) assignee_page_size: usize,
+    /// Whether Tab moved the focus of the ReviewerSelection screen to the assignees
+    pub(crate) assignee_focus: bool,
+    /// Currently highlighted repo on the Finalize screen, indexes into [`App::selected_dirs`]
+    pub(crate) finalize_index: usize,
+    /// Selected repos left out of the batch on the Plan screen or from the prompt about
+    /// uncommitted changes, they stay selected
+    pub(crate) skipped_dirs: HashSet<String>,
+    /// What happens to the uncommitted changes of selected repos on a feature branch, as
+    /// answered in the prompt; those without an answer are left as they are
+    pub(crate) uncommitted: HashMap<String, Uncommitted>,
+    /// Repos still to be asked about their uncommitted changes, the first one is asked
+    pub(crate) uncommitted_prompt: Vec<String>,
+    /// Input buffer of the target branch popup, `Some` while the popup is open
+    pub(crate) target_branch_popup: Option<String>,
+    /// Branch name typed on the Finalize screen, `None` to derive it from the title
+    pub(crate) branch_name: Option<String>,
+    /// Input buffer of the branch name popup, `Some` while the popup is open
+    pub(crate) branch_popup: Option<String>,
+    /// Input buffer of the path the script is exported to, `Some` while the popup is open
+    pub(crate) script_popup: Option<String>,
+    /// When the Finalize screen was shown, confirming is only possible after [`ARMING_DELAY`]
+    pub(crate) finalize_shown_at: Option<Instant>,
+    /// Input buffer of the typed confirmation for large batches, `Some` while it is asked for
+    pub(crate) confirm_popup: Option<String>,
+    /// Lines the uncommitted changes of each selected repo add and remove, counted by
+    /// [`App::inspect_changes`]
+    pub(crate) changed_lines: HashMap<String, usize>,
+    /// Uncommitted paths of each selected repo matching `suspicious_paths`, found by
+    /// [`App::inspect_changes`] for the repos whose changes are committed
+    pub(crate) suspicious: HashMap<String, Vec<String>>,
+    /// Receives the changed lines and suspicious paths of each repo as it is inspected,
+    /// `Some` while inspecting
+    pub(crate) inspection: Option<mpsc::Receiver<Inspected>>,
+    /// Problems with the chosen labels/reviewers per repo, found by [`App::validate_selection`]
+    pub(crate) validation_problems: HashMap<String, Vec<String>>,
+    /// Receives the problems of each repo as it is checked, `Some` while validating
+    pub(crate) validation: Option<mpsc::Receiver<(String, Vec<String>)>>,
+    /// The results of the preflight checks per repo, see [`App::start_preflight`]
+    pub(crate) preflight: HashMap<String, Vec<CheckResult>>,
+    /// Receives the results of the preflight checks, `Some` while checking
+    pub(crate) preflight_run: Option<mpsc::Receiver<(String, Vec<CheckResult>)>>,
+    /// Currently highlighted repo on the Preflight screen
+    pub(crate) preflight_index: usize,
+    /// Receives project members fetched in the background, `Some` while fetching
+    pub(crate) reviewer_fetch: Option<mpsc::Receiver<Vec<String>>>,
+    /// Receives the user glab is logged in as when no assignee is configured, `Some` while fetching
+    pub(crate) user_fetch: Option<mpsc::Receiver<String>>,
+    /// GitLab hosts glab couldn't reach and why, empty for the default host, see
+    /// [`App::start_reachability_check`]
+    pub(crate) unreachable_hosts: HashMap<String, String>,
+    /// Receives the hosts glab couldn't reach, `Some` while probing
+    pub(crate) reachability_check: Option<mpsc::Receiver<(String, String)>>,
+    /// The first maintainer of the repos assigned to one, by directory, see
+    /// [`App::start_maintainer_fetch`]
+    pub(crate) maintainers: HashMap<String, String>,
+    /// Receives the maintainers looked up in the background, `Some` while looking up
+    pub(crate) maintainer_fetch: Option<mpsc::Receiver<(String, String)>>,
+    /// Shuffles the reviewers for `reviewer_strategy = "random"`, fixed for the session so
+    /// every screen shows the same shares
+    pub(crate) reviewer_seed: u64,
+    /// Whether MR URLs and repo names on the results screen are OSC 8 hyperlinks
+    pub(crate) hyperlinks: bool,
+    /// The reviewers picked for each repo in earlier batches, preselected when it is selected
+    pub(crate) last_reviewers: LastReviewers,
+    /// Whether the assignee was detected with `glab api user` instead of configured
+    pub(crate) assignee_detected: bool,
+    /// Receives project labels fetched in the background, `Some` while fetching
+    pub(crate) label_fetch: Option<mpsc::Receiver<Vec<String>>>,
+    /// Open merge requests each reviewer is asked to review, across all repos in the working dir
+    pub(crate) review_load: HashMap<String, usize>,
+    /// Receives the review load fetched in the background, `Some` while fetching
+    pub(crate) review_load_fetch: Option<mpsc::Receiver<HashMap<String, usize>>>,
+    /// Receives where the run report was published, `Some` while publishing
+    pub(crate) report_publish: Option<mpsc::Receiver<crate::error::Result<Vec<String>>>>,
+    /// Receives what came of approving or merging the batch's MRs, `Some` while they are
+    pub(crate) batch_merge: Option<mpsc::Receiver<batch_merge::Outcome>>,
+    /// The commands of each repo in the dry run of this session, so running the batch for
+    /// real afterwards flags what changed since
+    pub(crate) dry_run_plan: Option<Vec<(String, Vec<String>)>>,
+    /// Receives whether the created MRs were announced to the `announce_webhook`, `Some`
+    /// while announcing
+    pub(crate) announce: Option<mpsc::Receiver<crate::error::Result<()>>>,
+    /// Receives the repos whose MRs were enqueued in the `[merge_queue]` and why others
+    /// weren't, `Some` while enqueueing
+    pub(crate) merge_queue_submit: Option<mpsc::Receiver<(Vec<String>, Vec<String>)>>,
+    /// The pipelines of the created MRs by directory, as last looked up
+    pub(crate) pipelines: HashMap<String, PipelineStatus>,
+    /// Receives the pipelines that changed, `Some` while watching them
+    pub(crate) pipeline_watch: Option<mpsc::Receiver<(String, PipelineStatus)>>,
+    /// Whether the advanced options are expanded on the CreateMR and Finalize screens
+    pub(crate) show_advanced: bool,
+    /// Options most merge requests don't need, hidden behind [`App::show_advanced`]
+    pub(crate) advanced: AdvancedOptions,
+    /// Transient feedback messages shown in the corner of the screen
+    pub(crate) toasts: Toasts,
+    /// Connection to the system clipboard, made on first use
+    pub(crate) clipboard: Clipboard,
+    /// Errors shown in a dialog on top of the current screen until dismissed
+    pub(crate) error_dialog: Option<String>,
+    /// Uncommitted changes of a repo shown on the RepoSelection screen, `Some` while open
+    pub(crate) diff_preview: Option<DiffPreview>,
+    /// Input buffer of the branch to select the repos on with `b`, `Some` while the popup is open
+    pub(crate) select_branch_popup: Option<String>,
+    /// The repo group selected last, with `g` or `--repo-group`
+    pub(crate) repo_group: Option<String>,
+    /// Only repos with this tag are listed, cycled with `t`
+    pub(crate) tag_filter: Option<String>,
+    /// When the batch started, in seconds since the Unix epoch, see [`runs::run_id`]
+    pub(crate) run_started_at: u64,
+    /// Recorded runs shown on the History screen, newest first
+    pub(crate) history: Vec<RunRecord>,
+    /// Highlighted run on the History screen
+    pub(crate) history_index: usize,
+    /// The capabilities popup, opened with F2 on any screen
+    pub(crate) capabilities: Option<Vec<Capability>>,
 
     // TODO: move this out of here
     /// The merge request that is created at the end of the process
     pub(crate) mr: Option<merge_request::MergeRequest>,
+    /// The batch being executed, `Some` once the user confirmed on the Finalize screen
+    pub(crate) execution: Option<Execution>,
+    /// Currently highlighted repo on the Executing screen
+    pub(crate) execution_index: usize,
+}
+
+/// What the scan found out about the working tree of a repo, `None` where it is unknown.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct RepoStatus {
+    /// No uncommitted changes
+    pub(crate) clean: Option<bool>,
+    /// Commits ahead of and behind the upstream branch, `None` without an upstream
+    pub(crate) ahead_behind: Option<(usize, usize)>,
+    /// Subject of the last commit on the current branch
+    pub(crate) last_commit: Option<String>,
+    /// HEAD points to a commit instead of a branch
+    pub(crate) detached: bool,
+    /// There is no `origin` remote to push to
+    pub(crate) missing_origin: bool,
+}
+
+impl RepoStatus {
+    pub(crate) fn scan(repo_dir: &Path) -> Self {
+        let repo = utils::open_repo(repo_dir).ok();
+        Self {
+            clean: utils::is_clean(repo_dir).ok(),
+            ahead_behind: utils::ahead_behind(repo_dir).ok(),
+            last_commit: utils::last_commit_subject(repo_dir).ok(),
+            detached: repo
+                .as_ref()
+                .is_some_and(|r| r.head_detached().unwrap_or_default()),
+            missing_origin: repo
+                .as_ref()
+                .is_some_and(|r| r.find_remote("origin").is_err()),
+        }
+    }
+
+    /// Why no merge request can be created from the repo as it is, it is only selected when
+    /// included deliberately.
+    pub(crate) fn problem(&self) -> Option<&'static str> {
+        if self.detached {
+            Some("detached HEAD")
+        } else if self.missing_origin {
+            Some("no origin remote")
+        } else {
+            None
+        }
+    }
+
+    /// Colored badges for the repo list: dirty or clean, ahead/behind and the last commit.
+    /// A clean repo is highlighted when `nothing_to_commit`, since it can't get a merge request.
+    fn badges(&self, nothing_to_commit: bool, colorblind: bool) -> Vec<Span<'_>> {
+        let mut badges = Vec::new();
+        if let Some(problem) = self.problem() {
+            badges.push(
+                format!(" [{}{}]", mark(colorblind, "⚠"), problem)
+                    .red()
+                    .bold(),
+            );
+        }
+        match self.clean {
+            Some(true) if nothing_to_commit => badges
+                .push(format!(" [{}clean, nothing to commit]", mark(colorblind, "⚠")).yellow()),
+            Some(true) => badges.push(" [clean]".dark_gray()),
+            Some(false) => badges.push(" [dirty]".light_magenta()),
+            None => {}
+        }
+        if let Some((ahead, behind)) = self.ahead_behind {
+            if ahead > 0 {
+                badges.push(format!(" ↑{}", ahead).green());
+            }
+            if behind > 0 {
+                badges.push(format!(" ↓{}", behind).red());
+            }
+        }
+        if let Some(subject) = &self.last_commit {
+            badges.push(format!("  {}", subject).dark_gray().italic());
+        }
+        badges
+    }
+}
+
+/// Colors tag badges are drawn in, each tag always gets the same one.
+const TAG_COLORS: [Color; 6] = [
+    Color::Cyan,
+    Color::Green,
+    Color::Magenta,
+    Color::Yellow,
+    Color::LightBlue,
+    Color::LightRed,
+];
+
+/// A colored badge for `tag` in the repo list.
+fn tag_badge(tag: &str) -> Span<'_> {
+    let color = TAG_COLORS[tag.bytes().map(usize::from).sum::<usize>() % TAG_COLORS.len()];
+    Span::styled(
+        format!(" {} ", tag),
+        Style::default().fg(Color::Black).bg(color),
+    )
+}
+
+/// `symbol` and a space in colorblind mode, so a state isn't told by its color alone.
+fn mark(colorblind: bool, symbol: &str) -> String {
+    if colorblind {
+        format!("{} ", symbol)
+    } else {
+        String::new()
+    }
+}
+
+/// The `{{PLACEHOLDER}}`s of a template being picked, one input per placeholder.
+#[derive(Debug, Default)]
+pub(crate) struct TemplateForm {
+    /// Name of the template and its content in the first selected repo that has it
+    pub(crate) name: String,
+    pub(crate) content: String,
+    /// Each placeholder with the value typed so far
+    pub(crate) fields: Vec<(String, String)>,
+    /// The field being typed in
+    pub(crate) index: usize,
+}
+
+/// The changes of a repo, shown in a scrollable popup before selecting it.
+#[derive(Debug, Default)]
+pub(crate) struct DiffPreview {
+    pub(crate) dir: String,
+    pub(crate) text: String,
+    /// Number of lines scrolled down
+    pub(crate) scroll: u16,
 }
 
 #[derive(Debug, Default, PartialEq, Eq)]
@@ -93,66 +500,509 @@ pub(crate) enum InputFocus {
     Title,
     Description,
     Label,
+    Draft,
+    Squash,
+    RemoveSourceBranch,
+    AutoMerge,
+    TargetBranch,
+    Milestone,
+    DueDate,
+    Issues,
+    PushOptions,
+}
+
+impl InputFocus {
+    /// Whether this field is part of the advanced options.
+    pub(crate) fn is_advanced(&self) -> bool {
+        !matches!(
+            self,
+            InputFocus::Title | InputFocus::Description | InputFocus::Label
+        )
+    }
+}
+
+/// Power options of the merge requests, which stay hidden until the user expands them.
+#[derive(Debug, Default)]
+pub(crate) struct AdvancedOptions {
+    pub(crate) draft: bool,
+    pub(crate) squash: bool,
+    pub(crate) remove_source_branch: bool,
+    /// Merge as soon as the pipeline succeeds
+    pub(crate) auto_merge: bool,
+    /// Target branch for all repos, empty for the project's default branch
+    pub(crate) target_branch: String,
+    pub(crate) milestone: String,
+    /// Review deadline, a date or an offset like `+3 days`
+    pub(crate) due_date: String,
+    /// Comma separated issues closed by the merge requests, `dir:N` for the repo `dir` only
+    pub(crate) issues: String,
+    /// Comma separated `git push -o` options
+    pub(crate) push_options: String,
+}
+
+impl AdvancedOptions {
+    /// The due date as typed, followed by the date it resolves to.
+    fn due_date_text(&self) -> String {
+        if self.due_date.trim().is_empty() {
+            return String::new();
+        }
+        match due_date::resolve(&self.due_date, due_date::today()) {
+            Some(date) if date != self.due_date.trim() => format!("{} ({})", self.due_date, date),
+            Some(_) => self.due_date.clone(),
+            None => format!("{} (invalid)", self.due_date),
+        }
+    }
+
+    /// One line per option, paired with the input field it is edited with.
+    pub(crate) fn lines(&self) -> [(InputFocus, String); 9] {
+        let check = |b: bool| if b { "[x]" } else { "[ ]" };
+        [
+            (InputFocus::Draft, format!("{} Draft", check(self.draft))),
+            (
+                InputFocus::Squash,
+                format!("{} Squash commits when merged", check(self.squash)),
+            ),
+            (
+                InputFocus::RemoveSourceBranch,
+                format!(
+                    "{} Delete source branch when merged",
+                    check(self.remove_source_branch)
+                ),
+            ),
+            (
+                InputFocus::AutoMerge,
+                format!("{} Merge when pipeline succeeds", check(self.auto_merge)),
+            ),
+            (
+                InputFocus::TargetBranch,
+                format!("Target branch: {}", self.target_branch),
+            ),
+            (
+                InputFocus::Milestone,
+                format!("Milestone: {}", self.milestone),
+            ),
+            (
+                InputFocus::DueDate,
+                format!("Due date: {}", self.due_date_text()),
+            ),
+            (
+                InputFocus::Issues,
+                format!("Closes issues: {}", self.issues),
+            ),
+            (
+                InputFocus::PushOptions,
+                format!("Push options: {}", self.push_options),
+            ),
+        ]
+    }
 }
 
 impl App {
     pub(crate) fn new(config: Config) -> Self {
+        let mut labels: Vec<(String, String)> = config
+            .labels
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        labels.sort();
+        // Presets come first, they bundle the most with one pick
+        let mut presets: Vec<(String, String)> = config
+            .label_presets
+            .iter()
+            .map(|(name, preset)| (name.clone(), preset.summary()))
+            .collect();
+        presets.sort();
+        labels.splice(0..0, presets);
+
+        let advanced = AdvancedOptions {
+            squash: config.squash,
+            remove_source_branch: config.remove_source_branch,
+            auto_merge: config.auto_merge,
+            due_date: config.due_date.clone().unwrap_or_default(),
+            ..Default::default()
+        };
+        let selected_assignees = config
+            .assignee
+            .iter()
+            .chain(&config.assignees)
+            .cloned()
+            .collect();
+        let links = hyperlinks::enabled(&config);
         let mut app = Self {
             config,
+            selected_assignees,
+            reviewer_seed: random_seed(),
+            hyperlinks: links,
+            last_reviewers: last_reviewers::default_path()
+                .map(|path| last_reviewers::load(&path))
+                .unwrap_or_default(),
+            label_usage: label_usage::default_path()
+                .map(|path| label_usage::load(&path))
+                .unwrap_or_default(),
+            labels,
+            advanced,
             selected_label: 0,
             selected_index: 0,
             ..Default::default()
         };
 
-        // Populate dirs with all directories in the current working directory
-        if let Ok(entries) = fs::read_dir(&app.config.working_dir) {
-            app.dirs = entries
-                .filter_map(|entry| entry.ok())
-                .filter_map(|entry| {
-                    let path = entry.path();
-                    if path.is_dir() {
-                        path.file_name().map(|n| n.to_string_lossy().to_string())
-                    } else {
-                        None
-                    }
-                })
-                .collect();
+        app.sort_labels();
+        app.preselect_cli_reviewers();
+        app.scan_repos();
+        if let Some(name) = app.config.repo_group.clone()
+            && !app.select_repo_group(&name)
+        {
+            app.show_error(format!("There is no repo group '{}' in [groups]", name));
+        }
+        app
+    }
 
-            let mut valid_dirs = Vec::new();
-            for dir in &app.dirs {
-                // Check if the directory is a git repository
-                if std::process::Command::new("git")
-                    .arg("rev-parse")
-                    .arg("--is-inside-work-tree")
-                    .current_dir(app.config.working_dir.join(dir))
-                    .stderr(Stdio::null())
-                    .stdout(Stdio::null())
-                    .status()
-                    .is_ok()
-                {
-                    // If it is, add it to the list of valid directories
-                    valid_dirs.push(dir.clone());
+    /// Populate dirs with all git repositories in the working directory, and their branches.
+    /// In group mode they are the projects of the GitLab group, on their default branches.
+    pub(crate) fn scan_repos(&mut self) {
+        self.dirs.clear();
+        self.branches.clear();
+        self.statuses.clear();
+        self.default_branches.clear();
+        // The working directory or group is listed and its repos inspected in the
+        // background, see `poll_scan`
+        self.scan = Some(match self.config.group.clone() {
+            Some(group) => RepoScan::start_group(&self.config, &group),
+            None => RepoScan::start(&self.config),
+        });
+    }
+
+    /// Add the repos found by the scan in progress to the list, and finish it once every
+    /// directory has been inspected.
+    pub(crate) fn poll_scan(&mut self) {
+        let Some(scan) = &mut self.scan else {
+            return;
+        };
+        let found = scan.poll();
+        for error in std::mem::take(&mut scan.errors) {
+            self.show_error(error);
+        }
+        let Some(scan) = &mut self.scan else {
+            return;
+        };
+        for repo in found {
+            if self.config.skip_clean_repos
+                && self.config.group.is_none()
+                && nothing_to_commit(&repo.branch, repo.default_branch.as_deref(), &repo.status)
+            {
+                scan.skipped_clean += 1;
+                continue;
+            }
+            // Keep the repos of each root together, in the order of the roots
+            let workspace = self.config.workspace_index(&repo.dir);
+            let position = self
+                .dirs
+                .iter()
+                .position(|d| self.config.workspace_index(d) > workspace)
+                .unwrap_or(self.dirs.len());
+            if position < self.dirs.len() {
+                self.selected_repos = self
+                    .selected_repos
+                    .iter()
+                    .map(|&i| if i >= position { i + 1 } else { i })
+                    .collect();
+                if self.selected_index >= position {
+                    self.selected_index += 1;
                 }
             }
-            app.dirs = valid_dirs;
+            let name = self.config.repo_name(&repo.dir);
+            // Repos included anyway before a rescan stay included
+            if scan.reselect.iter().any(|d| *d == repo.dir || d == name)
+                && !self.config.is_disabled(&repo.dir)
+                && (scan.rescan || repo.status.problem().is_none())
+            {
+                self.selected_repos.insert(position);
+            }
+            if let Some(default_branch) = repo.default_branch {
+                self.default_branches
+                    .insert(repo.dir.clone(), default_branch);
+            }
+            self.dirs.insert(position, repo.dir);
+            self.branches.insert(position, repo.branch);
+            self.statuses.insert(position, repo.status);
+        }
 
-            for dir in app.dirs.iter() {
-                // Check if the directory is a git repository
-                if let Ok(current_branch_output) = std::process::Command::new("git")
-                    .arg("branch")
-                    .arg("--show-current")
-                    .current_dir(app.config.working_dir.join(dir))
-                    .output()
-                {
-                    app.branches.push(
-                        String::from_utf8_lossy(&current_branch_output.stdout)
-                            .trim()
-                            .to_string(),
-                    )
-                }
+        if !scan.is_finished() {
+            return;
+        }
+        if scan.skipped_clean > 0 {
+            self.toasts.push(
+                ToastLevel::Info,
+                format!(
+                    "Skipped {} clean repositories on their default branch",
+                    scan.skipped_clean
+                ),
+            );
+        }
+        if scan.rescan {
+            self.toasts.push(
+                ToastLevel::Info,
+                format!("Scan refreshed: {} repositories", self.dirs.len()),
+            );
+        }
+        self.scan = None;
+        if self.screen == Screens::Finalize {
+            self.inspect_changes();
+        }
+    }
+
+    /// Stop the scan in progress and go on with the repos found so far, for working
+    /// directories on slow mounts.
+    pub(crate) fn cancel_scan(&mut self) {
+        let Some(scan) = self.scan.take() else {
+            return;
+        };
+        self.toasts.push(
+            ToastLevel::Info,
+            format!(
+                "Scan stopped after {} of {} directories, {} repositories found",
+                scan.done,
+                scan.total,
+                self.dirs.len()
+            ),
+        );
+    }
+
+    /// Whether the repo at `index` in dirs has no changes to commit and is on its default
+    /// branch, so there is nothing to create a merge request from.
+    pub(crate) fn has_nothing_to_commit(&self, index: usize) -> bool {
+        match (
+            self.dirs.get(index),
+            self.branches.get(index),
+            self.statuses.get(index),
+        ) {
+            (Some(dir), Some(branch), Some(status)) => {
+                nothing_to_commit(branch, self.default_branch_of(dir), status)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the repos are local checkouts, rather than projects of a group worked on remotely.
+    /// Fetching members and labels and validating them needs a checkout's remote.
+    pub(crate) fn has_checkouts(&self) -> bool {
+        self.config.group.is_none()
+    }
+
+    /// Show which optional integrations are active in a popup.
+    pub(crate) fn open_capabilities(&mut self) {
+        let clipboard = self.clipboard.connect().map_err(|e| e.to_string());
+        self.capabilities = Some(capabilities::detect(&self.config, clipboard));
+    }
+
+    /// Show the uncommitted changes of the highlighted repo in a popup.
+    pub(crate) fn open_diff_preview(&mut self) {
+        let Some(dir) = self.dirs.get(self.selected_index).cloned() else {
+            return;
+        };
+        if !self.has_checkouts() {
+            self.toasts
+                .push(ToastLevel::Info, "Group projects have no local changes");
+            return;
+        }
+        match utils::change_summary(&self.config.working_dir.join(&dir)) {
+            Ok(text) => {
+                self.diff_preview = Some(DiffPreview {
+                    dir,
+                    text,
+                    scroll: 0,
+                })
+            }
+            Err(e) => self.show_error(format!("Cannot show the changes in {}: {}", dir, e)),
+        }
+    }
+
+    /// Show an error in the error dialog, below the ones already shown.
+    pub(crate) fn show_error(&mut self, message: impl Into<String>) {
+        let message = message.into();
+        self.error_dialog = Some(match self.error_dialog.take() {
+            Some(shown) => format!("{}\n{}", shown, message),
+            None => message,
+        });
+    }
+
+    /// Select the repos of the named group from the config, replacing the selection.
+    /// Repos that are still being scanned get selected once they are found.
+    /// Returns whether the group exists.
+    pub(crate) fn select_repo_group(&mut self, name: &str) -> bool {
+        let Some(members) = self.config.repo_groups.get(name).cloned() else {
+            return false;
+        };
+        self.selected_repos = self
+            .dirs
+            .iter()
+            .enumerate()
+            .filter(|(_, d)| members.iter().any(|m| m == self.config.repo_name(d)))
+            .filter(|(i, _)| self.is_selectable(*i))
+            .map(|(i, _)| i)
+            .collect();
+        if let Some(scan) = &mut self.scan {
+            scan.reselect = members;
+        }
+        self.repo_group = Some(name.to_string());
+        true
+    }
+
+    /// Select the repos of the group after the last selected one, in name order.
+    pub(crate) fn select_next_repo_group(&mut self) {
+        let mut names: Vec<String> = self.config.repo_groups.keys().cloned().collect();
+        names.sort();
+        let next = match &self.repo_group {
+            Some(current) => names.iter().find(|n| *n > current).or(names.first()),
+            None => names.first(),
+        };
+        let Some(name) = next.cloned() else {
+            self.toasts
+                .push(ToastLevel::Info, "No repo groups configured in [groups]");
+            return;
+        };
+        self.select_repo_group(&name);
+        let members = self.config.repo_groups[&name].len();
+        let message = if self.scan.is_some() || self.selected_repos.len() == members {
+            format!("Selected group {}", name)
+        } else {
+            format!(
+                "Selected group {}: {} of its {} repos are here",
+                name,
+                self.selected_repos.len(),
+                members
+            )
+        };
+        self.toasts.push(ToastLevel::Info, message);
+    }
+
+    /// Whether the repo at `index` in dirs is shown with the current tag filter.
+    pub(crate) fn is_listed(&self, index: usize) -> bool {
+        match (&self.tag_filter, self.dirs.get(index)) {
+            (None, Some(_)) => true,
+            (Some(tag), Some(dir)) => self.config.tags(dir).contains(tag),
+            (_, None) => false,
+        }
+    }
+
+    /// Whether the repo at `index` may be selected, it is not disabled in its overrides and
+    /// has no [`RepoStatus::problem`]. A repo with a problem can still be included with `!`.
+    pub(crate) fn is_selectable(&self, index: usize) -> bool {
+        self.dirs
+            .get(index)
+            .is_some_and(|dir| !self.config.is_disabled(dir))
+            && self.repo_problem(index).is_none()
+    }
+
+    /// The [`RepoStatus::problem`] of the repo at `index`.
+    pub(crate) fn repo_problem(&self, index: usize) -> Option<&'static str> {
+        self.statuses.get(index).and_then(RepoStatus::problem)
+    }
+
+    /// Select the repo at `index` despite its [`RepoStatus::problem`], or deselect it.
+    pub(crate) fn toggle_repo_anyway(&mut self, index: usize) {
+        if !self.is_listed(index)
+            || self
+                .dirs
+                .get(index)
+                .is_none_or(|d| self.config.is_disabled(d))
+        {
+            return;
+        }
+        if !self.selected_repos.remove(&index) {
+            self.selected_repos.insert(index);
+        }
+    }
+
+    /// Move the cursor to the next (or previous) listed repo, wrapping around.
+    pub(crate) fn move_repo_cursor(&mut self, forward: bool) {
+        let len = self.dirs.len();
+        for step in 1..=len {
+            let index = if forward {
+                (self.selected_index + step) % len
+            } else {
+                (self.selected_index + len - step) % len
+            };
+            if self.is_listed(index) {
+                self.selected_index = index;
+                return;
+            }
+        }
+    }
+
+    /// Only list the repos with the next tag in name order, after the last one list all again.
+    pub(crate) fn cycle_tag_filter(&mut self) {
+        let tags = self.config.all_tags();
+        if tags.is_empty() {
+            self.toasts
+                .push(ToastLevel::Info, "No tags configured in [repo_overrides]");
+            return;
+        }
+        self.tag_filter = match &self.tag_filter {
+            Some(current) => tags.into_iter().find(|t| t > current),
+            None => tags.into_iter().next(),
+        };
+        if !self.is_listed(self.selected_index) {
+            self.move_repo_cursor(true);
+        }
+    }
+
+    /// Select exactly the repos that are on `branch` now, replacing the selection.
+    /// Returns how many were selected.
+    pub(crate) fn select_repos_on_branch(&mut self, branch: &str) -> usize {
+        self.selected_repos = self
+            .branches
+            .iter()
+            .enumerate()
+            .filter(|(i, b)| *b == branch && self.is_selectable(*i))
+            .map(|(i, _)| i)
+            .collect();
+        self.selected_repos.len()
+    }
+
+    /// Select every listed repo, or deselect them if they all are selected already.
+    pub(crate) fn toggle_listed_repos(&mut self) {
+        let listed: Vec<usize> = (0..self.dirs.len())
+            .filter(|&i| self.is_listed(i) && self.is_selectable(i))
+            .collect();
+        if listed.iter().all(|i| self.selected_repos.contains(i)) {
+            for i in &listed {
+                self.selected_repos.remove(i);
+            }
+        } else {
+            self.selected_repos.extend(listed);
+        }
+    }
+
+    /// Scan the working directory again, keeping repos that are still there selected.
+    pub(crate) fn rescan_repos(&mut self) {
+        if self.scan.is_some() {
+            return;
+        }
+        let selected = self.selected_dirs();
+        self.selected_repos.clear();
+        self.scan_repos();
+        self.selected_index = 0;
+        match &mut self.scan {
+            // Selected again as they are found
+            Some(scan) => {
+                scan.reselect = selected;
+                scan.rescan = true;
+            }
+            None => {
+                self.selected_repos = self
+                    .dirs
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, d)| selected.contains(d) && !self.config.is_disabled(d))
+                    .map(|(i, _)| i)
+                    .collect();
+                self.toasts.push(
+                    ToastLevel::Info,
+                    format!("Scan refreshed: {} repositories", self.dirs.len()),
+                );
             }
         }
-        app
     }
 
     /// Run the application's main loop.
@@ -161,6 +1011,7 @@ impl App {
         while self.running {
             terminal.draw(|frame| self.render(frame))?;
             self.handle_crossterm_events()?;
+            self.poll_background();
         }
         Ok(self)
     }
@@ -180,19 +1031,115 @@ impl App {
             .centered();
 
         // Outer block for the whole screen (except help)
-        let outer_block = Block::bordered().title(title);
-        let inner_area = outer_block.inner(window);
-
-        match self.screen {
-            Screens::RepoSelection => self.render_repo_selection(inner_area, frame.buffer_mut()),
-            Screens::CreateMR => self.render_create_mr(inner_area, frame.buffer_mut()),
-            Screens::ReviewerSelection => {
-                self.render_reviewer_selection(inner_area, frame.buffer_mut())
+        let mut outer_block = Block::bordered().title(title);
+        if let Some((trail, current)) = self.step_trail() {
+            let mut spans = Vec::new();
+            for (i, step) in trail.into_iter().enumerate() {
+                if i > 0 {
+                    spans.push(Span::raw(" › ").dark_gray());
+                }
+                spans.push(match i.cmp(&current) {
+                    std::cmp::Ordering::Less => {
+                        Span::raw(format!("{}{}", mark(self.config.colorblind, "✓"), step)).green()
+                    }
+                    std::cmp::Ordering::Equal => Span::raw(step).bold(),
+                    std::cmp::Ordering::Greater => Span::raw(step).dark_gray(),
+                });
             }
-            Screens::Finalize => self.render_overview(inner_area, frame.buffer_mut()),
+            outer_block = outer_block.title_bottom(Line::from(spans).centered());
+        }
+        let mut inner_area = outer_block.inner(window);
+        if self.config.tour {
+            let [screen_area, tour_area] =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(5)]).areas(inner_area);
+            inner_area = screen_area;
+            Paragraph::new(self.screen.tour())
+                .wrap(Wrap { trim: true })
+                .block(
+                    Block::bordered()
+                        .title("Tour")
+                        .border_style(Style::default().fg(Color::Yellow)),
+                )
+                .render(tour_area, frame.buffer_mut());
+        }
+
+        match self.current_step() {
+            Some(step) => step.render(self, inner_area, frame.buffer_mut()),
+            None => match self.screen {
+                Screens::Plan => self.render_plan(inner_area, frame.buffer_mut()),
+                Screens::Executing | Screens::Results => {
+                    self.render_execution(inner_area, frame.buffer_mut())
+                }
+                Screens::History => self.render_history(inner_area, frame.buffer_mut()),
+                _ => {}
+            },
         }
 
         outer_block.render(window, frame.buffer_mut());
+        self.toasts
+            .render(inner_area, frame.buffer_mut(), self.config.colorblind);
+        if let Some(capabilities) = &self.capabilities {
+            let area = popup_area(
+                inner_area,
+                80,
+                (capabilities.len() as u16 + 2).min(inner_area.height),
+            );
+            Clear.render(area, frame.buffer_mut());
+            let lines: Vec<Line> = capabilities
+                .iter()
+                .map(|c| {
+                    let (mark, color) = if c.active {
+                        ("✓", Color::Green)
+                    } else {
+                        ("✗", Color::DarkGray)
+                    };
+                    Line::from(vec![
+                        Span::styled(
+                            format!("{} {:<17}", mark, c.name),
+                            Style::default().fg(color),
+                        ),
+                        Span::raw(c.reason.as_str()),
+                    ])
+                })
+                .collect();
+            Paragraph::new(lines)
+                .block(Block::bordered().title("Capabilities (F2/Esc: Close)"))
+                .render(area, frame.buffer_mut());
+        }
+        if let Some(dir) = self.uncommitted_prompt.first() {
+            let area = popup_area(inner_area, 70, 7);
+            Clear.render(area, frame.buffer_mut());
+            Paragraph::new(vec![
+                Line::from(format!(
+                    "{} has uncommitted changes on {}.",
+                    dir,
+                    self.branch_of(dir)
+                )),
+                Line::from(""),
+                Line::from("c: Commit them into the merge request"),
+                Line::from("t: Stash them while the merge request is created"),
+                Line::from("s: Skip the repo"),
+            ])
+            .block(
+                Block::bordered()
+                    .title("Uncommitted changes (Esc: Leave them as they are)")
+                    .border_style(Style::default().fg(Color::Yellow)),
+            )
+            .render(area, frame.buffer_mut());
+        }
+        if let Some(message) = &self.error_dialog {
+            let height = message.lines().count() as u16 + 4;
+            let area = popup_area(inner_area, 70, height.min(inner_area.height));
+            Clear.render(area, frame.buffer_mut());
+            Paragraph::new(message.as_str())
+                .wrap(Wrap { trim: false })
+                .block(
+                    Block::bordered()
+                        .title("Error (Enter/Esc: Dismiss)")
+                        .border_style(Style::default().fg(Color::Red)),
+                )
+                .render(area, frame.buffer_mut());
+        }
         Paragraph::new(self.screen.help())
             .centered()
             .style(Style::default().fg(Color::DarkGray))
@@ -201,18 +1148,37 @@ impl App {
 
     /// The repo selection shows a list of directories in the current working directory and which ones are selected.
     pub(crate) fn render_repo_selection(&mut self, window: Rect, buf: &mut Buffer) {
-        let [repo_list_area, dir_info_area] = Layout::vertical([
+        let [repo_list_area, progress_area, dir_info_area] = Layout::vertical([
             Constraint::Min(3),
-            Constraint::Length(1), // for directory info
+            Constraint::Length(u16::from(self.scan.is_some())), // for the scan progress
+            Constraint::Length(1),                              // for directory info
         ])
         .areas(window);
 
-        let repos: Vec<ListItem> = self
-            .dirs
-            .iter()
-            .enumerate()
-            .map(|(i, d)| {
-                let line = if self.selected_repos.contains(&i) {
+        let several_roots = !self.config.working_dirs.is_empty();
+        let mut repos: Vec<ListItem> = Vec::new();
+        for (i, d) in self.dirs.iter().enumerate() {
+            if !self.is_listed(i) {
+                continue;
+            }
+            let workspace = self.config.workspace_index(d);
+            if several_roots
+                && (i == 0 || self.config.workspace_index(&self.dirs[i - 1]) != workspace)
+            {
+                let root = self.config.working_dirs[workspace].display().to_string();
+                repos.push(ListItem::new(Line::from(root).bold().blue()));
+            }
+            // The root is in the section header
+            let d = self.config.repo_name(d);
+            let disabled = self.config.is_disabled(&self.dirs[i]);
+            repos.push({
+                let text = if disabled {
+                    format!(
+                        "[-] {} ({}) disabled",
+                        d,
+                        self.branches.get(i).unwrap_or(&"???".to_string())
+                    )
+                } else if self.selected_repos.contains(&i) {
                     format!(
                         "[x] {} ({})",
                         d,
@@ -225,23 +1191,103 @@ impl App {
                         self.branches.get(i).unwrap_or(&"???".to_string())
                     )
                 };
+                let mut line = Line::from(text);
+                for tag in self.config.tags(&self.dirs[i]) {
+                    line.push_span(" ");
+                    line.push_span(tag_badge(tag));
+                }
+                if let Some(status) = self.statuses.get(i) {
+                    for badge in
+                        status.badges(self.has_nothing_to_commit(i), self.config.colorblind)
+                    {
+                        line.push_span(badge);
+                    }
+                }
                 let mut item = ListItem::new(line);
+                if disabled {
+                    item = item.style(Style::default().fg(Color::DarkGray));
+                }
                 if i == self.selected_index {
                     item = item.style(Style::default().fg(Color::Yellow).bg(Color::Blue));
                 }
                 item
-            })
-            .collect();
+            });
+        }
 
         List::new(repos).render(repo_list_area, buf);
 
+        if let Some(scan) = &self.scan {
+            let mut label = String::new();
+            if !self.config.reduced_motion {
+                let frame = scan.started_at.elapsed().as_millis() / 100;
+                label.push(SPINNER[frame as usize % SPINNER.len()]);
+                label.push(' ');
+            }
+            if scan.listed {
+                label.push_str(&format!(
+                    "Scanning {}/{} {}",
+                    scan.done,
+                    scan.total,
+                    scan.probing.as_deref().unwrap_or_default()
+                ));
+            } else if let Some(group) = &self.config.group {
+                label.push_str(&format!("Listing the projects of {}…", group));
+            } else {
+                label.push_str("Listing the working directory…");
+            }
+            LineGauge::default()
+                .ratio(scan.progress())
+                .label(format!("{}  (Esc: Stop scanning)", label))
+                .filled_style(Style::default().fg(Color::Blue))
+                .render(progress_area, buf);
+        }
+        let roots: Vec<String> = self
+            .config
+            .roots()
+            .iter()
+            .map(|root| root.display().to_string())
+            .collect();
+        let tag_filter = match &self.tag_filter {
+            Some(tag) => format!(" [tag: {}]", tag),
+            None => String::new(),
+        };
+        let profile = match &self.config.profile {
+            Some(profile) => format!(" [profile: {}]", profile),
+            None => String::new(),
+        };
         Paragraph::new(format!(
-            "Current directory: {} (Selected: {})",
-            self.config.working_dir.display(),
-            self.selected_repos.len()
+            "Current directory: {} (Selected: {}){}{}",
+            roots.join(", "),
+            self.selected_repos.len(),
+            profile,
+            tag_filter
         ))
         .centered()
         .render(dir_info_area, buf);
+
+        if let Some(preview) = &self.diff_preview {
+            let area = popup_area(window, 80, window.height.saturating_sub(2));
+            Clear.render(area, buf);
+            Paragraph::new(preview.text.as_str())
+                .scroll((preview.scroll, 0))
+                .block(Block::bordered().title(format!(
+                    "Changes in {} (↑/↓/j/k: Scroll, p/Esc: Close)",
+                    preview.dir
+                )))
+                .render(area, buf);
+        }
+
+        if let Some(input) = &self.select_branch_popup {
+            let area = popup_area(window, 60, 3);
+            Clear.render(area, buf);
+            Paragraph::new(input.as_str())
+                .style(Style::default().bg(Color::Blue).fg(Color::White))
+                .block(
+                    Block::bordered()
+                        .title("Select the repos on branch (Enter: Select, Esc: Cancel)"),
+                )
+                .render(area, buf);
+        }
     }
 
     /// This screen allows the user to enter a title, description, and select labels for the merge request.
@@ -251,11 +1297,13 @@ impl App {
             title_input_area,
             description_input_area,
             label_input_area,
+            advanced_area,
         ] = Layout::vertical([
             Constraint::Min(3),
             Constraint::Length(3),
-            Constraint::Length(3),
+            Constraint::Length(6),
             Constraint::Length(5),
+            Constraint::Length(if self.show_advanced { 11 } else { 0 }),
         ])
         .areas(window);
 
@@ -287,17 +1335,20 @@ impl App {
             .block(Block::bordered().title("Title"))
             .render(title_input_area, buf);
 
-        Paragraph::new(self.mr_description.as_str())
-            .style(if self.input_focus == InputFocus::Description {
-                Style::default().bg(Color::Blue).fg(Color::White)
-            } else {
-                Style::default()
-            })
-            .block(Block::bordered().title("Description"))
-            .render(description_input_area, buf);
+        let description_focused = self.input_focus == InputFocus::Description;
+        let mut description_block = Block::bordered().title("Description");
+        if description_focused {
+            description_block =
+                description_block.style(Style::default().bg(Color::Blue).fg(Color::White));
+        }
+        self.mr_description.render(
+            description_input_area,
+            buf,
+            description_block,
+            description_focused,
+        );
 
         let label_items: Vec<ListItem> = self
-            .config
             .labels
             .iter()
             .enumerate()
@@ -307,7 +1358,11 @@ impl App {
                 } else {
                     "( )"
                 };
-                let mut item = ListItem::new(format!("{} {}: {}", marker, k, v));
+                let mut item = if v.is_empty() {
+                    ListItem::new(format!("{} {}", marker, k))
+                } else {
+                    ListItem::new(format!("{} {}: {}", marker, k, v))
+                };
                 if self.input_focus == InputFocus::Label && i == self.selected_label {
                     item = item.style(Style::default().fg(Color::Yellow).bg(Color::Blue));
                 } else if i == self.selected_label {
@@ -317,56 +1372,338 @@ impl App {
             })
             .collect();
 
-        List::new(label_items)
-            .block(Block::bordered().title("Gitlab Label"))
-            .render(label_input_area, buf);
+        let label_title = if self.label_fetch.is_some() {
+            "Gitlab Label (fetching project labels...)"
+        } else {
+            "Gitlab Label"
+        };
+        self.label_page_size = render_columns(
+            label_items,
+            self.selected_label,
+            Block::bordered().title(label_title),
+            label_input_area,
+            buf,
+        );
+
+        if self.show_advanced {
+            let items: Vec<ListItem> = self
+                .advanced
+                .lines()
+                .into_iter()
+                .map(|(focus, line)| {
+                    let mut item = ListItem::new(line);
+                    if self.input_focus == focus {
+                        item = item.style(Style::default().bg(Color::Blue).fg(Color::White));
+                    }
+                    item
+                })
+                .collect();
+            List::new(items)
+                .block(Block::bordered().title("Advanced options (Space: Toggle)"))
+                .render(advanced_area, buf);
+        }
     }
 
-    /// This screen allows the user to select reviewers for the merge request.
-    pub(crate) fn render_reviewer_selection(&mut self, window: Rect, buf: &mut Buffer) {
-        let [reviewer_area, assignee_area] =
-            Layout::vertical([Constraint::Min(1), Constraint::Min(1)]).areas(window);
+    /// Lists the merge request templates found in the selected repos, with a preview.
+    pub(crate) fn render_template(&mut self, window: Rect, buf: &mut Buffer) {
+        let [list_area, preview_area] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .areas(window);
 
-        let items: Vec<ListItem> = self
-            .config
-            .reviewers
-            .iter()
+        let repo_count = self.selected_repos.len();
+        let names = std::iter::once("No template".to_string()).chain(
+            self.templates
+                .iter()
+                .map(|(name, count)| format!("{} ({}/{} repos)", name, count, repo_count)),
+        );
+        let items: Vec<ListItem> = names
             .enumerate()
-            .map(|(i, r)| {
-                let line = if self.selected_reviewers.contains(&i) {
-                    format!("[x] {}", r)
-                } else {
-                    format!("[ ] {}", r)
-                };
-                let mut item = ListItem::new(line);
-                if i == self.reviewer_index {
+            .map(|(i, name)| {
+                let mut item = ListItem::new(name);
+                if i == self.template_index {
                     item = item.style(Style::default().fg(Color::Yellow).bg(Color::Blue));
                 }
                 item
             })
             .collect();
+        List::new(items)
+            .block(Block::bordered().title("Templates"))
+            .render(list_area, buf);
 
-        List::new(items).render(reviewer_area, buf);
-        if let Some(assignee) = &self.config.assignee {
-            Paragraph::new(format!("Assignee: {}", assignee))
-                .style(Style::default().fg(Color::Green))
-                .render(assignee_area, buf);
-        } else {
-            // If no assignee is set, show a placeholder
-            Paragraph::new("No assignee set")
-                .style(Style::default().fg(Color::Red))
+        let preview = self.highlighted_template().unwrap_or_default();
+        Paragraph::new(preview)
+            .wrap(Wrap { trim: false })
+            .block(Block::bordered().title("Preview"))
+            .render(preview_area, buf);
+
+        if let Some(form) = &self.template_form {
+            let lines: Vec<Line> = form
+                .fields
+                .iter()
+                .enumerate()
+                .map(|(i, (name, value))| {
+                    let line = Line::from(format!("{}: {}", name, value));
+                    if i == form.index {
+                        line.yellow().bold()
+                    } else {
+                        line
+                    }
+                })
+                .collect();
+            let area = popup_area(window, 70, form.fields.len() as u16 + 2);
+            Clear.render(area, buf);
+            Paragraph::new(lines)
+                .block(Block::bordered().title(format!(
+                    "Fill in {} (Tab/↑/↓: Switch, Enter: Next/Done, Esc: Cancel)",
+                    form.name
+                )))
+                .render(area, buf);
+        }
+    }
+
+    /// Find the templates of the selected repos, returns whether there are any.
+    pub(crate) fn scan_templates(&mut self) -> bool {
+        self.templates.clear();
+        for dir in self.selected_dirs() {
+            for name in templates::names(&self.config.working_dir.join(dir)) {
+                match self.templates.iter_mut().find(|(n, _)| *n == name) {
+                    Some((_, count)) => *count += 1,
+                    None => self.templates.push((name, 1)),
+                }
+            }
+        }
+        self.templates.sort();
+        self.template_index = self.template_index.min(self.templates.len());
+        !self.templates.is_empty()
+    }
+
+    /// Content of the highlighted template, from the first selected repo that has it.
+    fn highlighted_template(&self) -> Option<String> {
+        let (name, _) = self.templates.get(self.template_index.checked_sub(1)?)?;
+        self.selected_dirs()
+            .iter()
+            .find_map(|dir| templates::load(&self.config.working_dir.join(dir), name))
+    }
+
+    /// Fill the description with the highlighted template, or leave it as is for "no template",
+    /// and continue to describe the MR. A template with placeholders first asks for their values.
+    pub(crate) fn apply_template(&mut self) {
+        let Some(content) = self.highlighted_template() else {
+            self.template = None;
+            self.template_values.clear();
+            self.next_step();
+            return;
+        };
+        let (name, _) = &self.templates[self.template_index - 1];
+        let name = name.clone();
+        // Every repo has its own version of the template, ask for the placeholders of all
+        let mut placeholders: Vec<String> = Vec::new();
+        for dir in self.selected_dirs() {
+            let repo_dir = self.config.working_dir.join(dir);
+            for placeholder in templates::load(&repo_dir, &name)
+                .map(|c| templates::placeholders(&c))
+                .unwrap_or_default()
+            {
+                if !placeholders.contains(&placeholder) {
+                    placeholders.push(placeholder);
+                }
+            }
+        }
+        if placeholders.is_empty() {
+            self.use_template(name, content, HashMap::new());
+            self.next_step();
+            return;
+        }
+        self.template_form = Some(TemplateForm {
+            name,
+            content,
+            fields: placeholders
+                .into_iter()
+                .map(|p| {
+                    let value = self.template_values.get(&p).cloned().unwrap_or_default();
+                    (p, value)
+                })
+                .collect(),
+            index: 0,
+        });
+    }
+
+    /// Fill the description with the template filled in with `values`.
+    pub(crate) fn use_template(
+        &mut self,
+        name: String,
+        content: String,
+        values: HashMap<String, String>,
+    ) {
+        let filled = templates::fill(&content, &values);
+        self.template = Some((name, filled.clone()));
+        self.template_values = values;
+        self.mr_description.set_text(filled);
+    }
+
+    /// Fill in the template of the form with the values typed, and continue to describe the MR.
+    pub(crate) fn submit_template_form(&mut self) {
+        let Some(form) = self.template_form.take() else {
+            return;
+        };
+        let values = form.fields.into_iter().collect();
+        self.use_template(form.name, form.content, values);
+        self.next_step();
+    }
+
+    /// This screen allows the user to select reviewers for the merge request.
+    pub(crate) fn render_reviewer_selection(&mut self, window: Rect, buf: &mut Buffer) {
+        let [reviewer_area, input_area, assignee_area] = Layout::vertical([
+            Constraint::Min(1),
+            Constraint::Length(if self.reviewer_input.is_some() { 1 } else { 0 }),
+            Constraint::Min(1),
+        ])
+        .areas(window);
+
+        if let Some(input) = &self.reviewer_input {
+            let mut line = Line::from(vec!["Add reviewer: ".bold(), input.as_str().into()]);
+            // The rest of the completion, taken with Tab
+            if let Some(completion) = self.reviewer_completion() {
+                let typed = input.trim_start_matches('@').len();
+                line.push_span(
+                    completion
+                        .get(typed..)
+                        .unwrap_or_default()
+                        .to_string()
+                        .dark_gray(),
+                );
+            }
+            Paragraph::new(line)
+                .style(Style::default().fg(Color::Yellow))
+                .render(input_area, buf);
+        }
+
+        let last_time = self.last_reviewers_of_selection();
+        let items: Vec<ListItem> = self
+            .config
+            .reviewers
+            .iter()
+            .enumerate()
+            .map(|(i, r)| {
+                let marker = if self.selected_reviewers.contains(&i) {
+                    "[x]"
+                } else {
+                    "[ ]"
+                };
+                // Reviewers missing from a known load have nothing to review
+                let text = if self.review_load.is_empty() {
+                    format!("{} {}", marker, r)
+                } else {
+                    let count = self.review_load.get(r).copied().unwrap_or_default();
+                    format!("{} {} ({} open reviews)", marker, r, count)
+                };
+                let mut line = Line::from(text);
+                if last_time.contains(r) {
+                    line.push_span("  picked last time".dark_gray());
+                }
+                let mut item = ListItem::new(line);
+                if i == self.reviewer_index && !self.assignee_focus {
+                    item = item.style(Style::default().fg(Color::Yellow).bg(Color::Blue));
+                }
+                item
+            })
+            .collect();
+
+        let list_block = if self.reviewer_fetch.is_some() {
+            Block::default().title_bottom(Line::from("Fetching project members...").dark_gray())
+        } else if self.review_load_fetch.is_some() {
+            Block::default().title_bottom(Line::from("Fetching review load...").dark_gray())
+        } else {
+            Block::default()
+        };
+        self.reviewer_page_size =
+            render_columns(items, self.reviewer_index, list_block, reviewer_area, buf);
+        if self.config.assignee_mode == AssigneeMode::Maintainer {
+            Paragraph::new("Assignee: first maintainer of each repository")
+                .style(Style::default().fg(Color::Green))
                 .render(assignee_area, buf);
+        } else {
+            let items: Vec<ListItem> = self
+                .assignee_candidates()
+                .iter()
+                .enumerate()
+                .map(|(i, username)| {
+                    let marker = if self.selected_assignees.contains(username) {
+                        "[x]"
+                    } else {
+                        "[ ]"
+                    };
+                    let line = if self.config.assignee.as_ref() == Some(username) {
+                        let source = if self.assignee_detected {
+                            ", logged in to glab"
+                        } else {
+                            ""
+                        };
+                        format!("{} me ({}{})", marker, username, source)
+                    } else {
+                        format!("{} {}", marker, username)
+                    };
+                    let mut item = ListItem::new(line);
+                    if i == self.assignee_index && self.assignee_focus {
+                        item = item.style(Style::default().fg(Color::Yellow).bg(Color::Blue));
+                    }
+                    item
+                })
+                .collect();
+            let title = if self.user_fetch.is_some() {
+                Line::from("Assignees (Tab to switch): looking up your GitLab user...").dark_gray()
+            } else if self.selected_assignees.is_empty() {
+                Line::from(format!(
+                    "{}Assignees (Tab to switch): none",
+                    mark(self.config.colorblind, "⚠")
+                ))
+                .red()
+            } else {
+                Line::from("Assignees (Tab to switch)").green()
+            };
+            self.assignee_page_size = render_columns(
+                items,
+                self.assignee_index,
+                Block::default().title(title),
+                assignee_area,
+                buf,
+            );
+        }
+    }
+
+    /// Who can be picked as assignee: the configured assignee as "me", the other
+    /// assignees given on the command line, then the reviewers.
+    pub(crate) fn assignee_candidates(&self) -> Vec<String> {
+        let mut candidates: Vec<String> = Vec::new();
+        for username in self
+            .config
+            .assignee
+            .iter()
+            .chain(&self.config.assignees)
+            .chain(&self.config.reviewers)
+        {
+            if !candidates.contains(username) {
+                candidates.push(username.clone());
+            }
+        }
+        candidates
+    }
+
+    /// Assign the highlighted candidate, or unassign them if they already are.
+    pub(crate) fn toggle_assignee(&mut self) {
+        let Some(username) = self.assignee_candidates().get(self.assignee_index).cloned() else {
+            return;
+        };
+        if let Some(position) = self.selected_assignees.iter().position(|a| *a == username) {
+            self.selected_assignees.remove(position);
+        } else {
+            self.selected_assignees.push(username);
         }
     }
 
     /// This screen shows an overview of selected configuration and prompts the user one final time.
     pub(crate) fn render_overview(&mut self, window: Rect, buf: &mut Buffer) {
-        let selected_dirs: Vec<&String> = self
-            .selected_repos
-            .iter()
-            .copied()
-            .filter_map(|i| self.dirs.get(i))
-            .collect();
+        let selected_dirs = self.selected_dirs();
         let selected_reviewers: Vec<&String> = self
             .selected_reviewers
             .iter()
@@ -387,28 +1724,2484 @@ impl App {
         let reviewers_text = if selected_reviewers.is_empty() {
             "No reviewers selected".to_string()
         } else {
+            let strategy = match self.config.reviewer_strategy {
+                ReviewerStrategy::All => "",
+                ReviewerStrategy::RoundRobin => " (one per MR, in turn)",
+                ReviewerStrategy::Random => " (one per MR, shuffled)",
+            };
             selected_reviewers
                 .iter()
                 .map(|s| s.as_str())
                 .collect::<Vec<_>>()
                 .join(", ")
+                + strategy
+        };
+
+        let assignees_text = if self.config.assignee_mode == AssigneeMode::Maintainer {
+            "first maintainer of each repository".to_string()
+        } else if self.selected_assignees.is_empty() {
+            "No assignees selected".to_string()
+        } else {
+            self.selected_assignees.join(", ")
+        };
+
+        let merge_options: Vec<&str> = [
+            (self.advanced.squash, "squash"),
+            (self.advanced.remove_source_branch, "delete source branch"),
+            (self.advanced.auto_merge, "when pipeline succeeds"),
+        ]
+        .into_iter()
+        .filter_map(|(on, name)| on.then_some(name))
+        .collect();
+        let merge_text = if merge_options.is_empty() {
+            "manually".to_string()
+        } else {
+            merge_options.join(", ")
         };
 
-        let [overview_area] = Layout::vertical([Constraint::Min(1)]).areas(window);
+        let warnings = self.warnings();
+        let [
+            overview_area,
+            advanced_area,
+            warnings_area,
+            target_branch_area,
+        ] = Layout::vertical([
+            Constraint::Length(11),
+            Constraint::Length(if self.show_advanced { 11 } else { 0 }),
+            Constraint::Length(if warnings.is_empty() {
+                0
+            } else {
+                warnings.len().min(8) as u16 + 2
+            }),
+            Constraint::Min(3),
+        ])
+        .areas(window);
 
         Paragraph::new(format!(
-            "Overview\n\nRepositories: {}\nTitle: {}\nDescription: {}\nReviewers: {}\n\nPress 'y' to confirm, 'n' to go back.",
-            dirs_text, self.mr_title, self.mr_description, reviewers_text
-        )).render(overview_area, buf);
+            "Overview\n\nRepositories: {}\nTitle: {}\nBranch: {}\nDescription: {}\nReviewers: {}\nAssignees: {}\nMerge: {}\n\n{}",
+            dirs_text,
+            self.mr_title,
+            self.branch_name(),
+            description_summary(self.mr_description.text()),
+            reviewers_text,
+            assignees_text,
+            merge_text,
+            self.confirm_hint()
+        ))
+        .render(overview_area, buf);
+
+        if self.show_advanced {
+            let lines: Vec<Line> = self
+                .advanced
+                .lines()
+                .into_iter()
+                .map(|(_, line)| Line::from(line))
+                .collect();
+            Paragraph::new(lines)
+                .block(Block::bordered().title("Advanced options"))
+                .render(advanced_area, buf);
+        }
+
+        if !warnings.is_empty() {
+            let lines: Vec<Line> = warnings
+                .iter()
+                .map(|warning| {
+                    let symbol = match warning.severity {
+                        Severity::Blocker => "✗",
+                        Severity::Warning => "⚠",
+                        Severity::Info => "●",
+                    };
+                    let line = Line::from(format!(
+                        "{}{}",
+                        mark(self.config.colorblind, symbol),
+                        warning
+                    ));
+                    match warning.severity {
+                        Severity::Blocker => line.red().bold(),
+                        Severity::Warning => line.yellow(),
+                        Severity::Info => line,
+                    }
+                })
+                .collect();
+            Paragraph::new(lines)
+                .block(Block::bordered().title("Checks"))
+                .render(warnings_area, buf);
+        }
+
+        let target_items: Vec<ListItem> = selected_dirs
+            .iter()
+            .enumerate()
+            .map(|(i, dir)| {
+                let overrides = self.config.repo_override(dir);
+                let target_branch = overrides
+                    .and_then(|o| o.target_branch.as_deref())
+                    .or(Some(self.advanced.target_branch.as_str()).filter(|b| !b.is_empty()))
+                    .unwrap_or("(default)");
+                let skip_ci = if overrides.and_then(|o| o.skip_ci).unwrap_or_default() {
+                    " [skip ci]"
+                } else {
+                    ""
+                };
+                let no_checkout = if overrides.and_then(|o| o.no_checkout).unwrap_or_default() {
+                    " [no checkout]"
+                } else {
+                    ""
+                };
+                let skipped = if self.skipped_dirs.contains(dir) {
+                    " [skipped]"
+                } else {
+                    ""
+                };
+                let mut item = ListItem::new(format!(
+                    "{} -> {}{}{}{}",
+                    dir, target_branch, skip_ci, no_checkout, skipped
+                ));
+                if i == self.finalize_index {
+                    item = item.style(Style::default().fg(Color::Yellow).bg(Color::Blue));
+                }
+                item
+            })
+            .collect();
+
+        List::new(target_items)
+            .block(Block::bordered().title("Target Branches"))
+            .render(target_branch_area, buf);
+
+        if let Some(input) = &self.target_branch_popup {
+            let dir = selected_dirs
+                .get(self.finalize_index)
+                .map(|d| d.as_str())
+                .unwrap_or_default();
+            let area = popup_area(window, 60, 3);
+            Clear.render(area, buf);
+            Paragraph::new(input.as_str())
+                .style(Style::default().bg(Color::Blue).fg(Color::White))
+                .block(Block::bordered().title(format!(
+                    "Target branch for {} (Enter: Save, Esc: Cancel)",
+                    dir
+                )))
+                .render(area, buf);
+        }
+
+        if let Some(input) = &self.branch_popup {
+            let area = popup_area(window, 60, 3);
+            Clear.render(area, buf);
+            Paragraph::new(input.as_str())
+                .style(Style::default().bg(Color::Blue).fg(Color::White))
+                .block(Block::bordered().title(
+                    "Branch name, empty to derive it from the title (Enter: Save, Esc: Cancel)",
+                ))
+                .render(area, buf);
+        }
+
+        if let Some(input) = &self.script_popup {
+            let area = popup_area(window, 60, 3);
+            Clear.render(area, buf);
+            Paragraph::new(input.as_str())
+                .style(Style::default().bg(Color::Blue).fg(Color::White))
+                .block(
+                    Block::bordered()
+                        .title("Write the commands to this script (Enter: Write, Esc: Cancel)"),
+                )
+                .render(area, buf);
+        }
+
+        if let Some(input) = &self.confirm_popup {
+            let area = popup_area(window, 60, 3);
+            Clear.render(area, buf);
+            Paragraph::new(input.as_str())
+                .style(Style::default().bg(Color::Red).fg(Color::White))
+                .block(Block::bordered().title(format!(
+                    "Type {} or 'yes' to create {} merge requests (Enter: Confirm, Esc: Cancel)",
+                    self.batch_dirs().len(),
+                    self.batch_dirs().len()
+                )))
+                .render(area, buf);
+        }
     }
 
-    /// Set running to false to quit the application.
-    pub(crate) fn quit(&mut self) {
-        self.running = false;
+    /// How to confirm the batch from the Finalize screen, or why it can't be confirmed yet.
+    fn confirm_hint(&self) -> &'static str {
+        if !self.is_armed() {
+            "Take a moment to review..."
+        } else if self.is_blocked() {
+            "Resolve the blockers below, or start with --force to confirm anyway. 'n' to go back."
+        } else if self.needs_typed_confirmation() {
+            "Press 'y' and type the number of repositories or 'yes' to confirm, 'n' to go back."
+        } else {
+            "Press 'y' to confirm, 'n' to go back."
+        }
     }
 
-    pub(crate) fn quit_completed(&mut self) {
-        self.user_input_completed = true;
-        self.running = false;
+    /// Show the Finalize screen, confirming only becomes possible after [`ARMING_DELAY`].
+    pub(crate) fn show_finalize(&mut self) {
+        self.screen = Screens::Finalize;
+        self.finalize_shown_at = Some(Instant::now());
+        self.inspect_changes();
+        self.start_reachability_check();
+        self.start_maintainer_fetch();
+    }
+
+    /// Look up the first maintainer of the repos whose merge request is assigned to one, so
+    /// the plan shows who it is before confirming.
+    pub(crate) fn start_maintainer_fetch(&mut self) {
+        if self.config.offline
+            || self.config.tour
+            || !self.has_checkouts()
+            || self.maintainer_fetch.is_some()
+        {
+            return;
+        }
+        let mr = self.build_merge_request();
+        let repos: Vec<(String, String, PathBuf)> = self
+            .batch_dirs()
+            .into_iter()
+            .filter(|dir| !self.maintainers.contains_key(dir))
+            .filter(|dir| mr.for_dir(&self.config, dir).assign_maintainer)
+            .map(|dir| {
+                let glab = self.config.glab_for(&dir).to_string();
+                let repo_dir = self.config.working_dir.join(&dir);
+                (dir, glab, repo_dir)
+            })
+            .collect();
+        if repos.is_empty() {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            for (dir, glab, repo_dir) in repos {
+                if let Ok(Some(maintainer)) = gitlab::first_maintainer(&glab, &repo_dir) {
+                    let _ = tx.send((dir, maintainer));
+                }
+            }
+        });
+        self.maintainer_fetch = Some(rx);
+    }
+
+    /// Ask every GitLab host of the batch for its version through glab in the background,
+    /// so a host that can't be reached is warned about before confirming.
+    pub(crate) fn start_reachability_check(&mut self) {
+        if self.config.dry_run || self.config.offline || self.config.tour || !self.has_checkouts() {
+            return;
+        }
+        self.unreachable_hosts.clear();
+        let mut hosts: Vec<(String, String)> = Vec::new();
+        for dir in self.batch_dirs() {
+            let host = gitlab::project_from_remote(&self.config.working_dir.join(&dir))
+                .map(|project| project.host)
+                .unwrap_or_default();
+            let glab = self.config.glab_for(&dir).to_string();
+            if !hosts.contains(&(glab.clone(), host.clone())) {
+                hosts.push((glab, host));
+            }
+        }
+        let (tx, rx) = mpsc::channel();
+        for (glab, host) in hosts {
+            let tx = tx.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = gitlab::probe(&glab, &host) {
+                    let _ = tx.send((host, e.to_string()));
+                }
+            });
+        }
+        self.reachability_check = Some(rx);
+    }
+
+    /// Count the lines each selected repo is about to commit, for the diff size check, and
+    /// look for suspicious paths among the changes committed on a new branch. The diffs are
+    /// made in the background, the Finalize screen arms once they are done.
+    pub(crate) fn inspect_changes(&mut self) {
+        self.changed_lines.clear();
+        self.suspicious.clear();
+        // An inspection of an earlier selection is stopped by dropping its receiver
+        self.inspection = None;
+        if !self.has_checkouts() {
+            return;
+        }
+        let repos: Vec<(String, PathBuf, bool)> = self
+            .selected_dirs()
+            .into_iter()
+            .map(|dir| {
+                let repo_dir = self.config.working_dir.join(&dir);
+                let on_default_branch = self.is_on_default_branch(&dir);
+                (dir, repo_dir, on_default_branch)
+            })
+            .collect();
+        let patterns = self.config.suspicious_paths.clone();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            for (dir, repo_dir, on_default_branch) in repos {
+                let lines = utils::changed_lines(&repo_dir).ok();
+                let paths = if on_default_branch {
+                    utils::suspicious_paths(&repo_dir, &patterns).unwrap_or_default()
+                } else {
+                    Vec::new()
+                };
+                if tx.send((dir, lines, paths)).is_err() {
+                    return;
+                }
+            }
+        });
+        self.inspection = Some(rx);
+    }
+
+    /// Leave the suspicious paths of `dir` out of its commit, or take them in again if they
+    /// all are left out already.
+    pub(crate) fn toggle_excluded_suspicious(&mut self, dir: &str) {
+        let Some(paths) = self.suspicious.get(dir) else {
+            return;
+        };
+        let overrides = self
+            .config
+            .repo_overrides
+            .entry(dir.to_string())
+            .or_default();
+        if paths.iter().all(|p| overrides.exclude_paths.contains(p)) {
+            overrides.exclude_paths.retain(|p| !paths.contains(p));
+        } else {
+            for path in paths {
+                if !overrides.exclude_paths.contains(path) {
+                    overrides.exclude_paths.push(path.clone());
+                }
+            }
+        }
+    }
+
+    /// Whether the Finalize screen has been shown long enough to confirm the batch.
+    pub(crate) fn is_armed(&self) -> bool {
+        self.inspection.is_none()
+            && self
+                .finalize_shown_at
+                .is_some_and(|shown_at| shown_at.elapsed() >= ARMING_DELAY)
+    }
+
+    /// Whether the batch is large enough to require typing the confirmation.
+    pub(crate) fn needs_typed_confirmation(&self) -> bool {
+        let threshold = self.config.confirm_threshold;
+        threshold > 0 && self.batch_dirs().len() > threshold
+    }
+
+    /// Whether the typed confirmation is the number of selected repos or "yes".
+    pub(crate) fn is_confirmation(&self, input: &str) -> bool {
+        let input = input.trim();
+        input.eq_ignore_ascii_case("yes") || input == self.batch_dirs().len().to_string()
+    }
+
+    /// The findings of every pre-flight check on the batch, most severe first.
+    pub(crate) fn warnings(&self) -> Vec<Warning> {
+        let mut found = Vec::new();
+        let selected_dirs = self.selected_dirs();
+        if selected_dirs.is_empty() {
+            found.push(Warning::new(
+                Severity::Blocker,
+                None,
+                "no repositories selected",
+            ));
+        } else if self.batch_dirs().is_empty() {
+            found.push(Warning::new(
+                Severity::Blocker,
+                None,
+                "every selected repository is skipped",
+            ));
+        }
+        if self.mr_title.trim().is_empty() {
+            found.push(Warning::new(
+                Severity::Blocker,
+                None,
+                "the merge request has no title",
+            ));
+        }
+
+        for dir in &selected_dirs {
+            if self.skipped_dirs.contains(dir) {
+                found.push(Warning::new(Severity::Info, Some(dir), "skipped"));
+                continue;
+            }
+            match self.uncommitted.get(dir) {
+                Some(Uncommitted::Commit) => found.push(Warning::new(
+                    Severity::Info,
+                    Some(dir),
+                    "uncommitted changes are committed into the merge request",
+                )),
+                Some(Uncommitted::Stash) => found.push(Warning::new(
+                    Severity::Info,
+                    Some(dir),
+                    "uncommitted changes are stashed while the merge request is created",
+                )),
+                None => {}
+            }
+            if let Some(problem) = self
+                .dirs
+                .iter()
+                .position(|d| d == dir)
+                .and_then(|i| self.repo_problem(i))
+            {
+                found.push(Warning::new(
+                    Severity::Warning,
+                    Some(dir),
+                    format!("{}, included anyway", problem),
+                ));
+            }
+            for problem in self.validation_problems.get(dir).into_iter().flatten() {
+                found.push(Warning::new(Severity::Warning, Some(dir), problem.clone()));
+            }
+            for result in self.preflight.get(dir).into_iter().flatten() {
+                if let Some(failure) = result.failure() {
+                    found.push(Warning::new(Severity::Blocker, Some(dir), failure));
+                }
+            }
+            if self.config.update {
+                // Nothing is committed, the branch only has to have a merge request
+                let has_source_branch = self
+                    .config
+                    .repo_override(dir)
+                    .is_some_and(|o| o.source_branch.is_some());
+                if self.is_on_default_branch(dir) && !has_source_branch {
+                    found.push(Warning::new(
+                        Severity::Warning,
+                        Some(dir),
+                        format!(
+                            "on {}, which has no merge request to update",
+                            self.branch_of(dir)
+                        ),
+                    ));
+                }
+                continue;
+            }
+            if self.config.no_git {
+                // Nothing is committed, so only the branch matters
+                let branch = self.branch_of(dir);
+                let has_source_branch = self
+                    .config
+                    .repo_override(dir)
+                    .is_some_and(|o| o.source_branch.is_some());
+                if self.is_on_default_branch(dir) && !has_source_branch {
+                    found.push(Warning::new(
+                        Severity::Warning,
+                        Some(dir),
+                        format!("on {}, fails since --no-git creates no branch", branch),
+                    ));
+                }
+                continue;
+            }
+            if let Some(paths) = self.suspicious.get(dir) {
+                let excluded = self
+                    .config
+                    .repo_override(dir)
+                    .map_or(&[][..], |o| o.exclude_paths.as_slice());
+                let included: Vec<&str> = paths
+                    .iter()
+                    .filter(|p| !excluded.contains(p))
+                    .map(String::as_str)
+                    .collect();
+                if included.is_empty() {
+                    found.push(Warning::new(
+                        Severity::Info,
+                        Some(dir),
+                        format!("leaves out {}", paths.join(", ")),
+                    ));
+                } else {
+                    found.push(Warning::new(
+                        Severity::Warning,
+                        Some(dir),
+                        format!(
+                            "would commit {}, exclude with x on the Plan screen (d)",
+                            included.join(", ")
+                        ),
+                    ));
+                }
+            }
+            if self
+                .dirs
+                .iter()
+                .position(|d| d == dir)
+                .is_some_and(|i| self.has_nothing_to_commit(i))
+            {
+                found.push(Warning::new(
+                    Severity::Warning,
+                    Some(dir),
+                    "no changes to commit on the default branch",
+                ));
+            }
+        }
+
+        let max_lines = self.config.max_changed_lines;
+        let sizes: Vec<(&String, usize)> = self
+            .batch_dirs()
+            .into_iter()
+            .filter_map(|dir| self.changed_lines.get_key_value(&dir))
+            .map(|(dir, &lines)| (dir, lines))
+            .collect();
+        let total: usize = sizes.iter().map(|(_, lines)| lines).sum();
+        if max_lines > 0
+            && !self.config.update
+            && total > max_lines
+            && let Some((largest, lines)) = sizes.iter().max_by_key(|(_, lines)| *lines)
+        {
+            found.push(Warning::new(
+                Severity::Warning,
+                None,
+                format!(
+                    "{} changed lines to commit, more than max_changed_lines ({}); the most in {} ({}), vendored files or build artifacts?",
+                    total, max_lines, largest, lines
+                ),
+            ));
+        }
+
+        if self.preflight_run.is_some() {
+            found.push(Warning::new(
+                Severity::Blocker,
+                None,
+                "the preflight checks are still running",
+            ));
+        }
+        if self.config.dry_run {
+            found.push(Warning::new(
+                Severity::Info,
+                None,
+                "dry run, no merge requests are created",
+            ));
+        } else if let Some(approved) = &self.dry_run_plan {
+            if self.scan.is_some() {
+                found.push(Warning::new(
+                    Severity::Blocker,
+                    None,
+                    "still scanning the repositories to compare with the dry run",
+                ));
+            } else {
+                let divergences = plan::divergences(approved, &self.current_plan());
+                if divergences.is_empty() {
+                    found.push(Warning::new(
+                        Severity::Info,
+                        None,
+                        "the same commands run as in the dry run",
+                    ));
+                }
+                for (dir, message) in divergences {
+                    found.push(Warning::new(Severity::Warning, Some(&dir), message));
+                }
+            }
+        }
+        let mut unreachable: Vec<_> = self.unreachable_hosts.iter().collect();
+        unreachable.sort();
+        for (host, e) in unreachable {
+            let host = if host.is_empty() {
+                "GitLab"
+            } else {
+                host.as_str()
+            };
+            found.push(Warning::new(
+                Severity::Warning,
+                None,
+                format!(
+                    "{} is not reachable through glab ({}), the merge requests are queued for `multimr flush` if it still isn't when they are created",
+                    host,
+                    e.trim()
+                ),
+            ));
+        }
+        if self.config.offline {
+            found.push(Warning::new(
+                Severity::Info,
+                None,
+                "offline, pushing and creating the merge requests is queued",
+            ));
+        }
+        if self.config.update {
+            found.push(Warning::new(
+                Severity::Info,
+                None,
+                "--update, the open merge requests of the current branches are updated instead of created",
+            ));
+        }
+        if self.config.no_git {
+            found.push(Warning::new(
+                Severity::Info,
+                None,
+                "--no-git, nothing is committed or pushed, only existing branches get a merge request",
+            ));
+        }
+        if self.config.force && warnings::blockers(&found) > 0 {
+            found.push(Warning::new(
+                Severity::Info,
+                None,
+                "--force given, blockers don't prevent confirming",
+            ));
+        }
+        warnings::sorted(found)
+    }
+
+    /// Whether a blocker prevents confirming the batch, `--force` overrides them.
+    pub(crate) fn is_blocked(&self) -> bool {
+        !self.config.force && warnings::blockers(&self.warnings()) > 0
+    }
+
+    /// Start the batch if it has been confirmed deliberately, asking to type the
+    /// confirmation for large batches.
+    pub(crate) fn confirm_execution(&mut self) {
+        if !self.is_armed() {
+            self.toasts
+                .push(ToastLevel::Info, "Review the batch before confirming");
+        } else if self.is_blocked() {
+            self.toasts.push(
+                ToastLevel::Error,
+                "Resolve the blockers first, or start with --force",
+            );
+        } else if self.needs_typed_confirmation() {
+            self.confirm_popup = Some(String::new());
+        } else {
+            self.start_execution();
+        }
+    }
+
+    /// Shows the exact commands that will run in each selected repo, without running them.
+    pub(crate) fn render_plan(&mut self, window: Rect, buf: &mut Buffer) {
+        let [repo_area, command_area] =
+            Layout::horizontal([Constraint::Percentage(30), Constraint::Percentage(70)])
+                .areas(window);
+
+        let selected_dirs = self.selected_dirs();
+        let items: Vec<ListItem> = selected_dirs
+            .iter()
+            .enumerate()
+            .map(|(i, dir)| {
+                let skipped = self.skipped_dirs.contains(dir);
+                let size = self
+                    .changed_lines
+                    .get(dir)
+                    .map(|lines| format!(", {} lines", lines))
+                    .unwrap_or_default();
+                let mut item = ListItem::new(format!(
+                    "{}{} ({}{})",
+                    if skipped { "[skip] " } else { "" },
+                    dir,
+                    self.branch_of(dir),
+                    size
+                ));
+                if skipped {
+                    item = item.dark_gray();
+                }
+                if i == self.finalize_index {
+                    item = item.style(Style::default().fg(Color::Yellow).bg(Color::Blue));
+                }
+                item
+            })
+            .collect();
+        List::new(items)
+            .block(Block::bordered().title("Repositories"))
+            .render(repo_area, buf);
+
+        let lines: Vec<Line> = match selected_dirs.get(self.finalize_index) {
+            Some(dir) if self.skipped_dirs.contains(dir) => {
+                vec![Line::from("Skipped, nothing runs in this repository")]
+            }
+            Some(dir) => self
+                .planned_commands(dir)
+                .into_iter()
+                .map(|c| Line::from(format!("$ {}", c)))
+                .collect(),
+            None => Vec::new(),
+        };
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(Block::bordered().title("Commands"))
+            .render(command_area, buf);
+    }
+
+    /// The branch `dir` was on when it was scanned.
+    pub(crate) fn branch_of(&self, dir: &str) -> &str {
+        self.dirs
+            .iter()
+            .position(|d| d == dir)
+            .and_then(|i| self.branches.get(i))
+            .map(|b| b.as_str())
+            .unwrap_or_default()
+    }
+
+    /// The default branch of `dir` found while scanning, `None` when it wasn't looked up.
+    pub(crate) fn default_branch_of(&self, dir: &str) -> Option<&str> {
+        self.default_branches.get(dir).map(|b| b.as_str())
+    }
+
+    /// Whether `dir` was on its default branch when it was scanned.
+    pub(crate) fn is_on_default_branch(&self, dir: &str) -> bool {
+        branch_detection::is_default(self.branch_of(dir), self.default_branch_of(dir))
+    }
+
+    /// The commands that would run in `dir` to create the merge request, as shell lines.
+    /// A first maintainer that isn't known is explained in a comment instead.
+    pub(crate) fn planned_commands(&self, dir: &str) -> Vec<String> {
+        let mut mr = self.build_merge_request().for_dir(&self.config, dir);
+        mr.default_branch = self.default_branch_of(dir).map(str::to_string);
+        let mut commands = Vec::new();
+        if mr.assign_maintainer && mr.maintainer.is_none() {
+            commands.push(
+                "# No --assignee: the first maintainer of the project isn't known, multimr looks it up when creating the merge request"
+                    .to_string(),
+            );
+        }
+        commands.extend(
+            mr.plan(self.branch_of(dir))
+                .iter()
+                .map(|step| utils::shell_line(&step.cmd)),
+        );
+        commands
+    }
+
+    /// The planned commands of `dirs` as a shell script.
+    pub(crate) fn plan_script(&self, dirs: &[String]) -> String {
+        let repos: Vec<script::RepoScript> = dirs
+            .iter()
+            .map(|dir| {
+                let repo_dir = self.config.working_dir.join(dir);
+                let mut notes = vec![format!("Currently on branch '{}'", self.branch_of(dir))];
+                if self.skipped_dirs.contains(dir) {
+                    notes.push("Skipped on the Plan screen".to_string());
+                }
+                script::RepoScript {
+                    name: dir.clone(),
+                    dir: repo_dir.is_dir().then_some(repo_dir),
+                    notes,
+                    commands: self.planned_commands(dir),
+                }
+            })
+            .collect();
+        script::render(&self.mr_title, &repos)
+    }
+
+    /// The batch with every repo's merge request resolved, to be run later by `multimr apply`.
+    pub(crate) fn resolved_plan(&self) -> Plan {
+        let mr = self.build_merge_request();
+        Plan {
+            title: self.mr_title.clone(),
+            restore_branch: self.config.restore_branch,
+            post_create: self.config.post_create.clone(),
+            repos: self
+                .batch_dirs()
+                .into_iter()
+                .map(|dir| {
+                    let merge_request = mr.for_dir(&self.config, &dir);
+                    let branch = if merge_request.no_checkout {
+                        String::new()
+                    } else {
+                        self.branch_of(&dir).to_string()
+                    };
+                    PlannedRepo {
+                        repo_dir: self.config.working_dir.join(&dir),
+                        commands: self.planned_commands(&dir),
+                        dir,
+                        branch,
+                        merge_request,
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    /// Write the planned commands of the batch to an executable script at `path`,
+    /// staying on the Finalize screen so the batch can still be run from here.
+    pub(crate) fn export_script(&mut self, path: &Path) {
+        let dirs = self.batch_dirs();
+        match script::write(path, &self.plan_script(&dirs)) {
+            Ok(()) => self.toasts.push(
+                ToastLevel::Info,
+                format!(
+                    "Wrote the commands of {} repositories to {}",
+                    dirs.len(),
+                    path.display()
+                ),
+            ),
+            Err(e) => self.show_error(format!("Cannot write {}: {}", path.display(), e)),
+        }
+    }
+
+    /// Copy the plan of the highlighted repo, or of the whole batch, as a shell script.
+    pub(crate) fn copy_plan(&mut self, all: bool) {
+        let dirs = if all {
+            self.batch_dirs()
+        } else {
+            self.selected_dirs()
+                .get(self.finalize_index)
+                .cloned()
+                .into_iter()
+                .collect()
+        };
+        match self.clipboard.copy(self.plan_script(&dirs)) {
+            Ok(()) => self.toasts.push(
+                ToastLevel::Info,
+                format!("Copied the script of {} repositories", dirs.len()),
+            ),
+            Err(e) => self
+                .toasts
+                .push(ToastLevel::Error, format!("Clipboard unavailable: {}", e)),
+        }
+    }
+
+    /// Shows the progress of the batch, and the output of the highlighted repo.
+    pub(crate) fn render_execution(&mut self, window: Rect, buf: &mut Buffer) {
+        let Some(execution) = &self.execution else {
+            return;
+        };
+
+        let [status_area, repo_area, log_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(3),
+            Constraint::Percentage(50),
+        ])
+        .areas(window);
+
+        let status = if execution.is_finished() && self.config.dry_run {
+            Line::from("Dry run done. Press R to run it for real, q or Enter to exit.").green()
+        } else if execution.is_finished() {
+            Line::from("Done. Press q or Enter to exit.").green()
+        } else if execution.awaiting_canary() {
+            let url = execution.repos[0]
+                .url
+                .as_deref()
+                .unwrap_or("(no URL found)");
+            Line::from(format!(
+                "Canary MR: {}  Press y to continue with {} more, q to stop.",
+                url,
+                execution.repos.len() - 1
+            ))
+            .yellow()
+        } else if execution.is_paused() {
+            Line::from(format!(
+                "Paused after {}/{} repositories. Press p to resume.",
+                execution.completed(),
+                execution.repos.len()
+            ))
+            .yellow()
+        } else {
+            Line::from(format!(
+                "Running {}/{}... Press p to pause after the current repository.",
+                execution.completed(),
+                execution.repos.len()
+            ))
+        };
+        Paragraph::new(status).render(status_area, buf);
+
+        let items: Vec<ListItem> = execution
+            .repos
+            .iter()
+            .enumerate()
+            .map(|(i, repo)| {
+                let (marker, color) = match repo.state {
+                    RepoState::Pending => ("[ ]", Color::Reset),
+                    RepoState::Running if self.config.colorblind => ("[●]", Color::Yellow),
+                    RepoState::Running => ("[~]", Color::Yellow),
+                    RepoState::Succeeded => ("[✓]", Color::Green),
+                    RepoState::Failed => ("[✗]", Color::Red),
+                    RepoState::Skipped => ("[-]", Color::DarkGray),
+                    RepoState::Queued => ("[…]", Color::Cyan),
+                };
+                let mut text = format!("{} {}", marker, repo.dir);
+                if repo.attempts > 1 {
+                    text.push_str(&format!(" (attempt {})", repo.attempts));
+                }
+                if let Some(iid) = repo.iid {
+                    text.push_str(&format!("  !{}", iid));
+                }
+                if let Some(url) = &repo.url {
+                    text.push_str(&format!("  {}", url));
+                } else if let Some(error) = &repo.error {
+                    text.push_str(&format!("  {}", error));
+                }
+                if let Some(confirmed) = &repo.confirmed_reviewers
+                    && !confirmed.is_empty()
+                {
+                    text.push_str(&format!("  reviewers: {}", confirmed.join(", ")));
+                }
+                let mut line = Line::from(text);
+                if let Some(pipeline) = self.pipelines.get(&repo.dir) {
+                    let color = match pipeline {
+                        PipelineStatus::Passed => Color::Green,
+                        PipelineStatus::Failed(_) => Color::Red,
+                        PipelineStatus::Missing => Color::DarkGray,
+                        _ => Color::Yellow,
+                    };
+                    let symbol = match pipeline {
+                        PipelineStatus::Passed => "✓ ",
+                        PipelineStatus::Failed(_) => "✗ ",
+                        _ => "",
+                    };
+                    line.push_span(Span::styled(
+                        format!("  {}{}", symbol, pipeline.label()),
+                        Style::default().fg(color),
+                    ));
+                }
+                let dropped = repo.dropped_reviewers();
+                if !dropped.is_empty() {
+                    line.push_span(
+                        format!(
+                            "  {}dropped: {}",
+                            mark(self.config.colorblind, "⚠"),
+                            dropped.join(", ")
+                        )
+                        .red(),
+                    );
+                }
+                let mut style = Style::default().fg(color);
+                // A failure stands out by more than its red
+                if self.config.colorblind && repo.state == RepoState::Failed {
+                    style = style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+                }
+                let mut item = ListItem::new(line).style(style);
+                if i == self.execution_index {
+                    item = item.style(Style::default().fg(Color::Yellow).bg(Color::Blue));
+                }
+                item
+            })
+            .collect();
+        List::new(items).render(repo_area, buf);
+        if self.hyperlinks {
+            for (i, repo) in execution.repos.iter().enumerate() {
+                let Some(url) = &repo.url else {
+                    continue;
+                };
+                if i as u16 >= repo_area.height {
+                    break;
+                }
+                let row = Rect::new(repo_area.x, repo_area.y + i as u16, repo_area.width, 1);
+                hyperlinks::link_text(buf, row, url, url);
+                hyperlinks::link_text(buf, row, &repo.dir, hyperlinks::project_url(url));
+            }
+        }
+
+        let log = execution
+            .repos
+            .get(self.execution_index)
+            .map(|r| r.log.as_str())
+            .unwrap_or_default();
+        Paragraph::new(log)
+            .block(Block::bordered().title("Output"))
+            .render(log_area, buf);
+    }
+
+    /// URLs of the created merge requests, highlighted one only unless `all` is set.
+    pub(crate) fn result_urls(&self, all: bool) -> Vec<String> {
+        let Some(execution) = &self.execution else {
+            return Vec::new();
+        };
+        execution
+            .repos
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| all || *i == self.execution_index)
+            .filter_map(|(_, repo)| repo.url.clone())
+            .collect()
+    }
+
+    /// Open the URLs of the created merge requests in the browser.
+    pub(crate) fn open_results(&mut self, all: bool) {
+        self.open_urls(self.result_urls(all));
+    }
+
+    /// Open merge requests in the browser, one tab each.
+    fn open_urls(&mut self, urls: Vec<String>) {
+        if urls.is_empty() {
+            self.toasts
+                .push(ToastLevel::Error, "No merge request URL to open");
+            return;
+        }
+        for url in &urls {
+            if let Err(e) = utils::open_in_browser(url) {
+                self.toasts
+                    .push(ToastLevel::Error, format!("Failed to open browser: {}", e));
+                return;
+            }
+        }
+        self.toasts.push(
+            ToastLevel::Info,
+            format!("Opened {} merge requests", urls.len()),
+        );
+    }
+
+    /// Copy the URLs of all created merge requests to the clipboard, one per line.
+    pub(crate) fn copy_results(&mut self) {
+        self.copy_urls(self.result_urls(true));
+    }
+
+    /// Copy merge request URLs to the clipboard, one per line.
+    fn copy_urls(&mut self, urls: Vec<String>) {
+        match self.clipboard.copy(urls.join("\n")) {
+            Ok(()) => self.toasts.push(
+                ToastLevel::Info,
+                format!("Copied {} URLs to the clipboard", urls.len()),
+            ),
+            Err(e) => self
+                .toasts
+                .push(ToastLevel::Error, format!("Clipboard unavailable: {}", e)),
+        }
+    }
+
+    /// Run the repos that failed once more, switching back to the Executing screen.
+    pub(crate) fn retry_failed(&mut self) {
+        let (Some(execution), Some(mr)) = (&mut self.execution, &self.mr) else {
+            return;
+        };
+        let count = execution.retry_failed(&self.config, mr);
+        if count == 0 {
+            self.toasts
+                .push(ToastLevel::Info, "No failed repositories to retry");
+            return;
+        }
+        self.toasts.push(
+            ToastLevel::Info,
+            format!("Retrying {} failed repositories", count),
+        );
+        self.screen = Screens::Executing;
+    }
+
+    /// Start creating the merge request in every selected repo.
+    /// With `--emit-script` the plan is written to the script instead, and the app quits.
+    pub(crate) fn start_execution(&mut self) {
+        if let Some(path) = self.config.emit_script.clone() {
+            match script::write(&path, &self.plan_script(&self.batch_dirs())) {
+                Ok(()) => {
+                    self.user_input_completed = true;
+                    self.quit();
+                }
+                Err(e) => self.show_error(format!("Cannot write {}: {}", path.display(), e)),
+            }
+            return;
+        }
+        if let Some(path) = self.config.emit_plan.clone() {
+            match plan::save(&path, &self.resolved_plan()) {
+                Ok(()) => {
+                    self.user_input_completed = true;
+                    self.quit();
+                }
+                Err(e) => self.show_error(format!("Cannot write {}: {}", path.display(), e)),
+            }
+            return;
+        }
+
+        let mut mr = self.build_merge_request();
+        self.run_started_at = runs::now();
+        mr.run_id = Some(runs::run_id(self.run_started_at));
+        if self.config.dry_run {
+            self.dry_run_plan = Some(self.current_plan());
+        } else {
+            self.remember_reviewers();
+            self.remember_label();
+        }
+        self.execution = Some(Execution::start(&self.config, &mr, self.batch_dirs()));
+        self.mr = Some(mr);
+        self.execution_index = 0;
+        self.user_input_completed = true;
+        self.screen = Screens::Executing;
+    }
+
+    /// The commands about to run in each repo of the batch.
+    pub(crate) fn current_plan(&self) -> Vec<(String, Vec<String>)> {
+        self.batch_dirs()
+            .into_iter()
+            .map(|dir| {
+                let commands = self.planned_commands(&dir);
+                (dir, commands)
+            })
+            .collect()
+    }
+
+    /// Go back to the Finalize screen after a dry run to run the same batch for real.
+    /// The repos are scanned again, the Finalize screen then flags where the commands
+    /// differ from the dry run, e.g. because a repo switched branches in the meantime.
+    pub(crate) fn run_for_real(&mut self) {
+        if !self.config.dry_run || !self.execution.as_ref().is_some_and(|e| e.is_finished()) {
+            return;
+        }
+        self.config.dry_run = false;
+        self.execution = None;
+        self.mr = None;
+        self.pipelines.clear();
+        self.user_input_completed = false;
+        self.rescan_repos();
+        self.show_finalize();
+    }
+
+    /// The branch created for the merge request: the one typed on the Finalize screen,
+    /// or else rendered from the title with `branch_template`.
+    pub(crate) fn branch_name(&self) -> String {
+        if let Some(branch) = &self.branch_name {
+            return branch.clone();
+        }
+        let branch_template = self
+            .config
+            .branch_template
+            .as_deref()
+            .unwrap_or(branch_name::DEFAULT_TEMPLATE);
+        let user = branch_name::current_user(self.config.assignee.as_deref());
+        branch_name::render(branch_template, &self.mr_title, &user, runs::now())
+    }
+
+    /// Ask what to do with the uncommitted changes of every selected repo on a feature branch
+    /// that wasn't asked about yet. On the default branch they are committed to the new branch
+    /// anyway, and without a checkout they don't matter.
+    pub(crate) fn ask_about_uncommitted(&mut self) {
+        if self.config.no_git || self.config.update || self.config.group.is_some() {
+            return;
+        }
+        self.uncommitted_prompt = self
+            .selected_dirs()
+            .into_iter()
+            .filter(|dir| {
+                !self.uncommitted.contains_key(dir)
+                    && !self.skipped_dirs.contains(dir)
+                    && !self.is_on_default_branch(dir)
+                    && self
+                        .dirs
+                        .iter()
+                        .position(|d| d == dir)
+                        .and_then(|i| self.statuses.get(i))
+                        .is_some_and(|status| status.clean == Some(false))
+            })
+            .collect();
+    }
+
+    /// Answer the prompt for the first repo in [`App::uncommitted_prompt`], `None` skips the repo.
+    pub(crate) fn answer_uncommitted(&mut self, answer: Option<Uncommitted>) {
+        if self.uncommitted_prompt.is_empty() {
+            return;
+        }
+        let dir = self.uncommitted_prompt.remove(0);
+        match answer {
+            Some(answer) => {
+                self.uncommitted.insert(dir, answer);
+            }
+            None => {
+                self.skipped_dirs.insert(dir);
+            }
+        }
+    }
+
+    /// The merge request described by the user's input so far.
+    pub(crate) fn build_merge_request(&self) -> merge_request::MergeRequest {
+        let due_date = due_date::resolve(&self.advanced.due_date, due_date::today());
+        let mut mr = merge_request::MergeRequest {
+            title: self.mr_title.clone(),
+            branch: Some(self.branch_name()),
+            description: self.mr_description.text().to_string(),
+            template_values: self.template_values.clone(),
+            ticket_links: self.config.ticket_links.clone(),
+            template: self
+                .template
+                .as_ref()
+                .filter(|(_, filled_in)| filled_in == self.mr_description.text())
+                .map(|(name, _)| name.clone()),
+            reviewers: self.picked_reviewers(),
+            labels: match self.selected_preset() {
+                Some(preset) => preset.labels.clone(),
+                None => self
+                    .labels
+                    .get(self.selected_label)
+                    .map(|(k, _)| vec![k.clone()])
+                    .unwrap_or_default(),
+            },
+            assignees: self.selected_assignees.clone(),
+            target_branch: non_empty(&self.advanced.target_branch),
+            draft: self.advanced.draft,
+            squash: self.advanced.squash,
+            remove_source_branch: self.advanced.remove_source_branch,
+            auto_merge: self.advanced.auto_merge,
+            milestone: non_empty(&self.advanced.milestone).or_else(|| {
+                due_date
+                    .as_ref()
+                    .and(self.config.due_date_milestone.clone())
+            }),
+            due_date,
+            closes_issues: self
+                .advanced
+                .issues
+                .split(',')
+                .filter_map(non_empty)
+                .collect(),
+            push_options: self
+                .advanced
+                .push_options
+                .split(',')
+                .filter_map(non_empty)
+                .collect(),
+            uncommitted_by_dir: self.uncommitted.clone(),
+            maintainers_by_dir: self.maintainers.clone(),
+            ..Default::default()
+        };
+        mr.apply_label_rules(&self.config.label_rules);
+        mr.reviewers.retain(|r| self.config.may_review(r));
+        let picked = self.picked_reviewers();
+        let order: Vec<usize> = match self.config.reviewer_strategy {
+            ReviewerStrategy::All => Vec::new(),
+            ReviewerStrategy::RoundRobin => (0..picked.len()).collect(),
+            ReviewerStrategy::Random => shuffled(picked.len(), self.reviewer_seed),
+        };
+        mr.share_reviewers(&picked, &order, &self.batch_dirs());
+        mr
+    }
+
+    /// The picked reviewers, in the order of the reviewer list.
+    fn picked_reviewers(&self) -> Vec<String> {
+        let mut picked: Vec<usize> = self.selected_reviewers.iter().copied().collect();
+        picked.sort();
+        picked
+            .into_iter()
+            .filter_map(|i| self.config.reviewers.get(i).cloned())
+            .collect()
+    }
+
+    /// Check the chosen labels and reviewers against every selected project in the
+    /// background, since GitLab silently ignores values that don't exist there. The problems
+    /// show up on the Finalize screen as they are found.
+    pub(crate) fn validate_selection(&mut self) {
+        let mr = self.build_merge_request();
+        self.validation_problems.clear();
+        // A validation of an earlier selection is stopped by dropping its receiver
+        self.validation = None;
+        if self.config.offline
+            || self.config.tour
+            || (mr.labels.is_empty() && mr.reviewers.is_empty())
+            || !self.has_checkouts()
+        {
+            return;
+        }
+
+        let repos: Vec<(String, String, PathBuf)> = self
+            .selected_dirs()
+            .into_iter()
+            .map(|dir| {
+                let glab = self.config.glab_for(&dir).to_string();
+                let repo_dir = self.config.working_dir.join(&dir);
+                (dir, glab, repo_dir)
+            })
+            .collect();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            for (dir, glab, repo_dir) in repos {
+                let problems = gitlab::validate_labels_and_reviewers(
+                    &glab,
+                    &repo_dir,
+                    &mr.labels,
+                    &mr.reviewers,
+                )
+                .unwrap_or_else(|e| vec![format!("could not validate: {}", e)]);
+                if tx.send((dir, problems)).is_err() {
+                    return;
+                }
+            }
+        });
+        self.validation = Some(rx);
+    }
+
+    /// Check every selected repo for what would make it fail halfway through the batch, in the
+    /// background, see [`preflight::Repo::check`].
+    pub(crate) fn start_preflight(&mut self) {
+        let mr = self.build_merge_request();
+        let repos = self
+            .selected_dirs()
+            .into_iter()
+            .map(|dir| {
+                let for_dir = mr.for_dir(&self.config, &dir);
+                preflight::Repo {
+                    repo_dir: self.config.working_dir.join(&dir),
+                    glab: self.config.glab_for(&dir).to_string(),
+                    project: for_dir.project,
+                    new_branch: self.is_on_default_branch(&dir).then(|| self.branch_name()),
+                    target_branch: for_dir.target_branch,
+                    dir,
+                }
+            })
+            .collect();
+        self.preflight.clear();
+        self.preflight_run = Some(preflight::start(repos));
+    }
+
+    /// Check again if the checks ran before, after something they depend on changed.
+    pub(crate) fn restart_preflight(&mut self) {
+        if !self.preflight.is_empty() || self.preflight_run.is_some() {
+            self.start_preflight();
+        }
+    }
+
+    fn poll_preflight(&mut self) {
+        let Some(rx) = &self.preflight_run else {
+            return;
+        };
+        loop {
+            match rx.try_recv() {
+                Ok((dir, results)) => {
+                    self.preflight.insert(dir, results);
+                }
+                Err(mpsc::TryRecvError::Empty) => return,
+                Err(mpsc::TryRecvError::Disconnected) => break,
+            }
+        }
+        self.preflight_run = None;
+        let failing = self.preflight_failing_dirs().len();
+        if failing > 0 {
+            self.toasts.push(
+                ToastLevel::Error,
+                format!("{} repositories failed the preflight checks", failing),
+            );
+        }
+    }
+
+    /// The repos of the batch that failed a preflight check, skipped ones don't count.
+    pub(crate) fn preflight_failing_dirs(&self) -> Vec<String> {
+        self.batch_dirs()
+            .into_iter()
+            .filter(|dir| {
+                self.preflight
+                    .get(dir)
+                    .is_some_and(|results| results.iter().any(|r| r.failure().is_some()))
+            })
+            .collect()
+    }
+
+    /// The checklist of each selected repo, with the checks of the highlighted one.
+    pub(crate) fn render_preflight(&mut self, window: Rect, buf: &mut Buffer) {
+        let [repo_area, check_area] =
+            Layout::horizontal([Constraint::Percentage(30), Constraint::Percentage(70)])
+                .areas(window);
+
+        let selected_dirs = self.selected_dirs();
+        let items: Vec<ListItem> = selected_dirs
+            .iter()
+            .enumerate()
+            .map(|(i, dir)| {
+                let (mark, color) = if self.skipped_dirs.contains(dir) {
+                    ("[skip]", Color::DarkGray)
+                } else {
+                    match self.preflight.get(dir) {
+                        None => ("[....]", Color::Yellow),
+                        Some(results) if results.iter().any(|r| r.failure().is_some()) => {
+                            ("[fail]", Color::Red)
+                        }
+                        Some(_) => ("[ ok ]", Color::Green),
+                    }
+                };
+                let mut item = ListItem::new(Line::from(vec![
+                    Span::styled(mark, Style::default().fg(color)),
+                    Span::raw(format!(" {}", dir)),
+                ]));
+                if i == self.preflight_index {
+                    item = item.style(Style::default().bg(Color::Blue));
+                }
+                item
+            })
+            .collect();
+        let mut title = "Repositories".to_string();
+        if self.preflight_run.is_some() {
+            title.push_str(&format!(
+                " (checking, {}/{} done)",
+                self.preflight.len(),
+                selected_dirs.len()
+            ));
+        }
+        List::new(items)
+            .block(Block::bordered().title(title))
+            .render(repo_area, buf);
+
+        let lines: Vec<Line> = match selected_dirs.get(self.preflight_index) {
+            Some(dir) if self.skipped_dirs.contains(dir) => {
+                vec![Line::from("Skipped, nothing runs in this repository")]
+            }
+            Some(dir) => match self.preflight.get(dir) {
+                None => vec![Line::from("Checking...")],
+                Some(results) => results
+                    .iter()
+                    .map(|result| match &result.outcome {
+                        Outcome::Passed => Line::from(vec![
+                            Span::styled("✓ ", Style::default().fg(Color::Green)),
+                            Span::raw(result.check.label()),
+                        ]),
+                        Outcome::Failed(message) => Line::from(vec![
+                            Span::styled("✗ ", Style::default().fg(Color::Red)),
+                            Span::raw(format!("{}: {}", result.check.label(), message)),
+                        ]),
+                        Outcome::NotChecked(reason) => Line::from(vec![
+                            Span::styled("- ", Style::default().fg(Color::DarkGray)),
+                            Span::raw(format!("{}: {}", result.check.label(), reason)).dark_gray(),
+                        ]),
+                    })
+                    .collect(),
+            },
+            None => Vec::new(),
+        };
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(Block::bordered().title("Checks"))
+            .render(check_area, buf);
+    }
+
+    /// Offer the members of the selected projects as reviewers next to the configured ones.
+    /// Cached members are shown right away while fresh ones are fetched in the background.
+    pub(crate) fn start_reviewer_fetch(&mut self) {
+        if self.reviewer_fetch.is_some() || !self.has_checkouts() {
+            return;
+        }
+
+        let repos = self.repos_with_glab(&self.selected_dirs());
+
+        // Cached members are completed to when typing a reviewer, even without fetching
+        for (_, repo_dir) in &repos {
+            if let Some(cached) = gitlab::cached_reviewer_candidates(repo_dir) {
+                self.remember_members(&cached);
+                if self.config.fetch_reviewers {
+                    self.add_reviewers(cached);
+                }
+            }
+        }
+        if !self.config.fetch_reviewers {
+            return;
+        }
+
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let usernames = repos
+                .iter()
+                .filter_map(|(glab, repo_dir)| gitlab::reviewer_candidates(glab, repo_dir).ok())
+                .flatten()
+                .collect();
+            let _ = tx.send(usernames);
+        });
+        self.reviewer_fetch = Some(rx);
+    }
+
+    /// Look up the user glab is logged in as to default the assignee to, when none is configured.
+    pub(crate) fn start_user_fetch(&mut self) {
+        if self.config.assignee.is_some() || self.config.offline || self.user_fetch.is_some() {
+            return;
+        }
+        let glab = self.config.glab_binary().to_string();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            if let Ok(user) = gitlab::current_user(&glab) {
+                let _ = tx.send(user.username);
+            }
+        });
+        self.user_fetch = Some(rx);
+    }
+
+    /// Default the assignee to `username`, unless one was set in the meantime.
+    pub(crate) fn use_detected_assignee(&mut self, username: String) {
+        if self.config.assignee.is_some() {
+            return;
+        }
+        if !self.selected_assignees.contains(&username) {
+            self.selected_assignees.insert(0, username.clone());
+        }
+        self.config.assignee = Some(username);
+        self.assignee_detected = true;
+    }
+
+    /// Count the open merge requests each reviewer is asked to review in the working dir, so
+    /// the load can be spread. Cached counts are shown while fresh ones are fetched.
+    pub(crate) fn start_review_load_fetch(&mut self) {
+        if !self.config.fetch_reviewers || self.review_load_fetch.is_some() || !self.has_checkouts()
+        {
+            return;
+        }
+
+        let repos = self.repos_with_glab(&self.dirs);
+        self.review_load = gitlab::sum_review_load(
+            repos
+                .iter()
+                .filter_map(|(_, repo_dir)| gitlab::cached_review_load(repo_dir)),
+        );
+
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let load = gitlab::sum_review_load(
+                repos
+                    .iter()
+                    .filter_map(|(glab, repo_dir)| gitlab::review_load(glab, repo_dir).ok()),
+            );
+            let _ = tx.send(load);
+        });
+        self.review_load_fetch = Some(rx);
+    }
+
+    /// The glab executable and the path of each of `dirs`, to query GitLab in the background.
+    fn repos_with_glab(&self, dirs: &[String]) -> Vec<(String, PathBuf)> {
+        dirs.iter()
+            .map(|dir| {
+                (
+                    self.config.glab_for(dir).to_string(),
+                    self.config.working_dir.join(dir),
+                )
+            })
+            .collect()
+    }
+
+    /// Show the recorded runs, newest first.
+    pub(crate) fn open_history(&mut self) {
+        let records = match runs::default_path() {
+            Some(path) => runs::load(&path).map_err(|e| e.to_string()),
+            None => Err("no state directory, set HOME or XDG_STATE_HOME".to_string()),
+        };
+        match records {
+            Ok(records) => {
+                self.history = records.into_iter().rev().collect();
+                self.history_index = 0;
+                self.screen = Screens::History;
+            }
+            Err(e) => self.show_error(format!("Cannot read the recorded runs: {}", e)),
+        }
+    }
+
+    /// Open every merge request of the highlighted run, or copy their URLs.
+    pub(crate) fn reopen_run(&mut self, copy: bool) {
+        let Some(record) = self.history.get(self.history_index) else {
+            return;
+        };
+        let urls = record.urls();
+        if copy {
+            self.copy_urls(urls);
+        } else {
+            self.open_urls(urls);
+        }
+    }
+
+    /// Past runs on the left, the merge requests of the highlighted one on the right.
+    pub(crate) fn render_history(&mut self, window: Rect, buf: &mut Buffer) {
+        let [run_area, repo_area] =
+            Layout::horizontal([Constraint::Percentage(45), Constraint::Percentage(55)])
+                .areas(window);
+
+        let items: Vec<ListItem> = self
+            .history
+            .iter()
+            .enumerate()
+            .map(|(i, record)| {
+                let mut item = ListItem::new(vec![
+                    Line::from(record.title.as_str()),
+                    Line::from(format!("  {}  {}", record.started(), record.outcome())).dark_gray(),
+                ]);
+                if i == self.history_index {
+                    item = item.style(Style::default().fg(Color::Yellow).bg(Color::Blue));
+                }
+                item
+            })
+            .collect();
+        if items.is_empty() {
+            Paragraph::new("No runs recorded yet")
+                .block(Block::bordered().title("Runs"))
+                .render(run_area, buf);
+        } else {
+            List::new(items)
+                .block(Block::bordered().title("Runs"))
+                .render(run_area, buf);
+        }
+
+        let lines: Vec<Line> = self
+            .history
+            .get(self.history_index)
+            .map(|record| {
+                record
+                    .repos
+                    .iter()
+                    .map(|repo| {
+                        Line::from(format!(
+                            "{} ({})  {}",
+                            repo.dir,
+                            repo.outcome,
+                            repo.url.as_deref().unwrap_or("-")
+                        ))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let title = self
+            .history
+            .get(self.history_index)
+            .map_or("Merge requests".to_string(), |r| format!("Run {}", r.id));
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(Block::bordered().title(title))
+            .render(repo_area, buf);
+    }
+
+    /// Add the finished batch to the recorded runs, again after retrying so the record
+    /// holds the final outcome. Dry runs create nothing worth recording.
+    pub(crate) fn record_run(&mut self) {
+        let (Some(mr), Some(execution)) = (&self.mr, &self.execution) else {
+            return;
+        };
+        if self.config.dry_run {
+            return;
+        }
+        let record = RunRecord::new(&self.config, mr, &execution.repos, self.run_started_at);
+        let result = match runs::default_path() {
+            Some(path) => runs::save(&path, record).map_err(|e| e.to_string()),
+            None => Err("no state directory, set HOME or XDG_STATE_HOME".to_string()),
+        };
+        if let Err(e) = result {
+            self.toasts
+                .push(ToastLevel::Error, format!("Cannot record this run: {}", e));
+        }
+    }
+
+    /// Publish the report of the finished batch as configured by `report_snippet` and
+    /// `report_issue`, and create the umbrella issue or epic. Dry runs create nothing worth
+    /// reporting.
+    pub(crate) fn start_report_publish(&mut self) {
+        if self.config.dry_run
+            || (!self.config.report_snippet
+                && self.config.report_issue.is_none()
+                && self.config.umbrella_issue.is_none()
+                && self.config.umbrella_epic.is_none())
+        {
+            return;
+        }
+        let (Some(mr), Some(execution)) = (&self.mr, &self.execution) else {
+            return;
+        };
+
+        let report = report::render(mr, &execution.repos);
+        let checklist = report::checklist(mr, &execution.repos);
+        let title = mr.title.clone();
+        let config = self.config.clone();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(report::publish(
+                &config,
+                &title,
+                &report,
+                checklist.as_deref(),
+            ));
+        });
+        self.report_publish = Some(rx);
+    }
+
+    /// Approve or merge the created MRs of the batch in the background, only those that
+    /// are no draft and whose latest pipeline passed.
+    pub(crate) fn start_batch_merge(&mut self, approve: bool, merge: bool) {
+        if self.batch_merge.is_some() {
+            return;
+        }
+        let (Some(mr), Some(execution)) = (&self.mr, &self.execution) else {
+            return;
+        };
+        let repos: Vec<_> = execution
+            .repos
+            .iter()
+            .filter_map(|repo| {
+                let iid = repo.iid?;
+                Some((
+                    repo.dir.clone(),
+                    self.config.working_dir.join(&repo.dir),
+                    self.config.glab_for(&repo.dir).to_string(),
+                    mr.for_dir(&self.config, &repo.dir).project,
+                    iid,
+                ))
+            })
+            .collect();
+        if repos.is_empty() {
+            self.toasts.push(
+                ToastLevel::Info,
+                "No merge requests were created to approve or merge",
+            );
+            return;
+        }
+        let action = if merge { "Merging" } else { "Approving" };
+        self.toasts.push(
+            ToastLevel::Info,
+            format!(
+                "{} the {} merge requests that are ready...",
+                action,
+                repos.len()
+            ),
+        );
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let candidates = repos
+                .into_iter()
+                .map(|(dir, repo_dir, glab, project, iid)| {
+                    let candidate = batch_merge::fetch(&dir, repo_dir, &glab, project, iid);
+                    (dir, candidate)
+                })
+                .collect();
+            let _ = tx.send(batch_merge::run_ready(candidates, approve, merge));
+        });
+        self.batch_merge = Some(rx);
+    }
+
+    /// Announce the created MRs to the `announce_webhook` in the background, if any were
+    /// created. Dry runs create nothing to announce.
+    pub(crate) fn start_announce(&mut self) {
+        let (Some(webhook), Some(mr), Some(execution)) =
+            (&self.config.announce_webhook, &self.mr, &self.execution)
+        else {
+            return;
+        };
+        let created: Vec<(String, String)> = execution
+            .repos
+            .iter()
+            .filter_map(|repo| Some((repo.dir.clone(), repo.url.clone()?)))
+            .collect();
+        if self.config.dry_run || created.is_empty() {
+            return;
+        }
+        let webhook = webhook.clone();
+        let text = announce::message(&mr.title, &created);
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(announce::send(&webhook, &text));
+        });
+        self.announce = Some(rx);
+    }
+
+    /// POST the created MRs to the `[merge_queue]` in the background. Dry runs create
+    /// nothing to enqueue.
+    pub(crate) fn start_merge_queue(&mut self) {
+        let (Some(queue), Some(mr), Some(execution)) =
+            (&self.config.merge_queue, &self.mr, &self.execution)
+        else {
+            return;
+        };
+        if self.config.dry_run {
+            return;
+        }
+        let entries: Vec<merge_queue::Entry> = execution
+            .repos
+            .iter()
+            .filter_map(|repo| {
+                Some(merge_queue::Entry {
+                    url: repo.url.clone()?,
+                    iid: repo.iid?,
+                    project: mr
+                        .for_dir(&self.config, &repo.dir)
+                        .project
+                        .map(|p| p.path)
+                        .unwrap_or_default(),
+                    repo: repo.dir.clone(),
+                    title: mr.title.clone(),
+                })
+            })
+            .collect();
+        if entries.is_empty() {
+            return;
+        }
+        let queue = queue.clone();
+        let run = runs::run_id(self.run_started_at);
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(queue.enqueue_all(&entries, &run));
+        });
+        self.merge_queue_submit = Some(rx);
+    }
+
+    /// Keep looking up the pipelines of the created MRs in the background, see
+    /// [`pipeline_watch::start`].
+    pub(crate) fn start_pipeline_watch(&mut self) {
+        if self.pipeline_watch.is_some() {
+            return;
+        }
+        let (Some(mr), Some(execution)) = (&self.mr, &self.execution) else {
+            return;
+        };
+        let watched: Vec<_> = execution
+            .repos
+            .iter()
+            .filter_map(|repo| {
+                Some(pipeline_watch::Watched {
+                    iid: repo.iid?,
+                    dir: repo.dir.clone(),
+                    repo_dir: self.config.working_dir.join(&repo.dir),
+                    glab: self.config.glab_for(&repo.dir).to_string(),
+                    project: mr.for_dir(&self.config, &repo.dir).project,
+                })
+            })
+            .collect();
+        if watched.is_empty() {
+            self.toasts.push(
+                ToastLevel::Info,
+                "No merge requests were created to watch the pipelines of",
+            );
+            return;
+        }
+        self.toasts.push(
+            ToastLevel::Info,
+            format!(
+                "Watching the pipelines of {} merge requests...",
+                watched.len()
+            ),
+        );
+        self.pipeline_watch = Some(pipeline_watch::start(
+            watched,
+            pipeline_watch::POLL_INTERVAL,
+        ));
+    }
+
+    fn poll_pipeline_watch(&mut self) {
+        let Some(rx) = &self.pipeline_watch else {
+            return;
+        };
+        loop {
+            match rx.try_recv() {
+                Ok((dir, status)) => {
+                    self.pipelines.insert(dir, status);
+                }
+                Err(mpsc::TryRecvError::Empty) => return,
+                Err(mpsc::TryRecvError::Disconnected) => break,
+            }
+        }
+        self.pipeline_watch = None;
+        let passed = self
+            .pipelines
+            .values()
+            .filter(|s| **s == PipelineStatus::Passed)
+            .count();
+        let failed = self
+            .pipelines
+            .values()
+            .filter(|s| matches!(s, PipelineStatus::Failed(_)))
+            .count();
+        let level = if failed > 0 {
+            ToastLevel::Error
+        } else {
+            ToastLevel::Info
+        };
+        self.toasts.push(
+            level,
+            format!("Pipelines finished: {} passed, {} failed", passed, failed),
+        );
+    }
+
+    /// Offer the labels of the selected projects next to the configured ones, combined as
+    /// configured by `fetch_labels`. Cached labels are shown while fresh ones are fetched.
+    pub(crate) fn start_label_fetch(&mut self) {
+        let mode = self.config.fetch_labels;
+        if mode == LabelFetch::Off || self.label_fetch.is_some() || !self.has_checkouts() {
+            return;
+        }
+
+        let repos = self.repos_with_glab(&self.selected_dirs());
+
+        let cached: Option<Vec<_>> = repos
+            .iter()
+            .map(|(_, repo_dir)| gitlab::cached_label_names(repo_dir))
+            .collect();
+        if let Some(cached) = cached {
+            self.add_labels(gitlab::combine_labels(cached, mode));
+        }
+
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let fetched: Result<Vec<_>, _> = repos
+                .iter()
+                .map(|(glab, repo_dir)| gitlab::label_names(glab, repo_dir))
+                .collect();
+            if let Ok(fetched) = fetched {
+                let _ = tx.send(gitlab::combine_labels(fetched, mode));
+            }
+        });
+        self.label_fetch = Some(rx);
+    }
+
+    /// The label preset picked in the label picker, if a preset rather than a label is picked.
+    pub(crate) fn selected_preset(&self) -> Option<&LabelPreset> {
+        let (name, _) = self.labels.get(self.selected_label)?;
+        self.config.label_presets.get(name)
+    }
+
+    /// Preselect the reviewers of the picked label preset and use its milestone,
+    /// unless another one is chosen. Both can still be changed afterwards.
+    pub(crate) fn apply_label_preset(&mut self) {
+        let Some(preset) = self.selected_preset().cloned() else {
+            return;
+        };
+        self.add_reviewers(preset.reviewers.iter().cloned());
+        for (i, reviewer) in self.config.reviewers.iter().enumerate() {
+            if preset.reviewers.contains(reviewer) {
+                self.selected_reviewers.insert(i);
+            }
+        }
+        if self.advanced.milestone.trim().is_empty()
+            && let Some(milestone) = preset.milestone
+        {
+            self.advanced.milestone = milestone;
+        }
+    }
+
+    /// Append labels that are not offered yet, keeping the selected label selected.
+    /// Returns how many labels were added.
+    pub(crate) fn add_labels(&mut self, names: impl IntoIterator<Item = String>) -> usize {
+        let before = self.labels.len();
+        for name in names {
+            if !self.labels.iter().any(|(k, v)| k == &name || v == &name) {
+                self.labels.push((name, String::new()));
+            }
+        }
+        self.sort_labels();
+        self.labels.len() - before
+    }
+
+    /// Order the label picker by usage unless `label_order` keeps the configured order,
+    /// keeping the selected label selected.
+    fn sort_labels(&mut self) {
+        if self.config.label_order != LabelOrder::Usage {
+            return;
+        }
+        let selected = self.labels.get(self.selected_label).cloned();
+        label_usage::sort(&mut self.labels, &self.label_usage);
+        if let Some(selected) = selected {
+            self.selected_label = self
+                .labels
+                .iter()
+                .position(|label| *label == selected)
+                .unwrap_or_default();
+        }
+    }
+
+    /// Count the use of the picked label or preset, to offer it earlier next time.
+    fn remember_label(&mut self) {
+        let (Some(path), Some((name, _))) = (
+            label_usage::default_path(),
+            self.labels.get(self.selected_label),
+        ) else {
+            return;
+        };
+        if let Err(e) = label_usage::record(&path, std::slice::from_ref(name), runs::now()) {
+            self.toasts.push(
+                ToastLevel::Error,
+                format!("Cannot remember the label: {}", e),
+            );
+        }
+    }
+
+    /// Append reviewers that are not known yet, keeping the indices of existing ones stable.
+    /// Those in `never_review` are left out. Returns how many reviewers were added.
+    pub(crate) fn add_reviewers(&mut self, usernames: impl IntoIterator<Item = String>) -> usize {
+        let before = self.config.reviewers.len();
+        for username in usernames {
+            if self.config.may_review(&username) && !self.config.reviewers.contains(&username) {
+                self.config.reviewers.push(username);
+            }
+        }
+        self.config.reviewers.len() - before
+    }
+
+    /// Where the reviewers picked for the repo `dir` are remembered.
+    fn last_reviewers_key(&self, dir: &str) -> String {
+        self.config.working_dir.join(dir).display().to_string()
+    }
+
+    /// The reviewers picked last time for any of the selected repos, each once.
+    pub(crate) fn last_reviewers_of_selection(&self) -> Vec<String> {
+        let mut reviewers: Vec<String> = Vec::new();
+        for dir in self.selected_dirs() {
+            for reviewer in self
+                .last_reviewers
+                .get(&self.last_reviewers_key(&dir))
+                .into_iter()
+                .flatten()
+            {
+                if !reviewers.contains(reviewer) {
+                    reviewers.push(reviewer.clone());
+                }
+            }
+        }
+        reviewers
+    }
+
+    /// Select the reviewers picked last time for the selected repos, unless some reviewers
+    /// are selected already.
+    pub(crate) fn preselect_last_reviewers(&mut self) {
+        if !self.selected_reviewers.is_empty() {
+            return;
+        }
+        for reviewer in self.last_reviewers_of_selection() {
+            self.add_reviewers([reviewer.clone()]);
+            if let Some(i) = self.config.reviewers.iter().position(|r| *r == reviewer) {
+                self.selected_reviewers.insert(i);
+            }
+        }
+    }
+
+    /// Select the reviewers given with `--reviewer`, adding those missing from the config for
+    /// this run. They take the place of the reviewers picked last time.
+    fn preselect_cli_reviewers(&mut self) {
+        let mut refused = Vec::new();
+        for reviewer in self.config.preselected_reviewers.clone() {
+            self.add_reviewers([reviewer.clone()]);
+            match self.config.reviewers.iter().position(|r| *r == reviewer) {
+                Some(i) => {
+                    self.selected_reviewers.insert(i);
+                }
+                None => refused.push(reviewer),
+            }
+        }
+        if !refused.is_empty() {
+            self.toasts.push(
+                ToastLevel::Error,
+                format!(
+                    "Not preselected, listed in never_review: {}",
+                    refused.join(", ")
+                ),
+            );
+        }
+    }
+
+    /// Remember the picked reviewers for every repo of the batch, for the next one.
+    fn remember_reviewers(&mut self) {
+        let Some(path) = last_reviewers::default_path() else {
+            return;
+        };
+        let repos: Vec<String> = self
+            .batch_dirs()
+            .iter()
+            .map(|dir| self.last_reviewers_key(dir))
+            .collect();
+        let picked = self.picked_reviewers();
+        if let Err(e) = last_reviewers::save(&path, &repos, &picked) {
+            self.toasts.push(
+                ToastLevel::Error,
+                format!("Cannot remember the reviewers: {}", e),
+            );
+        }
+    }
+
+    /// Keep `usernames` as project members to complete typed reviewers to.
+    pub(crate) fn remember_members(&mut self, usernames: &[String]) {
+        for username in usernames {
+            if !self.project_members.contains(username) {
+                self.project_members.push(username.clone());
+            }
+        }
+    }
+
+    /// The known reviewer or project member the typed username completes to, if any.
+    pub(crate) fn reviewer_completion(&self) -> Option<&str> {
+        let typed = self.reviewer_input.as_deref()?.trim_start_matches('@');
+        if typed.is_empty() {
+            return None;
+        }
+        let typed = typed.to_lowercase();
+        self.config
+            .reviewers
+            .iter()
+            .chain(&self.project_members)
+            .map(String::as_str)
+            .filter(|username| self.config.may_review(username))
+            .find(|username| {
+                username.len() > typed.len() && username.to_lowercase().starts_with(&typed)
+            })
+    }
+
+    /// Add the typed username to the reviewers of this run and select it.
+    pub(crate) fn add_typed_reviewer(&mut self) {
+        let Some(input) = self.reviewer_input.take() else {
+            return;
+        };
+        let username = input.trim().trim_start_matches('@').to_string();
+        if username.is_empty() {
+            return;
+        }
+        if username.contains(char::is_whitespace) {
+            self.toasts.push(
+                ToastLevel::Error,
+                format!("'{}' is not a GitLab username", username),
+            );
+            self.reviewer_input = Some(input);
+            return;
+        }
+        if !self.config.may_review(&username) {
+            self.toasts.push(
+                ToastLevel::Error,
+                format!("{} is listed in never_review", username),
+            );
+            return;
+        }
+        self.add_reviewers([username.clone()]);
+        if let Some(i) = self.config.reviewers.iter().position(|r| *r == username) {
+            self.selected_reviewers.insert(i);
+            self.reviewer_index = i;
+        }
+    }
+
+    /// Pick up results of background work without blocking the UI.
+    pub(crate) fn poll_background(&mut self) {
+        self.poll_scan();
+
+        // In reduced motion mode toasts are cleared on the next key press instead
+        if !self.config.reduced_motion {
+            self.toasts.prune();
+        }
+
+        if let Some(execution) = &mut self.execution {
+            let was_finished = execution.is_finished();
+            execution.poll();
+            if !was_finished && execution.is_finished() {
+                let failed = execution
+                    .repos
+                    .iter()
+                    .filter(|r| r.state == RepoState::Failed)
+                    .count();
+                let level = if failed > 0 {
+                    ToastLevel::Error
+                } else {
+                    ToastLevel::Info
+                };
+                self.toasts.push(
+                    level,
+                    format!(
+                        "Batch finished: {} succeeded, {} failed",
+                        execution.repos.len() - failed,
+                        failed
+                    ),
+                );
+                if self.config.desktop_notification {
+                    let (summary, body) = notification::batch_finished(
+                        &self.mr_title,
+                        execution.repos.len() - failed,
+                        failed,
+                        self.config.dry_run,
+                    );
+                    // Best effort, D-Bus might take a while to answer or not run at all
+                    std::thread::spawn(move || notification::send(&summary, &body));
+                }
+                let errors: Vec<String> = execution
+                    .repos
+                    .iter()
+                    .filter_map(|r| r.error.as_ref().map(|e| format!("{}: {}", r.dir, e)))
+                    .collect();
+                if !errors.is_empty() {
+                    self.show_error(errors.join("\n"));
+                }
+                self.screen = Screens::Results;
+                self.record_run();
+                self.start_report_publish();
+                self.start_merge_queue();
+                self.start_announce();
+                if self.config.watch_pipelines {
+                    self.start_pipeline_watch();
+                }
+            }
+        }
+
+        if let Some(rx) = &self.report_publish {
+            match rx.try_recv() {
+                Ok(Ok(published)) => {
+                    for message in published {
+                        self.toasts.push(ToastLevel::Info, message);
+                    }
+                    self.report_publish = None;
+                }
+                Ok(Err(e)) => {
+                    self.show_error(format!("Failed to publish the run report: {}", e));
+                    self.report_publish = None;
+                }
+                Err(mpsc::TryRecvError::Disconnected) => self.report_publish = None,
+                Err(mpsc::TryRecvError::Empty) => {}
+            }
+        }
+
+        if let Some(rx) = &self.batch_merge {
+            match rx.try_recv() {
+                Ok(outcome) => {
+                    self.batch_merge = None;
+                    let mut message = format!("Done with {} merge requests", outcome.done.len());
+                    if !outcome.not_ready.is_empty() {
+                        message.push_str(&format!(
+                            ", not ready yet: {}",
+                            outcome.not_ready.join(", ")
+                        ));
+                    }
+                    self.toasts.push(ToastLevel::Info, message);
+                    if !outcome.failed.is_empty() {
+                        self.show_error(outcome.failed.join("\n"));
+                    }
+                }
+                Err(mpsc::TryRecvError::Disconnected) => self.batch_merge = None,
+                Err(mpsc::TryRecvError::Empty) => {}
+            }
+        }
+
+        self.poll_pipeline_watch();
+        self.poll_preflight();
+
+        if let Some(rx) = &self.announce {
+            match rx.try_recv() {
+                Ok(Ok(())) => {
+                    self.announce = None;
+                    self.toasts
+                        .push(ToastLevel::Info, "Announced the merge requests");
+                }
+                Ok(Err(e)) => {
+                    self.announce = None;
+                    self.show_error(format!("Failed to announce the merge requests: {}", e));
+                }
+                Err(mpsc::TryRecvError::Disconnected) => self.announce = None,
+                Err(mpsc::TryRecvError::Empty) => {}
+            }
+        }
+
+        if let Some(rx) = &self.merge_queue_submit {
+            match rx.try_recv() {
+                Ok((enqueued, failed)) => {
+                    self.merge_queue_submit = None;
+                    if !enqueued.is_empty() {
+                        self.toasts.push(
+                            ToastLevel::Info,
+                            format!(
+                                "Enqueued {} merge requests in the merge queue",
+                                enqueued.len()
+                            ),
+                        );
+                    }
+                    if !failed.is_empty() {
+                        self.show_error(format!(
+                            "Failed to enqueue in the merge queue:\n{}",
+                            failed.join("\n")
+                        ));
+                    }
+                }
+                Err(mpsc::TryRecvError::Disconnected) => self.merge_queue_submit = None,
+                Err(mpsc::TryRecvError::Empty) => {}
+            }
+        }
+
+        if let Some(rx) = &self.user_fetch {
+            match rx.try_recv() {
+                Ok(username) => {
+                    self.user_fetch = None;
+                    self.use_detected_assignee(username);
+                }
+                Err(mpsc::TryRecvError::Disconnected) => self.user_fetch = None,
+                Err(mpsc::TryRecvError::Empty) => {}
+            }
+        }
+
+        while let Some(rx) = &self.validation {
+            match rx.try_recv() {
+                Ok((dir, problems)) => {
+                    if !problems.is_empty() {
+                        self.validation_problems.insert(dir, problems);
+                    }
+                }
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.validation = None;
+                    if !self.validation_problems.is_empty() {
+                        self.toasts.push(
+                            ToastLevel::Error,
+                            format!(
+                                "{} repositories have label/reviewer problems",
+                                self.validation_problems.len()
+                            ),
+                        );
+                    }
+                }
+                Err(mpsc::TryRecvError::Empty) => break,
+            }
+        }
+
+        while let Some(rx) = &self.inspection {
+            match rx.try_recv() {
+                Ok((dir, lines, paths)) => {
+                    if let Some(lines) = lines {
+                        self.changed_lines.insert(dir.clone(), lines);
+                    }
+                    if !paths.is_empty() {
+                        self.suspicious.insert(dir, paths);
+                    }
+                }
+                Err(mpsc::TryRecvError::Disconnected) => self.inspection = None,
+                Err(mpsc::TryRecvError::Empty) => break,
+            }
+        }
+
+        while let Some(rx) = &self.maintainer_fetch {
+            match rx.try_recv() {
+                Ok((dir, maintainer)) => {
+                    self.maintainers.insert(dir, maintainer);
+                }
+                Err(mpsc::TryRecvError::Disconnected) => self.maintainer_fetch = None,
+                Err(mpsc::TryRecvError::Empty) => break,
+            }
+        }
+
+        if let Some(rx) = &self.reachability_check {
+            match rx.try_recv() {
+                Ok((host, e)) => {
+                    self.unreachable_hosts.insert(host, e);
+                }
+                Err(mpsc::TryRecvError::Disconnected) => self.reachability_check = None,
+                Err(mpsc::TryRecvError::Empty) => {}
+            }
+        }
+
+        if let Some(rx) = &self.reviewer_fetch {
+            match rx.try_recv() {
+                Ok(usernames) => {
+                    self.remember_members(&usernames);
+                    let added = self.add_reviewers(usernames);
+                    if added > 0 {
+                        self.toasts.push(
+                            ToastLevel::Info,
+                            format!("Added {} project members as reviewers", added),
+                        );
+                    }
+                    self.reviewer_fetch = None;
+                }
+                Err(mpsc::TryRecvError::Disconnected) => self.reviewer_fetch = None,
+                Err(mpsc::TryRecvError::Empty) => {}
+            }
+        }
+
+        if let Some(rx) = &self.review_load_fetch {
+            match rx.try_recv() {
+                Ok(load) => {
+                    self.review_load = load;
+                    self.review_load_fetch = None;
+                }
+                Err(mpsc::TryRecvError::Disconnected) => self.review_load_fetch = None,
+                Err(mpsc::TryRecvError::Empty) => {}
+            }
+        }
+
+        if let Some(rx) = &self.label_fetch {
+            match rx.try_recv() {
+                Ok(names) => {
+                    let added = self.add_labels(names);
+                    if added > 0 {
+                        self.toasts
+                            .push(ToastLevel::Info, format!("Added {} project labels", added));
+                    }
+                    self.label_fetch = None;
+                }
+                Err(mpsc::TryRecvError::Disconnected) => self.label_fetch = None,
+                Err(mpsc::TryRecvError::Empty) => {}
+            }
+        }
+    }
+
+    /// Names of the selected repositories, in the order they appear in the repo list.
+    pub(crate) fn selected_dirs(&self) -> Vec<String> {
+        let mut indices: Vec<usize> = self.selected_repos.iter().copied().collect();
+        indices.sort_unstable();
+        indices
+            .into_iter()
+            .filter_map(|i| self.dirs.get(i).cloned())
+            .collect()
+    }
+
+    /// The selected repositories the batch runs in, leaving out the skipped ones.
+    pub(crate) fn batch_dirs(&self) -> Vec<String> {
+        self.selected_dirs()
+            .into_iter()
+            .filter(|dir| !self.skipped_dirs.contains(dir))
+            .collect()
+    }
+
+    /// Set running to false to quit the application.
+    pub(crate) fn quit(&mut self) {
+        self.running = false;
+    }
+}
+
+/// The first line of a description, noting how many more there are, to fit on one line.
+fn description_summary(description: &str) -> String {
+    let mut lines = description.lines();
+    let first = lines.next().unwrap_or_default();
+    match lines.count() {
+        0 => first.to_string(),
+        more => format!("{} (+{} lines)", first, more),
+    }
+}
+
+/// Render `items` in as many columns of [`MIN_COLUMN_WIDTH`] as fit into `block`, and in
+/// pages when even those are too few, showing the page with `selected` on it.
+/// Returns how many items fit on a page.
+fn render_columns(
+    items: Vec<ListItem>,
+    selected: usize,
+    block: Block,
+    area: Rect,
+    buf: &mut Buffer,
+) -> usize {
+    let page_size = |block: &Block| {
+        let inner = block.inner(area);
+        inner.height.max(1) as usize * (inner.width / MIN_COLUMN_WIDTH).max(1) as usize
+    };
+    let mut size = page_size(&block);
+    let block = if items.len() > size {
+        // The page number takes the bottom line of borderless blocks
+        size = page_size(&block.clone().title_bottom(""));
+        let pages = items.len().div_ceil(size);
+        let page = (selected / size).min(pages - 1);
+        block.title_bottom(
+            Line::from(format!("Page {}/{} (PgUp/PgDn)", page + 1, pages)).right_aligned(),
+        )
+    } else {
+        block
+    };
+    let inner = block.inner(area);
+    block.render(area, buf);
+
+    let rows = inner.height.max(1) as usize;
+    let page = selected.min(items.len().saturating_sub(1)) / size;
+    let mut items: Vec<ListItem> = items.into_iter().skip(page * size).take(size).collect();
+    let columns = items.len().div_ceil(rows).max(1);
+    let areas =
+        Layout::horizontal(vec![Constraint::Ratio(1, columns as u32); columns]).split(inner);
+    for column in areas.iter() {
+        let rest = items.split_off(rows.min(items.len()));
+        List::new(std::mem::replace(&mut items, rest)).render(*column, buf);
+    }
+    size
+}
+
+/// `index` moved a page of `page_size` forward or back in a list of `len`, stopping at its ends.
+fn page_step(index: usize, len: usize, page_size: usize, forward: bool) -> usize {
+    let step = page_size.max(1);
+    if forward {
+        (index + step).min(len.saturating_sub(1))
+    } else {
+        index.saturating_sub(step)
+    }
+}
+
+/// A horizontally centered rect of `percent_x` width and fixed `height`, for popups.
+fn popup_area(area: Rect, percent_x: u16, height: u16) -> Rect {
+    let [area] = Layout::vertical([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::horizontal([Constraint::Percentage(percent_x)])
+        .flex(Flex::Center)
+        .areas(area);
+    area
+}
+
+/// Whether a repo on `branch` with `status` is clean and on its `default` branch, so there
+/// is nothing to create a merge request from.
+fn nothing_to_commit(branch: &str, default: Option<&str>, status: &RepoStatus) -> bool {
+    branch_detection::is_default(branch, default) && status.clean == Some(true)
+}
+
+/// The trimmed text, `None` if nothing but whitespace was entered.
+fn non_empty(text: &str) -> Option<String> {
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// A seed that differs from run to run, from the randomly keyed hasher of the standard library.
+fn random_seed() -> u64 {
+    use std::hash::{BuildHasher, Hasher};
+    std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish()
+}
+
+/// The numbers `0..n` in an order shuffled by `seed`, the same for the same seed.
+pub(crate) fn shuffled(n: usize, seed: u64) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..n).collect();
+    // xorshift64, never seeded with 0 since it would stay 0
+    let mut state = seed | 1;
+    for i in (1..n).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        order.swap(i, (state % (i as u64 + 1)) as usize);
     }
+    order
 }
@@ -0,0 +1,92 @@
+//! Asks which `[profile.<name>]` of the config to use before the app starts, since the
+//! profile decides which repositories are listed.
+use color_eyre::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::{
+    DefaultTerminal, Frame,
+    layout::{Constraint, Layout},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, List, ListItem, Paragraph},
+};
+
+/// What was picked on the profile screen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ProfilePick {
+    Profile(String),
+    /// Use the config as is, without any profile on top
+    NoProfile,
+    Quit,
+}
+
+/// A list of the profiles in the config, the first entry is "no profile".
+#[derive(Debug)]
+pub(crate) struct ProfilePicker {
+    names: Vec<String>,
+    index: usize,
+}
+
+impl ProfilePicker {
+    pub(crate) fn new(names: Vec<String>) -> Self {
+        Self { names, index: 0 }
+    }
+
+    /// Show the picker until a profile is picked or the user quits.
+    pub(crate) fn run(mut self, terminal: &mut DefaultTerminal) -> Result<ProfilePick> {
+        loop {
+            terminal.draw(|frame| self.render(frame))?;
+            if let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+                && let Some(pick) = self.on_key_event(key)
+            {
+                return Ok(pick);
+            }
+        }
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let [window, footer] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let items: Vec<ListItem> = std::iter::once("No profile")
+            .chain(self.names.iter().map(|n| n.as_str()))
+            .enumerate()
+            .map(|(i, name)| {
+                let mut item = ListItem::new(name.to_string());
+                if i == self.index {
+                    item = item.style(Style::default().fg(Color::Yellow).bg(Color::Blue));
+                }
+                item
+            })
+            .collect();
+        let title = Line::from("Multi MR - Pick Profile")
+            .bold()
+            .blue()
+            .centered();
+        frame.render_widget(
+            List::new(items).block(Block::bordered().title(title)),
+            window,
+        );
+        frame.render_widget(
+            Paragraph::new("↑/↓/j/k: Move  Enter: Use profile  q/Esc: Quit").centered(),
+            footer,
+        );
+    }
+
+    /// Handle a key press, returns the pick once one is made.
+    pub(crate) fn on_key_event(&mut self, key: KeyEvent) -> Option<ProfilePick> {
+        let entries = self.names.len() + 1;
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => self.index = (self.index + 1) % entries,
+            KeyCode::Up | KeyCode::Char('k') => self.index = (self.index + entries - 1) % entries,
+            KeyCode::Enter => {
+                return Some(match self.index.checked_sub(1) {
+                    Some(i) => ProfilePick::Profile(self.names[i].clone()),
+                    None => ProfilePick::NoProfile,
+                });
+            }
+            KeyCode::Esc | KeyCode::Char('q') => return Some(ProfilePick::Quit),
+            _ => {}
+        }
+        None
+    }
+}
@@ -0,0 +1,186 @@
+//! `multimr config prune`: the stale entries of the config, looked up in the background, to
+//! pick which of them are removed from the config file.
+use std::collections::HashSet;
+use std::sync::mpsc;
+
+use color_eyre::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::{
+    DefaultTerminal, Frame,
+    layout::{Constraint, Layout},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, List, ListItem, Paragraph, Wrap},
+};
+
+use super::TICK_RATE;
+use crate::config::Config;
+use crate::prune::{self, Entry, Stale};
+
+/// The prune screen, it only picks the entries, writing them is left to the caller.
+#[derive(Debug)]
+pub(crate) struct Prune {
+    config: Config,
+    stale: Vec<Stale>,
+    errors: Vec<String>,
+    /// Indices into `stale` that are removed, all of them at first
+    picked: HashSet<usize>,
+    index: usize,
+    /// Receives the stale entries, `Some` while looking them up
+    lookup: Option<mpsc::Receiver<(Vec<Stale>, Vec<String>)>>,
+    running: bool,
+    /// Whether `w` was pressed to remove the picked entries
+    confirmed: bool,
+}
+
+impl Prune {
+    pub(crate) fn new(config: Config) -> Self {
+        Self {
+            config,
+            stale: Vec::new(),
+            errors: Vec::new(),
+            picked: HashSet::new(),
+            index: 0,
+            lookup: None,
+            running: true,
+            confirmed: false,
+        }
+    }
+
+    /// Show the stale entries until the user quits, returns those picked for removal, none
+    /// when the user quit without writing.
+    pub(crate) fn run(mut self, terminal: &mut DefaultTerminal) -> Result<Vec<Entry>> {
+        let config = self.config.clone();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(prune::find(&config));
+        });
+        self.lookup = Some(rx);
+        while self.running {
+            terminal.draw(|frame| self.render(frame))?;
+            if event::poll(TICK_RATE)?
+                && let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+            {
+                self.on_key_event(key);
+            }
+            self.poll_lookup();
+        }
+        Ok(self.picked_entries())
+    }
+
+    fn poll_lookup(&mut self) {
+        let Some(rx) = &self.lookup else {
+            return;
+        };
+        match rx.try_recv() {
+            Ok((stale, errors)) => {
+                self.picked = (0..stale.len()).collect();
+                self.stale = stale;
+                self.errors = errors;
+                self.lookup = None;
+            }
+            Err(mpsc::TryRecvError::Disconnected) => self.lookup = None,
+            Err(mpsc::TryRecvError::Empty) => {}
+        }
+    }
+
+    /// The picked entries once confirmed with `w`, in the order they are listed.
+    pub(crate) fn picked_entries(&self) -> Vec<Entry> {
+        if !self.confirmed {
+            return Vec::new();
+        }
+        self.stale
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.picked.contains(i))
+            .map(|(_, stale)| stale.entry.clone())
+            .collect()
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let error_height = if self.errors.is_empty() {
+            0
+        } else {
+            self.errors.len().min(5) as u16 + 2
+        };
+        let [window, errors, footer] = Layout::vertical([
+            Constraint::Min(0),
+            Constraint::Length(error_height),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let mut title = String::from("Multi MR - Prune config");
+        if self.lookup.is_some() {
+            title.push_str(" (looking up...)");
+        }
+        let title = Line::from(title).bold().blue().centered();
+
+        let items: Vec<ListItem> = self
+            .stale
+            .iter()
+            .enumerate()
+            .map(|(i, stale)| {
+                let check = if self.picked.contains(&i) {
+                    "[x]"
+                } else {
+                    "[ ]"
+                };
+                let mut item = ListItem::new(Line::from(vec![
+                    format!("{} {}", check, stale.entry).into(),
+                    format!("  {}", stale.reason).dark_gray(),
+                ]));
+                if i == self.index {
+                    item = item.style(Style::default().bg(Color::Blue));
+                }
+                item
+            })
+            .collect();
+        let list = if self.stale.is_empty() && self.lookup.is_none() {
+            List::new(["Nothing stale found, the config is up to date."])
+        } else {
+            List::new(items)
+        };
+        frame.render_widget(list.block(Block::bordered().title(title)), window);
+
+        if !self.errors.is_empty() {
+            frame.render_widget(
+                Paragraph::new(self.errors.join("\n"))
+                    .wrap(Wrap { trim: false })
+                    .block(
+                        Block::bordered()
+                            .title("Could not check")
+                            .border_style(Style::default().fg(Color::Red)),
+                    ),
+                errors,
+            );
+        }
+        frame.render_widget(
+            Paragraph::new("↑/↓/j/k: Move  Space: Keep/Remove  w: Remove the checked entries  q/Esc: Quit without changes")
+                .centered(),
+            footer,
+        );
+    }
+
+    pub(crate) fn on_key_event(&mut self, key: KeyEvent) {
+        let count = self.stale.len();
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') if count > 0 => {
+                self.index = (self.index + 1) % count
+            }
+            KeyCode::Up | KeyCode::Char('k') if count > 0 => {
+                self.index = (self.index + count - 1) % count;
+            }
+            KeyCode::Char(' ') if count > 0 && !self.picked.remove(&self.index) => {
+                self.picked.insert(self.index);
+            }
+            KeyCode::Char('w') if self.lookup.is_none() => {
+                self.confirmed = true;
+                self.running = false;
+            }
+            KeyCode::Esc | KeyCode::Char('q') => self.running = false,
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,271 @@
+//! Discovers the git repositories in the working directory on background threads, so the
+//! repo list fills in while slower repositories are still being inspected, and the scan can
+//! be stopped with what was found so far.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Instant;
+
+use super::RepoStatus;
+use crate::branch_detection::BranchDetection;
+use crate::config::Config;
+use crate::error::MultimrError;
+use crate::gitlab;
+use crate::utils;
+
+/// A repository found by the scan.
+#[derive(Debug)]
+pub(crate) struct ScannedRepo {
+    pub(crate) dir: String,
+    pub(crate) branch: String,
+    /// Found by the configured [`BranchDetection::default`] command, if any
+    pub(crate) default_branch: Option<String>,
+    pub(crate) status: RepoStatus,
+}
+
+/// What the scan threads report.
+#[derive(Debug)]
+enum Event {
+    /// The roots were listed, with why the ones that couldn't be listed failed
+    Listed { total: usize, errors: Vec<String> },
+    /// A directory is about to be inspected
+    Probing(String),
+    /// A directory was inspected, `None` if it is no git repository
+    Inspected(Option<ScannedRepo>),
+}
+
+/// A scan of the working directory in progress.
+#[derive(Debug)]
+pub(crate) struct RepoScan {
+    events: mpsc::Receiver<Event>,
+    /// Whether the roots have been listed, before that `total` is unknown
+    pub(crate) listed: bool,
+    /// Number of directories in the working directory
+    pub(crate) total: usize,
+    /// Number of directories inspected so far
+    pub(crate) done: usize,
+    /// The directory inspected last, shown next to the progress
+    pub(crate) probing: Option<String>,
+    /// Why roots couldn't be listed, taken by whoever shows them
+    pub(crate) errors: Vec<String>,
+    pub(crate) started_at: Instant,
+    /// Directories selected before a rescan, selected again when they are found
+    pub(crate) reselect: Vec<String>,
+    /// Whether the scan was started from the repo list, reported when it finishes
+    pub(crate) rescan: bool,
+    /// Clean repos on their default branch that were left out because of `skip_clean_repos`
+    pub(crate) skipped_clean: usize,
+}
+
+impl RepoScan {
+    /// List the roots of `config` and inspect the directories in them, spread over as many
+    /// threads as there are cores. Nothing touches the disk on the calling thread, a slow
+    /// network mount only slows down the scan.
+    pub(crate) fn start(config: &Config) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let config = config.clone();
+        thread::spawn(move || {
+            let (dirs, errors) = list(&config);
+            if tx
+                .send(Event::Listed {
+                    total: dirs.len(),
+                    errors,
+                })
+                .is_err()
+            {
+                return;
+            }
+            let threads = thread::available_parallelism()
+                .map_or(4, |n| n.get())
+                .min(dirs.len());
+            for offset in 0..threads {
+                let tx = tx.clone();
+                let working_dir = config.working_dir.clone();
+                let branch_detection = config.branch_detection.clone();
+                let dirs: Vec<String> =
+                    dirs.iter().skip(offset).step_by(threads).cloned().collect();
+                thread::spawn(move || {
+                    for dir in dirs {
+                        // Stops once the scan is dropped, e.g. cancelled
+                        if tx.send(Event::Probing(dir.clone())).is_err()
+                            || tx
+                                .send(Event::Inspected(inspect(
+                                    &working_dir,
+                                    dir,
+                                    &branch_detection,
+                                )))
+                                .is_err()
+                        {
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+        Self::new(rx)
+    }
+
+    /// List the projects of the GitLab `group` instead, each a repo on its default branch
+    /// without a checkout. The GitLab API is asked in the background just the same.
+    pub(crate) fn start_group(config: &Config, group: &str) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let config = config.clone();
+        let group = group.to_string();
+        thread::spawn(move || {
+            let projects: Vec<_> = match gitlab::group_projects(config.glab_binary(), &group) {
+                Ok(projects) => projects
+                    .into_iter()
+                    .filter(|p| config.is_listed(&p.path_with_namespace))
+                    .collect(),
+                Err(e) => {
+                    let _ = tx.send(Event::Listed {
+                        total: 0,
+                        errors: vec![format!("Cannot list the projects of {}: {}", group, e)],
+                    });
+                    return;
+                }
+            };
+            if tx
+                .send(Event::Listed {
+                    total: projects.len(),
+                    errors: Vec::new(),
+                })
+                .is_err()
+            {
+                return;
+            }
+            for project in projects {
+                let repo = ScannedRepo {
+                    dir: project.path_with_namespace,
+                    branch: project.default_branch.unwrap_or_default(),
+                    default_branch: None,
+                    status: RepoStatus::default(),
+                };
+                if tx.send(Event::Inspected(Some(repo))).is_err() {
+                    return;
+                }
+            }
+        });
+        Self::new(rx)
+    }
+
+    fn new(events: mpsc::Receiver<Event>) -> Self {
+        Self {
+            events,
+            listed: false,
+            total: 0,
+            done: 0,
+            probing: None,
+            errors: Vec::new(),
+            started_at: Instant::now(),
+            reselect: Vec::new(),
+            rescan: false,
+            skipped_clean: 0,
+        }
+    }
+
+    /// The repositories found since the last call.
+    pub(crate) fn poll(&mut self) -> Vec<ScannedRepo> {
+        let mut found = Vec::new();
+        loop {
+            match self.events.try_recv() {
+                Ok(Event::Listed { total, errors }) => {
+                    self.listed = true;
+                    self.total = total;
+                    self.errors.extend(errors);
+                }
+                Ok(Event::Probing(dir)) => self.probing = Some(dir),
+                Ok(Event::Inspected(result)) => {
+                    self.done += 1;
+                    found.extend(result);
+                }
+                Err(mpsc::TryRecvError::Empty) => break,
+                // Every thread is done, also if one of them panicked
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.listed = true;
+                    self.done = self.total;
+                    break;
+                }
+            }
+        }
+        found
+    }
+
+    /// Whether every directory has been inspected.
+    pub(crate) fn is_finished(&self) -> bool {
+        self.listed && self.done >= self.total
+    }
+
+    /// How far the scan got, from 0 to 1.
+    pub(crate) fn progress(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.done as f64 / self.total as f64
+        }
+    }
+}
+
+/// The directories in the roots of `config` that are listed, and why the roots that couldn't
+/// be read failed. With several roots the dirs are full paths, `working_dir.join` leaves
+/// those as is.
+fn list(config: &Config) -> (Vec<String>, Vec<String>) {
+    let several_roots = !config.working_dirs.is_empty();
+    let mut dirs = Vec::new();
+    let mut errors = Vec::new();
+    for root in config.roots() {
+        match fs::read_dir(root) {
+            Err(source) => errors.push(
+                MultimrError::Scan {
+                    path: root.to_path_buf(),
+                    source,
+                }
+                .to_string(),
+            ),
+            Ok(entries) => dirs.extend(
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path())
+                    .filter(|path| path.is_dir())
+                    .filter_map(|path| {
+                        let name = path.file_name()?.to_string_lossy().to_string();
+                        if !config.is_listed(&name) {
+                            None
+                        } else if several_roots {
+                            Some(path.to_string_lossy().to_string())
+                        } else {
+                            Some(name)
+                        }
+                    }),
+            ),
+        }
+    }
+    (dirs, errors)
+}
+
+/// Find out whether `dir` is a git repository, and if so its branch and status. Only the top
+/// of a working tree counts: plain directories are left out, also when the working
+/// directory itself is inside a repository.
+fn inspect(
+    working_dir: &Path,
+    dir: String,
+    branch_detection: &BranchDetection,
+) -> Option<ScannedRepo> {
+    let repo_dir: PathBuf = working_dir.join(&dir);
+    // Opening doesn't search the parent directories, unlike `git rev-parse`, but does open
+    // a `.git` directory itself
+    let repo = utils::open_repo(&repo_dir).ok()?;
+    if repo.workdir().and_then(|w| fs::canonicalize(w).ok()) != fs::canonicalize(&repo_dir).ok() {
+        return None;
+    }
+
+    Some(ScannedRepo {
+        branch: branch_detection
+            .current_branch(&repo_dir)
+            .unwrap_or_default(),
+        default_branch: branch_detection.default_branch(&repo_dir).ok().flatten(),
+        status: RepoStatus::scan(&repo_dir),
+        dir,
+    })
+}
@@ -0,0 +1,314 @@
+//! The screens of the wizard as steps, registered in order in [`App::steps`]. A screen
+//! doesn't need to know which one comes before or after it, and optional steps like the
+//! template picker are skipped when they don't apply to the selection.
+use crossterm::event::KeyEvent;
+use ratatui::{buffer::Buffer, layout::Rect};
+
+use super::{App, Screens};
+
+/// One screen of the wizard that leads up to creating the merge requests.
+pub(crate) trait WizardStep: std::fmt::Debug + Sync {
+    /// The screen showing this step.
+    fn screen(&self) -> Screens;
+
+    fn render(&self, app: &mut App, area: Rect, buf: &mut Buffer);
+
+    fn handle_key(&self, app: &mut App, key: KeyEvent);
+
+    /// Why the wizard can't continue past this step yet, if it can't.
+    fn validate(&self, _app: &App) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// What was chosen on this step, shown for the steps already done.
+    fn summary(&self, _app: &App) -> Option<String> {
+        None
+    }
+
+    /// Work started when continuing from this step, before the next one is picked.
+    fn leave(&self, _app: &mut App) {}
+
+    /// Called when the step is reached going forward.
+    fn enter(&self, _app: &mut App) {}
+
+    /// Whether the step is part of the wizard right now, checked after the step before it
+    /// is left.
+    fn enabled(&self, _app: &App) -> bool {
+        true
+    }
+}
+
+/// `n` with `noun`, plural unless there is one.
+fn count(n: usize, noun: &str) -> String {
+    format!("{} {}{}", n, noun, if n == 1 { "" } else { "s" })
+}
+
+#[derive(Debug)]
+pub(crate) struct RepoSelection;
+
+impl WizardStep for RepoSelection {
+    fn screen(&self) -> Screens {
+        Screens::RepoSelection
+    }
+
+    fn render(&self, app: &mut App, area: Rect, buf: &mut Buffer) {
+        app.render_repo_selection(area, buf);
+    }
+
+    fn handle_key(&self, app: &mut App, key: KeyEvent) {
+        app.on_key_event_selection(key);
+    }
+
+    fn validate(&self, app: &App) -> Result<(), String> {
+        if app.selected_repos.is_empty() {
+            return Err("Select at least one repository with Space".to_string());
+        }
+        Ok(())
+    }
+
+    fn summary(&self, app: &App) -> Option<String> {
+        Some(count(app.selected_repos.len(), "repo"))
+    }
+
+    fn leave(&self, app: &mut App) {
+        app.start_label_fetch();
+        app.scan_templates();
+        app.ask_about_uncommitted();
+    }
+}
+
+/// Only shown when one of the selected repos has merge request templates.
+#[derive(Debug)]
+pub(crate) struct Template;
+
+impl WizardStep for Template {
+    fn screen(&self) -> Screens {
+        Screens::Template
+    }
+
+    fn render(&self, app: &mut App, area: Rect, buf: &mut Buffer) {
+        app.render_template(area, buf);
+    }
+
+    fn handle_key(&self, app: &mut App, key: KeyEvent) {
+        app.on_key_event_template(key);
+    }
+
+    fn summary(&self, app: &App) -> Option<String> {
+        Some(
+            app.template
+                .as_ref()
+                .map_or("none".to_string(), |(name, _)| name.clone()),
+        )
+    }
+
+    fn enabled(&self, app: &App) -> bool {
+        !app.templates.is_empty()
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Describe;
+
+impl WizardStep for Describe {
+    fn screen(&self) -> Screens {
+        Screens::CreateMR
+    }
+
+    fn render(&self, app: &mut App, area: Rect, buf: &mut Buffer) {
+        app.render_create_mr(area, buf);
+    }
+
+    fn handle_key(&self, app: &mut App, key: KeyEvent) {
+        app.on_key_event_create_mr(key);
+    }
+
+    fn summary(&self, app: &App) -> Option<String> {
+        Some(app.mr_title.clone()).filter(|title| !title.is_empty())
+    }
+
+    fn leave(&self, app: &mut App) {
+        app.apply_label_preset();
+        app.preselect_last_reviewers();
+        app.start_reviewer_fetch();
+        app.start_review_load_fetch();
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Reviewers;
+
+impl WizardStep for Reviewers {
+    fn screen(&self) -> Screens {
+        Screens::ReviewerSelection
+    }
+
+    fn render(&self, app: &mut App, area: Rect, buf: &mut Buffer) {
+        app.render_reviewer_selection(area, buf);
+    }
+
+    fn handle_key(&self, app: &mut App, key: KeyEvent) {
+        app.on_key_event_select_reviewers(key);
+    }
+
+    fn summary(&self, app: &App) -> Option<String> {
+        Some(format!(
+            "{}, {}",
+            count(app.selected_reviewers.len(), "reviewer"),
+            count(app.selected_assignees.len(), "assignee")
+        ))
+    }
+
+    fn leave(&self, app: &mut App) {
+        app.validate_selection();
+    }
+}
+
+/// Only shown when the batch commits and pushes from local checkouts, the checks need them
+/// and the network.
+#[derive(Debug)]
+pub(crate) struct Preflight;
+
+impl WizardStep for Preflight {
+    fn screen(&self) -> Screens {
+        Screens::Preflight
+    }
+
+    fn render(&self, app: &mut App, area: Rect, buf: &mut Buffer) {
+        app.render_preflight(area, buf);
+    }
+
+    fn handle_key(&self, app: &mut App, key: KeyEvent) {
+        app.on_key_event_preflight(key);
+    }
+
+    fn validate(&self, app: &App) -> Result<(), String> {
+        if app.preflight_run.is_some() {
+            return Err("Wait for the checks to finish".to_string());
+        }
+        let failing = app.preflight_failing_dirs();
+        if !failing.is_empty() && !app.config.force {
+            return Err(format!(
+                "Fix and check again with r, or skip with Space: {}",
+                failing.join(", ")
+            ));
+        }
+        Ok(())
+    }
+
+    fn summary(&self, app: &App) -> Option<String> {
+        let failing = app.preflight_failing_dirs().len();
+        let skipped = app.selected_dirs().len() - app.batch_dirs().len();
+        Some(if failing > 0 {
+            format!("{} failing", count(failing, "repo"))
+        } else if skipped > 0 {
+            format!("{} skipped", count(skipped, "repo"))
+        } else {
+            "all passed".to_string()
+        })
+    }
+
+    fn enter(&self, app: &mut App) {
+        app.preflight_index = 0;
+        app.start_preflight();
+    }
+
+    fn enabled(&self, app: &App) -> bool {
+        app.has_checkouts()
+            && !app.config.no_git
+            && !app.config.update
+            && !app.config.offline
+            && !app.config.tour
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Finalize;
+
+impl WizardStep for Finalize {
+    fn screen(&self) -> Screens {
+        Screens::Finalize
+    }
+
+    fn render(&self, app: &mut App, area: Rect, buf: &mut Buffer) {
+        app.render_overview(area, buf);
+    }
+
+    fn handle_key(&self, app: &mut App, key: KeyEvent) {
+        app.on_key_event_overview(key);
+    }
+
+    fn enter(&self, app: &mut App) {
+        app.show_finalize();
+    }
+}
+
+impl App {
+    /// The steps of the wizard, in order. Steps whose [`WizardStep::enabled`] is false are
+    /// passed over by [`App::next_step`] and [`App::previous_step`].
+    pub(crate) fn steps(&self) -> Vec<&'static dyn WizardStep> {
+        vec![
+            &RepoSelection,
+            &Template,
+            &Describe,
+            &Reviewers,
+            &Preflight,
+            &Finalize,
+        ]
+    }
+
+    /// The step shown on the current screen, `None` outside the wizard.
+    pub(crate) fn current_step(&self) -> Option<&'static dyn WizardStep> {
+        self.steps()
+            .into_iter()
+            .find(|step| step.screen() == self.screen)
+    }
+
+    /// Continue to the next enabled step, if the current one is complete.
+    pub(crate) fn next_step(&mut self) {
+        let steps = self.steps();
+        let Some(current) = steps.iter().position(|s| s.screen() == self.screen) else {
+            return;
+        };
+        if let Err(message) = steps[current].validate(self) {
+            self.toasts.push(super::ToastLevel::Error, message);
+            return;
+        }
+        steps[current].leave(self);
+        if let Some(next) = steps[current + 1..].iter().find(|s| s.enabled(self)) {
+            self.screen = next.screen();
+            next.enter(self);
+        }
+    }
+
+    /// Go back to the previous enabled step.
+    pub(crate) fn previous_step(&mut self) {
+        let steps = self.steps();
+        let Some(current) = steps.iter().position(|s| s.screen() == self.screen) else {
+            return;
+        };
+        if let Some(previous) = steps[..current].iter().rev().find(|s| s.enabled(self)) {
+            self.screen = previous.screen();
+        }
+    }
+
+    /// The enabled steps as `Title: summary`, the summary only for the steps already done,
+    /// with the index of the current one.
+    pub(crate) fn step_trail(&self) -> Option<(Vec<String>, usize)> {
+        let steps: Vec<_> = self
+            .steps()
+            .into_iter()
+            .filter(|s| s.enabled(self) || s.screen() == self.screen)
+            .collect();
+        let current = steps.iter().position(|s| s.screen() == self.screen)?;
+        let trail = steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| match step.summary(self) {
+                Some(summary) if i < current => format!("{}: {}", step.screen().title(), summary),
+                _ => step.screen().title().to_string(),
+            })
+            .collect();
+        Some((trail, current))
+    }
+}
@@ -0,0 +1,173 @@
+//! A multi-line text input with a cursor and word wrap, used for the merge request description.
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    widgets::{Block, Widget},
+};
+
+#[derive(Debug, Default, Clone)]
+pub(crate) struct TextArea {
+    text: String,
+    /// Byte offset of the cursor in `text`, always on a char boundary
+    cursor: usize,
+}
+
+impl TextArea {
+    pub(crate) fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replace the text, putting the cursor at its end.
+    pub(crate) fn set_text(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.cursor = self.text.len();
+    }
+
+    /// Apply an editing key. Returns whether the key was used, so others can be handled elsewhere.
+    pub(crate) fn handle_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => self.insert(c),
+            KeyCode::Enter => self.insert('\n'),
+            KeyCode::Backspace => {
+                if let Some(c) = self.text[..self.cursor].chars().next_back() {
+                    self.cursor -= c.len_utf8();
+                    self.text.remove(self.cursor);
+                }
+            }
+            KeyCode::Delete if self.cursor < self.text.len() => {
+                self.text.remove(self.cursor);
+            }
+            KeyCode::Left => {
+                if let Some(c) = self.text[..self.cursor].chars().next_back() {
+                    self.cursor -= c.len_utf8();
+                }
+            }
+            KeyCode::Right => {
+                if let Some(c) = self.text[self.cursor..].chars().next() {
+                    self.cursor += c.len_utf8();
+                }
+            }
+            KeyCode::Home => self.cursor = self.line_start(self.cursor),
+            KeyCode::End => self.cursor = self.line_end(self.cursor),
+            KeyCode::Up => {
+                let start = self.line_start(self.cursor);
+                if start > 0 {
+                    let column = self.column();
+                    self.cursor = self.offset_in_line(self.line_start(start - 1), column);
+                }
+            }
+            KeyCode::Down => {
+                let end = self.line_end(self.cursor);
+                if end < self.text.len() {
+                    let column = self.column();
+                    self.cursor = self.offset_in_line(end + 1, column);
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn insert(&mut self, c: char) {
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    /// Byte offset of the start of the line containing `offset`.
+    fn line_start(&self, offset: usize) -> usize {
+        self.text[..offset].rfind('\n').map_or(0, |i| i + 1)
+    }
+
+    /// Byte offset of the end of the line containing `offset`, before its newline.
+    fn line_end(&self, offset: usize) -> usize {
+        self.text[offset..]
+            .find('\n')
+            .map_or(self.text.len(), |i| offset + i)
+    }
+
+    /// Number of chars between the start of the cursor's line and the cursor.
+    fn column(&self) -> usize {
+        self.text[self.line_start(self.cursor)..self.cursor]
+            .chars()
+            .count()
+    }
+
+    /// Byte offset of `column` in the line starting at `start`, or its end if the line is shorter.
+    fn offset_in_line(&self, start: usize, column: usize) -> usize {
+        let end = self.line_end(start);
+        self.text[start..end]
+            .char_indices()
+            .nth(column)
+            .map_or(end, |(i, _)| start + i)
+    }
+
+    /// Draw the text word wrapped inside `block`, scrolled so the cursor is visible.
+    /// The cursor is only drawn when `focused`.
+    pub(crate) fn render(&self, area: Rect, buf: &mut Buffer, block: Block, focused: bool) {
+        let inner = block.inner(area);
+        block.render(area, buf);
+        if inner.width == 0 || inner.height == 0 {
+            return;
+        }
+
+        let rows = wrap(&self.text, inner.width as usize);
+        // The cursor sits on the last row that starts at or before it
+        let cursor_row = rows
+            .iter()
+            .rposition(|&(start, _)| start <= self.cursor)
+            .unwrap_or_default();
+        let scroll = (cursor_row + 1).saturating_sub(inner.height as usize);
+
+        for (y, &(start, end)) in rows
+            .iter()
+            .skip(scroll)
+            .take(inner.height as usize)
+            .enumerate()
+        {
+            let line = self.text[start..end].trim_end_matches('\n');
+            buf.set_string(inner.x, inner.y + y as u16, line, Style::default());
+        }
+
+        if focused {
+            let (start, _) = rows[cursor_row];
+            let column = self.text[start..self.cursor].chars().count() as u16;
+            let x = (inner.x + column).min(inner.right() - 1);
+            let y = inner.y + (cursor_row - scroll) as u16;
+            buf[(x, y)].set_style(Style::default().add_modifier(Modifier::REVERSED));
+        }
+    }
+}
+
+/// Split `text` into rows of at most `width` chars, breaking after spaces where possible.
+/// Returns the byte range of each row, rows ending in a newline include it.
+fn wrap(text: &str, width: usize) -> Vec<(usize, usize)> {
+    let mut rows = Vec::new();
+    let mut start = 0;
+    for line in text.split_inclusive('\n') {
+        let line_end = start + line.len();
+        let mut row_start = start;
+        loop {
+            let rest = text[row_start..line_end].trim_end_matches('\n');
+            // Leave room for the cursor behind the last char
+            if rest.chars().count() < width {
+                rows.push((row_start, line_end));
+                break;
+            }
+            let hard_break = rest
+                .char_indices()
+                .nth(width)
+                .map_or(rest.len(), |(i, _)| i);
+            let row_len = rest[..hard_break].rfind(' ').map_or(hard_break, |i| i + 1);
+            rows.push((row_start, row_start + row_len));
+            row_start += row_len;
+        }
+        start = line_end;
+    }
+    // An empty text or one ending in a newline still has a row to type on
+    if text.is_empty() || text.ends_with('\n') {
+        rows.push((text.len(), text.len()));
+    }
+    rows
+}
@@ -0,0 +1,86 @@
+//! Transient notifications shown in the top right corner, for feedback that needs no action.
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::{Block, Clear, Paragraph, Widget},
+};
+
+/// How long a toast stays on screen.
+const TOAST_DURATION: Duration = Duration::from_secs(3);
+/// At most this many toasts are shown at once, older ones are dropped.
+const MAX_TOASTS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ToastLevel {
+    Info,
+    Error,
+}
+
+#[derive(Debug)]
+pub(crate) struct Toast {
+    message: String,
+    level: ToastLevel,
+    shown_at: Instant,
+}
+
+/// The toasts currently on screen, oldest first.
+#[derive(Debug, Default)]
+pub(crate) struct Toasts {
+    toasts: VecDeque<Toast>,
+}
+
+impl Toasts {
+    /// Show a toast, dropping the oldest one if too many are on screen.
+    pub(crate) fn push(&mut self, level: ToastLevel, message: impl Into<String>) {
+        if self.toasts.len() == MAX_TOASTS {
+            self.toasts.pop_front();
+        }
+        self.toasts.push_back(Toast {
+            message: message.into(),
+            level,
+            shown_at: Instant::now(),
+        });
+    }
+
+    /// Remove the toasts that have been shown long enough.
+    pub(crate) fn prune(&mut self) {
+        self.toasts
+            .retain(|toast| toast.shown_at.elapsed() < TOAST_DURATION);
+    }
+
+    /// Remove all toasts, used instead of [`Toasts::prune`] in reduced motion mode.
+    pub(crate) fn clear(&mut self) {
+        self.toasts.clear();
+    }
+
+    /// Stack the toasts in the top right corner of `area`, on top of whatever is drawn there.
+    /// With `colorblind` the level is also shown as a symbol.
+    pub(crate) fn render(&self, area: Rect, buf: &mut Buffer, colorblind: bool) {
+        let mut y = area.y;
+        for toast in &self.toasts {
+            let message = match (colorblind, toast.level) {
+                (false, _) => toast.message.clone(),
+                (true, ToastLevel::Info) => format!("✓ {}", toast.message),
+                (true, ToastLevel::Error) => format!("✗ {}", toast.message),
+            };
+            let width = (message.chars().count() as u16 + 4).min(area.width);
+            if y + 3 > area.bottom() {
+                break;
+            }
+            let toast_area = Rect::new(area.right() - width, y, width, 3);
+            let color = match toast.level {
+                ToastLevel::Info => Color::Green,
+                ToastLevel::Error => Color::Red,
+            };
+            Clear.render(toast_area, buf);
+            Paragraph::new(message)
+                .block(Block::bordered().border_style(Style::default().fg(color)))
+                .render(toast_area, buf);
+            y += 3;
+        }
+    }
+}
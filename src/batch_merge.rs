@@ -0,0 +1,211 @@
+//! `multimr merge`: approve and merge my open merge requests across the workspace in bulk,
+//! once their pipelines passed. `A` and `M` on the results screen do the same for the
+//! merge requests of the batch that was just created.
+use std::path::PathBuf;
+use std::process;
+
+use crate::config::Config;
+use crate::digest;
+use crate::error::Result;
+use crate::gitlab::{self, ListedMergeRequest, Project};
+use crate::utils;
+
+/// An open merge request that might be approved or merged.
+#[derive(Debug, Clone)]
+pub(crate) struct Candidate {
+    /// The directory of its repo, as listed in the TUI
+    pub(crate) dir: String,
+    pub(crate) repo_dir: PathBuf,
+    /// The project path, or the directory when the remote isn't recognized
+    pub(crate) name: String,
+    pub(crate) glab: String,
+    /// Passed to glab as `--repo`, glab finds the project from `repo_dir` otherwise
+    pub(crate) project: Option<Project>,
+    pub(crate) mr: ListedMergeRequest,
+}
+
+impl Candidate {
+    /// Whether it can be merged: it is no draft and its latest pipeline passed, if it has one.
+    pub(crate) fn is_ready(&self) -> bool {
+        !self.mr.draft
+            && self
+                .mr
+                .head_pipeline
+                .as_ref()
+                .is_none_or(|pipeline| pipeline.status == "success")
+    }
+
+    /// Why it is or isn't ready, for the list shown before merging.
+    pub(crate) fn status(&self) -> String {
+        match &self.mr.head_pipeline {
+            _ if self.mr.draft => "draft".to_string(),
+            Some(pipeline) => format!("pipeline {}", pipeline.status),
+            None => "no pipeline".to_string(),
+        }
+    }
+
+    /// The glab commands that approve and/or merge it.
+    pub(crate) fn commands(&self, approve: bool, merge: bool) -> Vec<process::Command> {
+        let glab_mr = |action: &str| {
+            let mut cmd = process::Command::new(&self.glab);
+            cmd.arg("mr").arg(action).arg(self.mr.iid.to_string());
+            if let Some(project) = &self.project {
+                cmd.arg("--repo").arg(project.repo_arg());
+            }
+            cmd
+        };
+        let mut commands = Vec::new();
+        if approve {
+            commands.push(glab_mr("approve"));
+        }
+        if merge {
+            let mut cmd = glab_mr("merge");
+            // There is no terminal to answer glab's confirmation prompt
+            cmd.arg("--yes");
+            commands.push(cmd);
+        }
+        commands
+    }
+
+    /// Approve and/or merge it, appending the output of glab to `log`.
+    /// Stops at the first command that fails.
+    pub(crate) fn run(&self, approve: bool, merge: bool, log: &mut String) -> Result<()> {
+        for mut cmd in self.commands(approve, merge) {
+            if self.repo_dir.is_dir() {
+                cmd.current_dir(&self.repo_dir);
+            }
+            utils::run_checked(&mut cmd, log)?;
+        }
+        Ok(())
+    }
+}
+
+/// Look up merge request `iid` with its latest pipeline.
+pub(crate) fn fetch(
+    dir: &str,
+    repo_dir: PathBuf,
+    glab: &str,
+    project: Option<Project>,
+    iid: u64,
+) -> Result<Candidate> {
+    let mr = gitlab::merge_request(glab, &repo_dir, project.as_ref(), iid)?;
+    Ok(Candidate {
+        dir: dir.to_string(),
+        name: project.as_ref().map_or(dir.to_string(), |p| p.path.clone()),
+        repo_dir,
+        glab: glab.to_string(),
+        project,
+        mr,
+    })
+}
+
+/// My open merge requests in the repos of the workspace, see [`digest::workspace_repos`],
+/// with their latest pipelines. Also returns why repos or merge requests could not be
+/// looked up.
+pub(crate) fn collect(config: &Config) -> (Vec<Candidate>, Vec<String>) {
+    if let Some(group) = &config.group {
+        return collect_group(config, group);
+    }
+    let (repos, mut errors) = digest::workspace_repos(config);
+    let mut candidates = Vec::new();
+    for repo in repos {
+        let glab = config.glab_for(&repo.dir);
+        let mrs = match gitlab::my_open_merge_requests(glab, &repo.path) {
+            Ok(mrs) => mrs,
+            Err(e) => {
+                errors.push(format!("{}: {}", repo.dir, e));
+                continue;
+            }
+        };
+        for listed in mrs {
+            match gitlab::merge_request(glab, &repo.path, None, listed.iid) {
+                Ok(mr) => candidates.push(Candidate {
+                    dir: repo.dir.clone(),
+                    repo_dir: repo.path.clone(),
+                    name: repo.project.clone(),
+                    glab: glab.to_string(),
+                    project: None,
+                    mr,
+                }),
+                Err(e) => errors.push(format!("{}!{}: {}", repo.project, listed.iid, e)),
+            }
+        }
+    }
+    (candidates, errors)
+}
+
+/// My open merge requests in the projects of the `--group`, looked up through the API only.
+fn collect_group(config: &Config, group: &str) -> (Vec<Candidate>, Vec<String>) {
+    let glab = config.glab_binary();
+    let listed = match gitlab::my_open_group_merge_requests(glab, group) {
+        Ok(listed) => listed,
+        Err(e) => return (Vec::new(), vec![format!("{}: {}", group, e)]),
+    };
+    let mut candidates = Vec::new();
+    let mut errors = Vec::new();
+    for listed in listed {
+        // The project is only named in the URL of a merge request listed for a group
+        let project = listed
+            .web_url
+            .split("/-/")
+            .next()
+            .and_then(Project::from_remote_url);
+        let Some(project) = project else {
+            errors.push(format!("{}: no project in {}", group, listed.web_url));
+            continue;
+        };
+        let repo_dir = config.working_dir.join(&project.path);
+        match fetch(
+            &project.path.clone(),
+            repo_dir,
+            glab,
+            Some(project),
+            listed.iid,
+        ) {
+            Ok(candidate) => candidates.push(candidate),
+            Err(e) => errors.push(format!("{}: {}", listed.web_url, e)),
+        }
+    }
+    (candidates, errors)
+}
+
+/// What came of approving and merging several merge requests.
+#[derive(Debug, Default)]
+pub(crate) struct Outcome {
+    /// The directories whose merge request was approved and/or merged
+    pub(crate) done: Vec<String>,
+    /// Why merge requests were left alone, by directory
+    pub(crate) not_ready: Vec<String>,
+    /// Why looking up, approving or merging failed, by directory
+    pub(crate) failed: Vec<String>,
+}
+
+/// Approve and/or merge those of the looked up merge requests that are ready.
+pub(crate) fn run_ready(
+    candidates: Vec<(String, Result<Candidate>)>,
+    approve: bool,
+    merge: bool,
+) -> Outcome {
+    let mut outcome = Outcome::default();
+    for (dir, candidate) in candidates {
+        let candidate = match candidate {
+            Ok(candidate) => candidate,
+            Err(e) => {
+                outcome.failed.push(format!("{}: {}", dir, e));
+                continue;
+            }
+        };
+        if !candidate.is_ready() {
+            outcome
+                .not_ready
+                .push(format!("{} ({})", candidate.dir, candidate.status()));
+            continue;
+        }
+        let mut log = String::new();
+        match candidate.run(approve, merge, &mut log) {
+            Ok(()) => outcome.done.push(candidate.dir),
+            Err(e) => outcome.failed.push(format!("{}: {}", candidate.dir, e)),
+        }
+    }
+    outcome
+}
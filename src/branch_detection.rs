@@ -0,0 +1,60 @@
+//! How the current and the default branch of a repo are found out: with git by default, or
+//! with configured shell commands for checkouts git can't read, like jj or Sapling working
+//! copies that still push to GitLab.
+use std::path::Path;
+use std::process;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::DEFAULT_BRANCHES;
+use crate::error::Result;
+use crate::utils;
+
+/// The `[branch_detection]` of the config, git and `main`/`master` when not set.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct BranchDetection {
+    /// Shell command printing the branch the repo is on, e.g. `jj log -r @- --no-graph -T bookmarks`
+    pub current: Option<String>,
+    /// Shell command printing the default branch of the repo, instead of assuming `main` or `master`
+    pub default: Option<String>,
+}
+
+impl BranchDetection {
+    /// The branch `repo_dir` is on, empty for a detached HEAD.
+    pub(crate) fn current_branch(&self, repo_dir: &Path) -> Result<String> {
+        match &self.current {
+            Some(command) => run_shell(command, repo_dir),
+            None => utils::get_current_branch(repo_dir),
+        }
+    }
+
+    /// The default branch of `repo_dir`, `None` when no command is configured for it.
+    pub(crate) fn default_branch(&self, repo_dir: &Path) -> Result<Option<String>> {
+        match &self.default {
+            Some(command) => Ok(Some(run_shell(command, repo_dir)?).filter(|b| !b.is_empty())),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Whether `branch` is the `default` branch, or one of [`DEFAULT_BRANCHES`] when it is unknown.
+pub(crate) fn is_default(branch: &str, default: Option<&str>) -> bool {
+    match default {
+        Some(default) => branch == default,
+        None => DEFAULT_BRANCHES.contains(&branch),
+    }
+}
+
+/// Run `command` with `sh` in `repo_dir` and return the first line it printed.
+fn run_shell(command: &str, repo_dir: &Path) -> Result<String> {
+    let mut cmd = process::Command::new("sh");
+    cmd.arg("-c").arg(command).current_dir(repo_dir);
+    let output = utils::run_output(&mut cmd)?;
+    Ok(String::from_utf8_lossy(&output)
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_string())
+}
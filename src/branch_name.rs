@@ -0,0 +1,95 @@
+//! Names of the branches created for new merge requests, rendered from `branch_template`.
+use deunicode::deunicode;
+
+use crate::runs;
+
+/// Template used when none is configured, just the slugified title.
+pub(crate) const DEFAULT_TEMPLATE: &str = "{slug}";
+/// Slugs are cut to this many characters, so branch names stay readable.
+pub(crate) const MAX_SLUG_LEN: usize = 50;
+/// `{type}` when the title has no conventional commit type like `fix:`.
+const DEFAULT_TYPE: &str = "feature";
+
+/// Turn text into a lowercase ASCII slug of words separated by dashes, transliterating
+/// unicode (`Überprüfung` becomes `uberprufung`) and cut at [`MAX_SLUG_LEN`] on a word boundary.
+pub(crate) fn slugify(text: &str) -> String {
+    let ascii = deunicode(text).to_lowercase();
+    let words = ascii
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty());
+
+    let mut slug = String::new();
+    for word in words {
+        let separator = usize::from(!slug.is_empty());
+        if slug.len() + separator + word.len() > MAX_SLUG_LEN {
+            if slug.is_empty() {
+                slug.push_str(&word[..MAX_SLUG_LEN]);
+            }
+            break;
+        }
+        if separator == 1 {
+            slug.push('-');
+        }
+        slug.push_str(word);
+    }
+    slug
+}
+
+/// Split a conventional commit type off a title: `fix(api)!: Handle nulls` gives
+/// `(Some("fix"), "Handle nulls")`. Titles without one are returned as is.
+pub(crate) fn split_type(title: &str) -> (Option<&str>, &str) {
+    let Some((prefix, rest)) = title.split_once(':') else {
+        return (None, title);
+    };
+    let kind = prefix.split(['(', '!']).next().unwrap_or_default().trim();
+    if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_lowercase()) {
+        return (None, title);
+    }
+    (Some(kind), rest.trim())
+}
+
+/// Render a branch name from `template`, filling in `{slug}` (the title without its type),
+/// `{type}` (its conventional commit type, `feature` if it has none) and `{user}`.
+/// Titles without a letter or digit to slugify, like `!!!` or `— ?`, get the slug
+/// `multimr-YYYYMMDD` of the day `now` (seconds since the Unix epoch) falls on.
+pub(crate) fn render(template: &str, title: &str, user: &str, now: u64) -> String {
+    let (kind, rest) = split_type(title);
+    let mut slug = slugify(rest);
+    if slug.is_empty() {
+        slug = format!("multimr-{}", &runs::run_id(now)[..8]);
+    }
+    let name = template
+        .replace("{slug}", &slug)
+        .replace("{type}", &slugify(kind.unwrap_or(DEFAULT_TYPE)))
+        .replace("{user}", &slugify(user));
+    // Placeholders that were empty must not leave `//` or a leading `/` behind
+    name.split('/')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Whether git accepts `name` as a branch name, following the rules of `git check-ref-format`.
+pub(crate) fn is_valid(name: &str) -> bool {
+    !name.is_empty()
+        && name != "@"
+        && !name.starts_with(['/', '-', '.'])
+        && !name.ends_with(['/', '.'])
+        && !name.ends_with(".lock")
+        && !name.contains("..")
+        && !name.contains("//")
+        && !name.contains("@{")
+        && !name.contains("/.")
+        && !name
+            .chars()
+            .any(|c| c.is_ascii_control() || c.is_whitespace() || "~^:?*[\\".contains(c))
+}
+
+/// The user for `{user}`: the configured assignee, or the login name.
+pub(crate) fn current_user(assignee: Option<&str>) -> String {
+    assignee
+        .map(str::to_string)
+        .or_else(|| std::env::var("USER").ok())
+        .or_else(|| std::env::var("USERNAME").ok())
+        .unwrap_or_default()
+}
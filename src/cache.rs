@@ -0,0 +1,42 @@
+//! A small on-disk JSON cache for slow GitLab API responses.
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// Directory holding the cache files, following the XDG base directory spec.
+pub(crate) fn cache_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .map(|dir| dir.join("multimr"))
+}
+
+/// Build a cache key that is unique per repository on disk.
+pub(crate) fn repo_key(kind: &str, repo_dir: &Path) -> String {
+    let path: String = repo_dir
+        .to_string_lossy()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("{}-{}", kind, path)
+}
+
+/// Read a cached value, `None` if it was never stored or cannot be parsed anymore.
+pub(crate) fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let content = std::fs::read_to_string(cache_dir()?.join(format!("{}.json", key))).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Store a value in the cache, failures are ignored since the cache is only an optimization.
+pub(crate) fn store<T: Serialize>(key: &str, value: &T) {
+    let Some(dir) = cache_dir() else {
+        return;
+    };
+    if std::fs::create_dir_all(&dir).is_err() {
+        return;
+    }
+    if let Ok(content) = serde_json::to_string(value) {
+        let _ = std::fs::write(dir.join(format!("{}.json", key)), content);
+    }
+}
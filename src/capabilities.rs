@@ -0,0 +1,218 @@
+//! Which optional integrations are active for this run, and why the others are not,
+//! so "why didn't X happen" can be answered from inside the app.
+use crate::config::{Config, LabelFetch};
+use crate::hyperlinks;
+use crate::notification;
+use crate::utils;
+
+/// Environment variables glab reads a GitLab token from, before its own login.
+const TOKEN_VARIABLES: [&str; 3] = ["GITLAB_TOKEN", "GITLAB_ACCESS_TOKEN", "OAUTH_TOKEN"];
+
+/// An optional integration and whether it is active.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Capability {
+    pub(crate) name: &'static str,
+    pub(crate) active: bool,
+    /// How it is set up when active, why it is not otherwise
+    pub(crate) reason: String,
+}
+
+impl Capability {
+    fn new(name: &'static str, active: bool, reason: impl Into<String>) -> Self {
+        Self {
+            name,
+            active,
+            reason: reason.into(),
+        }
+    }
+}
+
+/// The capabilities of a run with `config`. `clipboard` is the outcome of connecting to the
+/// system clipboard, which can only be found out by trying.
+pub(crate) fn detect(config: &Config, clipboard: Result<(), String>) -> Vec<Capability> {
+    let has_checkouts = config.group.is_none();
+    let mut capabilities = vec![Capability::new(
+        "Backend",
+        true,
+        match &config.group {
+            Some(group) => format!(
+                "GitLab through `{}`, projects of group {} without checkouts",
+                config.glab_binary(),
+                group
+            ),
+            None => format!(
+                "GitLab through `{}`, checkouts in {}",
+                config.glab_binary(),
+                config.working_dir.display()
+            ),
+        },
+    )];
+
+    let token = TOKEN_VARIABLES
+        .into_iter()
+        .find(|name| std::env::var(name).is_ok_and(|v| !v.is_empty()));
+    capabilities.push(match token {
+        Some(name) => Capability::new("API token", true, format!("{} is set", name)),
+        None => Capability::new(
+            "API token",
+            false,
+            "no GITLAB_TOKEN in the environment, glab uses its `glab auth login` session",
+        ),
+    });
+
+    capabilities.push(if config.offline {
+        Capability::new(
+            "Offline mode",
+            true,
+            "pushing and creating MRs is queued for `multimr flush`",
+        )
+    } else {
+        Capability::new(
+            "Offline mode",
+            false,
+            "only used for hosts that cannot be reached when the batch starts",
+        )
+    });
+
+    let checkouts_reason = "needs local checkouts, not available in group mode";
+    capabilities.push(if !config.fetch_reviewers {
+        Capability::new("Fetch reviewers", false, "fetch_reviewers = false")
+    } else if !has_checkouts {
+        Capability::new("Fetch reviewers", false, checkouts_reason)
+    } else {
+        Capability::new(
+            "Fetch reviewers",
+            true,
+            "project members are offered as reviewers",
+        )
+    });
+    capabilities.push(match config.fetch_labels {
+        LabelFetch::Off => Capability::new("Fetch labels", false, "fetch_labels = \"off\""),
+        _ if !has_checkouts => Capability::new("Fetch labels", false, checkouts_reason),
+        mode => Capability::new(
+            "Fetch labels",
+            true,
+            format!("{:?} of the project labels", mode).to_lowercase(),
+        ),
+    });
+
+    let mut report = Vec::new();
+    if config.report_snippet {
+        report.push("as a private snippet".to_string());
+    }
+    if let Some(issue) = &config.report_issue {
+        report.push(format!("on issue {}", issue));
+    }
+    capabilities.push(if report.is_empty() {
+        Capability::new(
+            "Run report",
+            false,
+            "neither report_snippet nor report_issue is configured",
+        )
+    } else {
+        Capability::new("Run report", true, report.join(" and "))
+    });
+
+    let mut umbrella = Vec::new();
+    if let Some(project) = &config.umbrella_issue {
+        umbrella.push(format!("issue in {}", project));
+    }
+    if let Some(group) = &config.umbrella_epic {
+        umbrella.push(format!("epic in {}", group));
+    }
+    capabilities.push(if umbrella.is_empty() {
+        Capability::new(
+            "Umbrella",
+            false,
+            "neither umbrella_issue nor umbrella_epic is configured",
+        )
+    } else {
+        Capability::new("Umbrella", true, umbrella.join(" and "))
+    });
+
+    capabilities.push(match config.hyperlinks {
+        Some(true) => Capability::new("Hyperlinks", true, "hyperlinks = true"),
+        Some(false) => Capability::new("Hyperlinks", false, "hyperlinks = false"),
+        None if hyperlinks::supported(|name| std::env::var(name).ok()) => Capability::new(
+            "Hyperlinks",
+            true,
+            "the terminal supports OSC 8 links, MR URLs are clickable",
+        ),
+        None => Capability::new(
+            "Hyperlinks",
+            false,
+            "the terminal isn't known to support OSC 8 links, set hyperlinks = true to force",
+        ),
+    });
+
+    capabilities.push(if config.label_rules.is_empty() {
+        Capability::new("Label rules", false, "no [label_rules] configured")
+    } else {
+        Capability::new(
+            "Label rules",
+            true,
+            format!("{} configured", config.label_rules.len()),
+        )
+    });
+
+    capabilities.push(if config.ticket_links.is_empty() {
+        Capability::new("Ticket links", false, "no [ticket_links] configured")
+    } else {
+        let mut prefixes: Vec<&str> = config.ticket_links.keys().map(String::as_str).collect();
+        prefixes.sort_unstable();
+        Capability::new(
+            "Ticket links",
+            true,
+            format!("{} IDs are linked to the tracker", prefixes.join(", ")),
+        )
+    });
+
+    let mut posts = Vec::new();
+    if config.merge_queue.is_some() {
+        posts.push("merge_queue");
+    }
+    if config.announce_webhook.is_some() {
+        posts.push("announce_webhook");
+    }
+    capabilities.push(if posts.is_empty() {
+        Capability::new(
+            "curl",
+            false,
+            "not needed, neither merge_queue nor announce_webhook is configured",
+        )
+    } else if utils::on_path("curl") {
+        Capability::new("curl", true, format!("POSTs {}", posts.join(" and ")))
+    } else {
+        Capability::new(
+            "curl",
+            false,
+            format!(
+                "not found on the PATH, {} cannot be sent",
+                posts.join(" and ")
+            ),
+        )
+    });
+
+    let notifications = notification::available(|name| std::env::var(name).ok());
+    capabilities.push(match (config.desktop_notification, notifications) {
+        (false, _) => Capability::new(
+            "Desktop notification",
+            false,
+            "desktop_notification = false",
+        ),
+        (true, true) => {
+            Capability::new("Desktop notification", true, "shown when a batch finishes")
+        }
+        (true, false) => Capability::new(
+            "Desktop notification",
+            false,
+            "desktop_notification = true, but there is no D-Bus session bus to show it on",
+        ),
+    });
+
+    capabilities.push(match clipboard {
+        Ok(()) => Capability::new("Clipboard", true, "copying URLs and scripts works"),
+        Err(e) => Capability::new("Clipboard", false, e),
+    });
+    capabilities
+}
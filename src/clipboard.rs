@@ -0,0 +1,35 @@
+//! Copies text to the system clipboard.
+use std::fmt;
+
+/// Lazily connects to the system clipboard. The connection is kept open since on some
+/// platforms the copied text is only available while its owner is alive.
+#[derive(Default)]
+pub(crate) struct Clipboard {
+    inner: Option<arboard::Clipboard>,
+}
+
+impl fmt::Debug for Clipboard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Clipboard")
+            .field("connected", &self.inner.is_some())
+            .finish()
+    }
+}
+
+impl Clipboard {
+    /// Connect to the system clipboard if not connected yet, to find out whether copying works.
+    pub(crate) fn connect(&mut self) -> Result<(), arboard::Error> {
+        if self.inner.is_none() {
+            self.inner = Some(arboard::Clipboard::new()?);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn copy(&mut self, text: impl Into<String>) -> Result<(), arboard::Error> {
+        let clipboard = match &mut self.inner {
+            Some(clipboard) => clipboard,
+            None => self.inner.insert(arboard::Clipboard::new()?),
+        };
+        clipboard.set_text(text.into())
+    }
+}
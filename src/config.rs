@@ -1,72 +1,875 @@
 //! Handles loading the configuration for the multimr application from a TOML file .
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+
+use crate::branch_detection::BranchDetection;
+use crate::error::{MultimrError, Result};
+use crate::limits::Limits;
+use crate::merge_queue::MergeQueue;
+use crate::post_merge::PostMerge;
+use crate::utils;
 
 pub(crate) const CONFIG_FILE: &str = "multimr.toml";
+/// Name of the global configuration file in the config directory, see [`utils::config_dir`].
+pub(crate) const GLOBAL_CONFIG_FILE: &str = "config.toml";
+/// The table holding the `[profile.<name>]` sections.
+const PROFILES: &str = "profile";
+/// Prefix of the environment variables that override settings, e.g. `MULTIMR_ASSIGNEE`.
+pub(crate) const ENV_PREFIX: &str = "MULTIMR_";
+/// Separates the keys of nested settings in a variable name, e.g. `MULTIMR_LIMITS__PARALLEL`.
+const ENV_NESTING: &str = "__";
+/// Variables that pick which config is loaded, so they are read before it, see [`env_var`].
+const ENV_SELECTORS: [&str; 2] = ["CONFIG", "PROFILE"];
 pub(crate) const DEFAULT_BRANCHES: [&str; 2] = ["main", "master"];
+/// The glab executable used unless another one is configured, looked up on the PATH.
+pub(crate) const DEFAULT_GLAB: &str = "glab";
+/// Default for [`Config::confirm_threshold`] when it is not in the TOML file.
+pub(crate) const DEFAULT_CONFIRM_THRESHOLD: usize = 10;
+/// Default for [`Config::max_changed_lines`] when it is not in the TOML file.
+pub(crate) const DEFAULT_MAX_CHANGED_LINES: usize = 5000;
+/// Default for [`Config::suspicious_paths`] when it is not in the TOML file.
+pub(crate) const DEFAULT_SUSPICIOUS_PATHS: [&str; 4] =
+    ["target/", "node_modules/", "*.log", ".env"];
 
 /// Total Configuration for the application.
 /// First read from a `multimr.toml` file, then overwritten with optional cli args.
-#[derive(Debug, Default, Clone)]
+/// Serializes to the settings as they would be written in the file, for `multimr config check`.
+#[derive(Debug, Default, Clone, Serialize)]
 pub(crate) struct Config {
     pub working_dir: PathBuf,
+    /// Every root directory when several are configured, `working_dir` is the first of them.
+    /// Empty when only `working_dir` is scanned.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub working_dirs: Vec<PathBuf>,
     pub reviewers: Vec<String>,
+    /// Usernames never offered or added as reviewers, e.g. bots or people who left the team.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub never_review: Vec<String>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
     pub labels: HashMap<String, String>,
+    /// Bundles of labels, reviewers and a milestone, picked as one entry in the label picker.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub label_presets: HashMap<String, LabelPreset>,
+    /// Labels and reviewers added whenever the label they are keyed by is set.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub label_rules: HashMap<String, LabelRule>,
+    /// Issue tracker URLs keyed by ticket prefix, e.g. `ABC = "https://jira.example.com/browse/{id}"`.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub ticket_links: HashMap<String, String>,
     pub assignee: Option<String>,
+    /// Assigned next to `assignee`, from repeating `--assignee` on the command line.
+    #[serde(skip)]
+    pub assignees: Vec<String>,
+    /// Selected on the reviewer screen, from repeating `--reviewer` on the command line.
+    #[serde(skip)]
+    pub preselected_reviewers: Vec<String>,
     /// Is this a dry run? If true, no merge requests will be created.
+    #[serde(skip)]
     pub dry_run: bool,
+    /// Per-repo settings keyed by directory name, taking precedence over the global ones.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub repo_overrides: HashMap<String, RepoOverride>,
+    /// Add the members of the selected projects to the configured reviewers.
+    pub fetch_reviewers: bool,
+    /// Switch each repo back to the branch it was on once its merge request is created.
+    pub restore_branch: bool,
+    /// How the labels of the selected projects are added to the label picker.
+    pub fetch_labels: LabelFetch,
+    /// The order of the label picker.
+    pub label_order: LabelOrder,
+    /// Create the first merge request only, and wait for confirmation before doing the rest.
+    pub canary: bool,
+    /// Due date prefilled in the advanced options, a date or an offset like `+3 days`.
+    pub due_date: Option<String>,
+    /// Milestone set on merge requests with a due date, unless another milestone is chosen.
+    pub due_date_milestone: Option<String>,
+    /// Batches of more repos than this have to be confirmed by typing, 0 disables this.
+    pub confirm_threshold: usize,
+    /// Warn when the selected repos commit more changed lines than this together, 0 disables this.
+    pub max_changed_lines: usize,
+    /// Paths that probably shouldn't be committed, warned about when `git add .` would pick
+    /// them up. `dir/` matches a directory anywhere, other patterns file names or paths.
+    pub suspicious_paths: Vec<String>,
+    /// Squash the commits when the merge requests are merged, preset in the advanced options.
+    pub squash: bool,
+    /// Delete the source branches when the merge requests are merged, preset in the
+    /// advanced options.
+    pub remove_source_branch: bool,
+    /// Merge each merge request as soon as its pipeline succeeds, preset in the advanced options.
+    pub auto_merge: bool,
+    /// Who the merge requests are assigned to.
+    pub assignee_mode: AssigneeMode,
+    /// Whether every merge request gets all picked reviewers or a share of them.
+    pub reviewer_strategy: ReviewerStrategy,
+    /// Name of the branches created for the merge requests, e.g. `{user}/{type}/{slug}`.
+    pub branch_template: Option<String>,
+    /// Work on the projects of this GitLab group through the API, without local checkouts.
+    pub group: Option<String>,
+    /// Publish the run report as a private personal snippet once the batch finishes.
+    pub report_snippet: bool,
+    /// Add the run report as a comment to this issue, e.g. `group/project#42`.
+    pub report_issue: Option<String>,
+    /// Create an issue in this project listing the batch's merge requests as checkboxes.
+    pub umbrella_issue: Option<String>,
+    /// Create an epic in this group listing the batch's merge requests as checkboxes.
+    pub umbrella_epic: Option<String>,
+    /// Slack or Mattermost incoming webhook the created merge requests are announced to.
+    pub announce_webhook: Option<String>,
+    /// Keep the screen still: nothing appears or disappears on a timer, only on input or progress.
+    pub reduced_motion: bool,
+    /// Mark states with symbols and text styles besides their color, so none is told by hue alone.
+    pub colorblind: bool,
+    /// Show a desktop notification with the number of succeeded and failed repos once the
+    /// batch finishes.
+    pub desktop_notification: bool,
+    /// Make MR URLs and repo names clickable with OSC 8 hyperlinks, detected from the terminal
+    /// when not set.
+    pub hyperlinks: Option<bool>,
+    /// Path of the glab executable, or a wrapper script around it, instead of `glab` on the PATH.
+    pub glab: Option<String>,
+    /// Leave repos without uncommitted changes that are on their default branch out of the list.
+    pub skip_clean_repos: bool,
+    /// Write the plan to this shell script instead of running it.
+    #[serde(skip)]
+    pub emit_script: Option<PathBuf>,
+    /// Write the resolved batch to this JSON file instead of running it, see `multimr plan`.
+    #[serde(skip)]
+    pub emit_plan: Option<PathBuf>,
+    /// Commit locally and queue pushing and creating the merge requests for `multimr flush`,
+    /// without checking whether GitLab can be reached.
+    pub offline: bool,
+    /// Never branch, stage, commit or push: only open merge requests for branches that
+    /// already exist on the remote, failing the repos that aren't on one.
+    pub no_git: bool,
+    /// Keep looking up the pipelines of the created merge requests on the results screen
+    /// until all of them passed or failed.
+    pub watch_pipelines: bool,
+    /// Update the open merge requests of the repos' current branches instead of creating new ones.
+    #[serde(skip)]
+    pub update: bool,
+    /// Confirm the batch even when the pre-flight checks found blockers.
+    #[serde(skip)]
+    pub force: bool,
+    /// How many repos, subprocesses and git hooks run at once, and at which priority.
+    pub limits: Limits,
+    /// Commands finding out the current and the default branch instead of git.
+    pub branch_detection: BranchDetection,
+    /// Where to enqueue the created merge requests once the batch finishes, if anywhere.
+    pub merge_queue: Option<MergeQueue>,
+    /// What `multimr post-merge` does to the local repos once their merge requests are merged.
+    pub post_merge: PostMerge,
+    /// The `[profile.<name>]` whose settings were applied over the rest of the config.
+    #[serde(skip)]
+    pub profile: Option<String>,
+    /// Shell commands run in each repo after its merge request is created, with `{iid}`,
+    /// `{url}`, `{repo}` and `{project}` filled in.
+    pub post_create: Vec<String>,
+    /// Named sets of repos that are selected together, e.g. `backend = ["api", "worker"]`.
+    #[serde(rename = "groups")]
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub repo_groups: HashMap<String, Vec<String>>,
+    /// Name of the repo group selected on start.
+    #[serde(skip)]
+    pub repo_group: Option<String>,
+    /// Only list directories matching one of these glob patterns, all of them if empty.
+    pub include: Vec<String>,
+    /// Never list directories matching one of these glob patterns, even if included.
+    pub exclude: Vec<String>,
+    /// Explain every screen in a panel below it, set by `multimr tour`.
+    #[serde(skip)]
+    pub tour: bool,
+}
+
+/// How the picked reviewers are spread over the merge requests of a batch.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ReviewerStrategy {
+    /// Every merge request gets all picked reviewers.
+    #[default]
+    All,
+    /// Each merge request gets one of them, in turn.
+    RoundRobin,
+    /// Each merge request gets one of them, in an order shuffled once per session.
+    Random,
+}
+
+/// How the assignee of each merge request is chosen.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum AssigneeMode {
+    /// The configured `assignee` for every repo.
+    #[default]
+    Fixed,
+    /// The first maintainer of each repo's project, for repos owned by someone else.
+    Maintainer,
+}
+
+/// Which labels of the selected GitLab projects are offered in the label picker.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum LabelFetch {
+    /// Only offer the labels from `multimr.toml`.
+    #[default]
+    Off,
+    /// Labels that exist in any of the selected projects.
+    Union,
+    /// Labels that exist in all of the selected projects.
+    Intersection,
+}
+
+/// How the label picker is ordered.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum LabelOrder {
+    /// The most often picked first, the most recently picked among equally often picked ones.
+    #[default]
+    Usage,
+    /// Presets, then the configured labels by name, then project labels, as configured.
+    Config,
+}
+
+/// Labels plus the reviewers and milestone that go with them, e.g. for security patches.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct LabelPreset {
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// Preselected on the reviewer screen, added to the reviewers if they are not configured.
+    #[serde(default)]
+    pub reviewers: Vec<String>,
+    /// Used unless another milestone is chosen in the advanced options.
+    pub milestone: Option<String>,
+}
+
+impl LabelPreset {
+    /// What picking the preset applies, to show it in the label picker.
+    pub(crate) fn summary(&self) -> String {
+        let mut parts = vec![format!("labels {}", self.labels.join(", "))];
+        if !self.reviewers.is_empty() {
+            parts.push(format!("reviewers {}", self.reviewers.join(", ")));
+        }
+        if let Some(milestone) = &self.milestone {
+            parts.push(format!("milestone {}", milestone));
+        }
+        format!("preset: {}", parts.join("; "))
+    }
 }
 
-/// User configuration is loaded from a `multimr.toml` file in the current working directory.
-pub(crate) fn load_config_from_toml() -> Config {
-    let content = std::fs::read_to_string(CONFIG_FILE).unwrap_or_default();
+/// What a label brings along when it is set, e.g. team policy for breaking changes.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct LabelRule {
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub reviewers: Vec<String>,
+}
 
-    /// This contains only the fields we need from the TOML file.
-    #[derive(Deserialize)]
-    struct ConfigToml {
-        reviewers: Option<Vec<String>>,
-        labels: Option<HashMap<String, String>>,
-        working_dir: Option<String>,
-        assignee: Option<String>,
+/// Settings that can be overridden for an individual repository via `[repo_overrides.<dir>]`.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RepoOverride {
+    /// Branch the merge request should target instead of the project's default branch.
+    pub target_branch: Option<String>,
+    /// Push without triggering a CI pipeline, e.g. for documentation-only repos.
+    pub skip_ci: Option<bool>,
+    /// GitLab project as `group/project` or `host/group/project`, instead of the one `origin` points to.
+    pub project: Option<String>,
+    /// How the assignee is chosen for this repo.
+    pub assignee_mode: Option<AssigneeMode>,
+    /// Open the merge request for a branch that is already pushed, without touching the local clone.
+    pub no_checkout: Option<bool>,
+    /// Already pushed branch to open the merge request for in no-checkout mode.
+    pub source_branch: Option<String>,
+    /// Path of the glab executable or wrapper script to use for this repo.
+    pub glab: Option<String>,
+    /// Categories shown as badges in the repo list and filtered on with `t`, e.g. `backend`.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Greyed out in the repo list and never selected, e.g. archived or externally managed repos.
+    #[serde(alias = "readonly")]
+    pub disabled: Option<bool>,
+    /// Paths left out when committing everything, e.g. build output that isn't ignored.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_paths: Vec<String>,
+}
+
+impl Config {
+    /// Returns the overrides for the given repo directory, if any are configured.
+    /// With several working directories `dir` is a full path, its last component is the key.
+    pub(crate) fn repo_override(&self, dir: &str) -> Option<&RepoOverride> {
+        self.repo_overrides
+            .get(dir)
+            .or_else(|| self.repo_overrides.get(self.repo_name(dir)))
     }
 
-    // if the entire parsing fails return a config with None values
-    let parsed: ConfigToml = toml::from_str(&content).unwrap_or(ConfigToml {
-        reviewers: None,
-        labels: None,
-        working_dir: None,
-        assignee: None,
-    });
+    /// Whether `username` may be offered or added as a reviewer, see [`Config::never_review`].
+    pub(crate) fn may_review(&self, username: &str) -> bool {
+        let username = username.trim_start_matches('@');
+        !self.never_review.iter().any(|excluded| {
+            excluded
+                .trim_start_matches('@')
+                .eq_ignore_ascii_case(username)
+        })
+    }
 
-    // check if a root is specified in toml, if not use current directory
-    let working_dir_str = parsed.working_dir.unwrap_or(".".to_string());
+    /// Whether the repo `dir` is marked `disabled` (or `readonly`) and can't be selected.
+    pub(crate) fn is_disabled(&self, dir: &str) -> bool {
+        self.repo_override(dir)
+            .and_then(|o| o.disabled)
+            .unwrap_or_default()
+    }
+
+    /// The tags of the repo `dir`, none if it has no overrides.
+    pub(crate) fn tags(&self, dir: &str) -> &[String] {
+        self.repo_override(dir).map_or(&[], |o| o.tags.as_slice())
+    }
+
+    /// Every tag used by any repo, in name order.
+    pub(crate) fn all_tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self
+            .repo_overrides
+            .values()
+            .flat_map(|o| o.tags.iter().cloned())
+            .collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    /// The name of the repo `dir`, without the root it lives in when there are several.
+    pub(crate) fn repo_name<'a>(&self, dir: &'a str) -> &'a str {
+        match Path::new(dir).file_name().and_then(|n| n.to_str()) {
+            Some(name) if !self.working_dirs.is_empty() => name,
+            _ => dir,
+        }
+    }
+
+    /// The directories scanned for repositories.
+    pub(crate) fn roots(&self) -> Vec<&Path> {
+        if self.working_dirs.is_empty() {
+            vec![self.working_dir.as_path()]
+        } else {
+            self.working_dirs.iter().map(PathBuf::as_path).collect()
+        }
+    }
+
+    /// Position of the root the repo `dir` lives in, see [`Config::roots`].
+    pub(crate) fn workspace_index(&self, dir: &str) -> usize {
+        self.working_dirs
+            .iter()
+            .position(|root| Path::new(dir).starts_with(root))
+            .unwrap_or_default()
+    }
+
+    /// Whether the directory `dir` passes the `include` and `exclude` patterns.
+    pub(crate) fn is_listed(&self, dir: &str) -> bool {
+        let matches = |patterns: &[String]| patterns.iter().any(|p| utils::glob_match(p, dir));
+        (self.include.is_empty() || matches(&self.include)) && !matches(&self.exclude)
+    }
+
+    /// The glab executable for work that doesn't belong to a single repo.
+    pub(crate) fn glab_binary(&self) -> &str {
+        self.glab.as_deref().unwrap_or(DEFAULT_GLAB)
+    }
+
+    /// The glab executable for the repo `dir`, its override or else the global one.
+    pub(crate) fn glab_for(&self, dir: &str) -> &str {
+        self.repo_override(dir)
+            .and_then(|o| o.glab.as_deref())
+            .unwrap_or_else(|| self.glab_binary())
+    }
+
+    /// Every glab executable that may be run, each once.
+    pub(crate) fn glab_binaries(&self) -> Vec<&str> {
+        let mut binaries = vec![self.glab_binary()];
+        for glab in self
+            .repo_overrides
+            .values()
+            .filter_map(|o| o.glab.as_deref())
+        {
+            if !binaries.contains(&glab) {
+                binaries.push(glab);
+            }
+        }
+        binaries
+    }
+}
+
+/// Values that take precedence over the TOML file, e.g. given on the command line.
+#[derive(Debug, Default)]
+pub(crate) struct ConfigOverrides {
+    pub working_dir: Option<PathBuf>,
+    /// The first replaces the configured assignee, the others are assigned too.
+    pub assignees: Vec<String>,
+    /// Selected on the reviewer screen, added for this run if not configured.
+    pub reviewers: Vec<String>,
+    pub dry_run: bool,
+    pub restore_branch: bool,
+    pub canary: bool,
+    pub group: Option<String>,
+    pub reduced_motion: bool,
+    pub emit_script: Option<PathBuf>,
+    pub emit_plan: Option<PathBuf>,
+    pub offline: bool,
+    pub no_git: bool,
+    pub watch_pipelines: bool,
+    pub update: bool,
+    pub force: bool,
+    pub repo_group: Option<String>,
+}
+
+impl Config {
+    /// Layer `overrides` on top of this config, only the values that were given replace ours.
+    pub(crate) fn merge(mut self, overrides: ConfigOverrides) -> Result<Config> {
+        if let Some(working_dir) = overrides.working_dir {
+            self.working_dir =
+                resolve_dir(&std::env::current_dir().unwrap_or_default(), working_dir)?;
+            self.working_dirs.clear();
+        }
+        if let Some((assignee, others)) = overrides.assignees.split_first() {
+            self.assignee = Some(assignee.clone());
+            self.assignees = others.to_vec();
+        }
+        if !overrides.reviewers.is_empty() {
+            self.preselected_reviewers = overrides.reviewers;
+        }
+        self.dry_run |= overrides.dry_run;
+        self.restore_branch |= overrides.restore_branch;
+        self.canary |= overrides.canary;
+        self.reduced_motion |= overrides.reduced_motion;
+        self.offline |= overrides.offline;
+        self.no_git |= overrides.no_git;
+        self.watch_pipelines |= overrides.watch_pipelines;
+        self.update |= overrides.update;
+        self.force |= overrides.force;
+        if let Some(emit_script) = overrides.emit_script {
+            self.emit_script = Some(emit_script);
+        }
+        if let Some(emit_plan) = overrides.emit_plan {
+            self.emit_plan = Some(emit_plan);
+        }
+        if let Some(repo_group) = overrides.repo_group {
+            self.repo_group = Some(repo_group);
+        }
+        if let Some(group) = overrides.group {
+            self.group = Some(group);
+        }
+        Ok(self)
+    }
+}
+
+/// Resolve `dir` relative to `base` unless it is absolute.
+/// A leading `~` is the home directory.
+fn resolve_dir(base: &Path, dir: impl AsRef<Path>) -> Result<PathBuf> {
+    let dir = dir.as_ref();
+    let path = match (dir.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => base.join(dir),
+    };
+    path.canonicalize()
+        .map_err(|source| MultimrError::WorkingDir { path, source })
+}
 
-    // there is a root, now create a PathBuf
-    let working_dir = if working_dir_str.starts_with('/') || working_dir_str.starts_with('\\') {
-        // root // absolute path
-        PathBuf::from(&working_dir_str)
-            .canonicalize()
-            .expect("Failed to resolve absolute path")
+/// The local configuration file: `explicit` if given, otherwise the first `multimr.toml`
+/// found in `start` or one of its parents.
+pub(crate) fn find_config(explicit: Option<&Path>, start: &Path) -> Option<PathBuf> {
+    if let Some(explicit) = explicit {
+        return Some(explicit.to_path_buf());
+    }
+    start
+        .ancestors()
+        .map(|dir| dir.join(CONFIG_FILE))
+        .find(|path| path.is_file())
+}
+
+/// The global configuration file, if there is one.
+pub(crate) fn global_config() -> Option<PathBuf> {
+    utils::config_dir()
+        .map(|dir| dir.join(GLOBAL_CONFIG_FILE))
+        .filter(|path| path.is_file())
+}
+
+/// Load the configuration: the `--config` file or the nearest `multimr.toml`, layered over
+/// the global `$XDG_CONFIG_HOME/multimr/config.toml`, with `profile` on top of both and the
+/// `MULTIMR_*` environment variables on top of that.
+pub(crate) fn load_config(explicit: Option<&Path>, profile: Option<&str>) -> Result<Config> {
+    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    load_config_files(
+        find_config(explicit, &cwd).as_deref(),
+        global_config().as_deref(),
+        profile,
+        env_table(std::env::vars()),
+    )
+}
+
+/// Names of the `[profile.<name>]` sections in the local and global config, sorted.
+pub(crate) fn profile_names(explicit: Option<&Path>) -> Vec<String> {
+    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let mut names: Vec<String> = [find_config(explicit, &cwd), global_config()]
+        .iter()
+        .flat_map(|file| profiles(&read_table(file.as_deref()).unwrap_or_default()))
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Names of the profiles in a config table.
+fn profiles(table: &toml::Table) -> Vec<String> {
+    match table.get(PROFILES) {
+        Some(toml::Value::Table(profiles)) => profiles.keys().cloned().collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The `[profile.<name>]` section of a config table, if it has one.
+fn profile_table<'a>(table: &'a toml::Table, name: &str) -> Option<&'a toml::Table> {
+    table.get(PROFILES)?.get(name)?.as_table()
+}
+
+/// The settings of a config file, unknown keys are rejected so typos don't go unnoticed.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct ConfigToml {
+    reviewers: Option<Vec<String>>,
+    never_review: Option<Vec<String>>,
+    labels: Option<HashMap<String, String>>,
+    label_presets: Option<HashMap<String, LabelPreset>>,
+    label_rules: Option<HashMap<String, LabelRule>>,
+    ticket_links: Option<HashMap<String, String>>,
+    limits: Option<Limits>,
+    branch_detection: Option<BranchDetection>,
+    merge_queue: Option<MergeQueue>,
+    post_merge: Option<PostMerge>,
+    working_dir: Option<String>,
+    working_dirs: Option<Vec<String>>,
+    assignee: Option<String>,
+    repo_overrides: Option<HashMap<String, RepoOverride>>,
+    fetch_reviewers: Option<bool>,
+    restore_branch: Option<bool>,
+    fetch_labels: Option<LabelFetch>,
+    label_order: Option<LabelOrder>,
+    canary: Option<bool>,
+    due_date: Option<String>,
+    due_date_milestone: Option<String>,
+    confirm_threshold: Option<usize>,
+    max_changed_lines: Option<usize>,
+    suspicious_paths: Option<Vec<String>>,
+    squash: Option<bool>,
+    remove_source_branch: Option<bool>,
+    auto_merge: Option<bool>,
+    assignee_mode: Option<AssigneeMode>,
+    reviewer_strategy: Option<ReviewerStrategy>,
+    branch_template: Option<String>,
+    group: Option<String>,
+    report_snippet: Option<bool>,
+    report_issue: Option<String>,
+    umbrella_issue: Option<String>,
+    umbrella_epic: Option<String>,
+    announce_webhook: Option<String>,
+    reduced_motion: Option<bool>,
+    colorblind: Option<bool>,
+    desktop_notification: Option<bool>,
+    hyperlinks: Option<bool>,
+    glab: Option<String>,
+    skip_clean_repos: Option<bool>,
+    include: Option<Vec<String>>,
+    offline: Option<bool>,
+    no_git: Option<bool>,
+    watch_pipelines: Option<bool>,
+    groups: Option<HashMap<String, Vec<String>>>,
+    post_create: Option<Vec<String>>,
+    exclude: Option<Vec<String>>,
+    /// Only checked here, the profiles are merged as tables in [`load_config_files`]
+    #[serde(rename = "profile")]
+    _profiles: Option<HashMap<String, ConfigToml>>,
+}
+
+/// Settings that are otherwise only given on the command line, they can come from the
+/// environment but not from a config file.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct EnvOnly {
+    dry_run: Option<bool>,
+    force: Option<bool>,
+    update: Option<bool>,
+    repo_group: Option<String>,
+    emit_script: Option<PathBuf>,
+}
+
+impl EnvOnly {
+    const KEYS: [&str; 5] = ["dry_run", "force", "update", "repo_group", "emit_script"];
+}
+
+/// The value of `MULTIMR_<name>`, if set and not empty.
+pub(crate) fn env_var(name: &str) -> Option<String> {
+    std::env::var(format!("{}{}", ENV_PREFIX, name))
+        .ok()
+        .filter(|value| !value.is_empty())
+}
+
+/// A setting given in the environment, as the value of the setting `keys` lead to. It stays a
+/// string when the setting takes one, so branch names like `2024` or dates need no quotes,
+/// and is read as TOML for settings that take a number, boolean, array or table.
+/// Quoted values are TOML strings either way.
+fn env_value(keys: &[String], value: &str) -> toml::Value {
+    let string = toml::Value::String(value.to_string());
+    let parsed = toml::from_str::<toml::Table>(&format!("value = {}", value))
+        .ok()
+        .and_then(|mut table| table.remove("value"));
+    match parsed {
+        Some(toml::Value::String(unquoted)) => toml::Value::String(unquoted),
+        Some(parsed) if !env_setting_accepts(keys, &string) => parsed,
+        _ => string,
+    }
+}
+
+/// Whether the setting `keys` lead to can be set to `value`.
+fn env_setting_accepts(keys: &[String], value: &toml::Value) -> bool {
+    let table = env_nested(keys, value.clone());
+    if EnvOnly::KEYS.contains(&keys[0].as_str()) {
+        toml::Value::Table(table).try_into::<EnvOnly>().is_ok()
     } else {
-        // working dir is specified as relative path
-        std::env::current_dir()
-            .unwrap_or_else(|_| PathBuf::from("."))
-            .join(working_dir_str)
-            .canonicalize()
-            .expect("Failed to resolve relative path")
+        toml::Value::Table(table).try_into::<ConfigToml>().is_ok()
+    }
+}
+
+/// `value` in tables nested along `keys`, e.g. `{ limits = { parallel = 4 } }`.
+fn env_nested(keys: &[String], value: toml::Value) -> toml::Table {
+    let (last, parents) = keys.split_last().expect("a setting has a name");
+    parents.iter().rev().fold(
+        toml::Table::from_iter([(last.clone(), value)]),
+        |inner, key| toml::Table::from_iter([(key.clone(), toml::Value::Table(inner))]),
+    )
+}
+
+/// The `MULTIMR_*` settings among `vars` as a table like that of a config file, e.g.
+/// `MULTIMR_ASSIGNEE=me` is `assignee = "me"` and `MULTIMR_LIMITS__PARALLEL=4` is
+/// `parallel = 4` in `[limits]`.
+pub(crate) fn env_table(vars: impl IntoIterator<Item = (String, String)>) -> toml::Table {
+    let mut table = toml::Table::new();
+    for (name, value) in vars {
+        let Some(name) = name.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        if value.is_empty() || ENV_SELECTORS.contains(&name) {
+            continue;
+        }
+        let keys: Vec<String> = name.split(ENV_NESTING).map(str::to_lowercase).collect();
+        let value = env_value(&keys, &value);
+        merge_tables(&mut table, env_nested(&keys, value));
+    }
+    table
+}
+
+/// The settings of `file` as a TOML table, empty without a file.
+/// Fails if the file can't be read, isn't valid TOML or has unknown or mistyped settings,
+/// the error points at the line and column.
+fn read_table(file: Option<&Path>) -> Result<toml::Table> {
+    let Some(path) = file else {
+        return Ok(toml::Table::new());
     };
+    let error = |message: String| MultimrError::Config {
+        path: path.to_path_buf(),
+        message,
+    };
+    let content = std::fs::read_to_string(path).map_err(|e| error(e.to_string()))?;
+    toml::from_str::<ConfigToml>(&content).map_err(|e| error(e.to_string()))?;
+    toml::from_str(&content).map_err(|e| error(e.to_string()))
+}
+
+/// Put the settings of `local` over those of `global`, tables like `[repo_overrides]` are
+/// merged key by key.
+fn merge_tables(global: &mut toml::Table, local: toml::Table) {
+    for (key, value) in local {
+        match (global.get_mut(&key), value) {
+            (Some(toml::Value::Table(global)), toml::Value::Table(local)) => {
+                merge_tables(global, local)
+            }
+            (_, value) => {
+                global.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Load the `local` configuration over the `global` one, either can be left out, the
+/// settings of `[profile.<profile>]` over both and those of `env` over everything, see
+/// [`env_table`].
+/// A relative `working_dir` is resolved against the directory of the file that sets it, as
+/// are the entries of `working_dirs`, or against the current directory if `env` sets it.
+pub(crate) fn load_config_files(
+    local: Option<&Path>,
+    global: Option<&Path>,
+    profile: Option<&str>,
+    mut env: toml::Table,
+) -> Result<Config> {
+    let env_error = |e: toml::de::Error| MultimrError::Env(e.message().to_string());
+    let env_only: EnvOnly = toml::Value::Table(
+        EnvOnly::KEYS
+            .iter()
+            .filter_map(|key| env.remove(*key).map(|value| (key.to_string(), value)))
+            .collect(),
+    )
+    .try_into()
+    .map_err(env_error)?;
+    toml::Value::Table(env.clone())
+        .try_into::<ConfigToml>()
+        .map_err(env_error)?;
+    let env_sets_working_dir = env.contains_key("working_dir") || env.contains_key("working_dirs");
 
-    // if individual fields fail, we use default values
-    Config {
+    let local_table = read_table(local)?;
+    let mut table = read_table(global)?;
+    let sets_working_dir = |t: &toml::Table| {
+        t.contains_key("working_dir")
+            || t.contains_key("working_dirs")
+            || profile.is_some_and(|name| {
+                profile_table(t, name).is_some_and(|p| {
+                    p.contains_key("working_dir") || p.contains_key("working_dirs")
+                })
+            })
+    };
+    let path = match (local, global) {
+        (Some(local), _) if sets_working_dir(&local_table) => local,
+        (_, Some(global)) if sets_working_dir(&table) => global,
+        (Some(local), _) => local,
+        _ => Path::new(CONFIG_FILE),
+    };
+    merge_tables(&mut table, local_table);
+    let profiles = table.remove(PROFILES);
+    if let Some(name) = profile {
+        let Some(toml::Value::Table(settings)) =
+            profiles.as_ref().and_then(|p| p.get(name)).cloned()
+        else {
+            let mut available = profiles
+                .as_ref()
+                .and_then(|p| p.as_table())
+                .map(|p| p.keys().cloned().collect::<Vec<_>>())
+                .unwrap_or_default();
+            available.sort();
+            return Err(MultimrError::UnknownProfile {
+                name: name.to_string(),
+                available: available.join(", "),
+            });
+        };
+        merge_tables(&mut table, settings);
+    }
+    merge_tables(&mut table, env);
+
+    // Each file was checked on its own, so this only fails if they disagree on a type
+    let parsed: ConfigToml =
+        toml::Value::Table(table)
+            .try_into()
+            .map_err(|e: toml::de::Error| MultimrError::Config {
+                path: path.to_path_buf(),
+                message: e.to_string(),
+            })?;
+
+    // check if a root is specified in toml, if not use the directory of the config file
+    let working_dir_str = parsed.working_dir.unwrap_or(".".to_string());
+    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let config_dir = if env_sets_working_dir {
+        cwd
+    } else {
+        cwd.join(path.parent().unwrap_or(Path::new("")))
+    };
+    let working_dirs = parsed
+        .working_dirs
+        .unwrap_or_default()
+        .into_iter()
+        .map(|dir| resolve_dir(&config_dir, dir))
+        .collect::<Result<Vec<_>>>()?;
+    let working_dir = match working_dirs.first() {
+        Some(first) => first.clone(),
+        None => resolve_dir(&config_dir, working_dir_str)?,
+    };
+
+    let mut config = Config {
         working_dir,
+        // A single entry is the same as `working_dir`
+        working_dirs: if working_dirs.len() > 1 {
+            working_dirs
+        } else {
+            Vec::new()
+        },
         reviewers: parsed.reviewers.unwrap_or_default(),
+        never_review: parsed.never_review.unwrap_or_default(),
         labels: parsed
             .labels
             .map(|m| m.into_iter().collect())
             .unwrap_or_default(),
+        label_presets: parsed.label_presets.unwrap_or_default(),
+        label_rules: parsed.label_rules.unwrap_or_default(),
+        ticket_links: parsed.ticket_links.unwrap_or_default(),
         assignee: parsed.assignee,
-        dry_run: false, // Default to false, can be set later
-    }
+        assignees: Vec::new(),
+        preselected_reviewers: Vec::new(),
+        dry_run: env_only.dry_run.unwrap_or_default(),
+        repo_overrides: parsed.repo_overrides.unwrap_or_default(),
+        fetch_reviewers: parsed.fetch_reviewers.unwrap_or_default(),
+        restore_branch: parsed.restore_branch.unwrap_or_default(),
+        fetch_labels: parsed.fetch_labels.unwrap_or_default(),
+        label_order: parsed.label_order.unwrap_or_default(),
+        canary: parsed.canary.unwrap_or_default(),
+        due_date: parsed.due_date,
+        due_date_milestone: parsed.due_date_milestone,
+        confirm_threshold: parsed
+            .confirm_threshold
+            .unwrap_or(DEFAULT_CONFIRM_THRESHOLD),
+        max_changed_lines: parsed
+            .max_changed_lines
+            .unwrap_or(DEFAULT_MAX_CHANGED_LINES),
+        suspicious_paths: parsed.suspicious_paths.unwrap_or_else(|| {
+            DEFAULT_SUSPICIOUS_PATHS
+                .iter()
+                .map(|p| p.to_string())
+                .collect()
+        }),
+        squash: parsed.squash.unwrap_or_default(),
+        remove_source_branch: parsed.remove_source_branch.unwrap_or_default(),
+        auto_merge: parsed.auto_merge.unwrap_or_default(),
+        assignee_mode: parsed.assignee_mode.unwrap_or_default(),
+        reviewer_strategy: parsed.reviewer_strategy.unwrap_or_default(),
+        branch_template: parsed.branch_template,
+        group: parsed.group,
+        report_snippet: parsed.report_snippet.unwrap_or_default(),
+        report_issue: parsed.report_issue,
+        umbrella_issue: parsed.umbrella_issue,
+        umbrella_epic: parsed.umbrella_epic,
+        announce_webhook: parsed.announce_webhook,
+        reduced_motion: parsed.reduced_motion.unwrap_or_default(),
+        colorblind: parsed.colorblind.unwrap_or_default(),
+        desktop_notification: parsed.desktop_notification.unwrap_or_default(),
+        hyperlinks: parsed.hyperlinks,
+        glab: parsed.glab,
+        skip_clean_repos: parsed.skip_clean_repos.unwrap_or_default(),
+        include: parsed.include.unwrap_or_default(),
+        offline: parsed.offline.unwrap_or_default(),
+        no_git: parsed.no_git.unwrap_or_default(),
+        watch_pipelines: parsed.watch_pipelines.unwrap_or_default(),
+        update: env_only.update.unwrap_or_default(),
+        force: env_only.force.unwrap_or_default(),
+        limits: parsed.limits.unwrap_or_default(),
+        branch_detection: parsed.branch_detection.unwrap_or_default(),
+        merge_queue: parsed.merge_queue,
+        post_merge: parsed.post_merge.unwrap_or_default(),
+        profile: profile.map(str::to_string),
+        repo_groups: parsed.groups.unwrap_or_default(),
+        post_create: parsed.post_create.unwrap_or_default(),
+        repo_group: env_only.repo_group,
+        exclude: parsed.exclude.unwrap_or_default(),
+        emit_script: env_only.emit_script,
+        emit_plan: None,
+        tour: false,
+    };
+    // The exclusions win over the reviewers configured in another file or profile
+    let reviewers = std::mem::take(&mut config.reviewers);
+    config.reviewers = reviewers
+        .into_iter()
+        .filter(|r| config.may_review(r))
+        .collect();
+    Ok(config)
 }
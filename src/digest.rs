@@ -0,0 +1,161 @@
+//! `multimr digest`: a markdown summary of the merge requests created, merged and closed
+//! across the workspace in a recent period, e.g. for a team standup.
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::due_date;
+use crate::gitlab::{self, ListedMergeRequest};
+
+/// A merge request of one project in the workspace.
+#[derive(Debug, Clone)]
+pub(crate) struct Entry {
+    /// The project path, or the directory when the remote isn't recognized
+    pub(crate) project: String,
+    pub(crate) mr: ListedMergeRequest,
+}
+
+/// Parse a period like `7d`, `2w` or `24h` into seconds.
+pub(crate) fn parse_since(input: &str) -> Option<u64> {
+    let input = input.trim();
+    let digits = input.chars().take_while(|c| c.is_ascii_digit()).count();
+    let amount: u64 = input[..digits].parse().ok()?;
+    let unit = match input[digits..].trim() {
+        "h" | "hour" | "hours" => 3600,
+        "d" | "day" | "days" => 86_400,
+        "w" | "week" | "weeks" => 7 * 86_400,
+        _ => return None,
+    };
+    Some(amount * unit)
+}
+
+/// `secs` since the Unix epoch as an ISO 8601 timestamp in UTC, as the GitLab API takes it.
+pub(crate) fn timestamp(secs: u64) -> String {
+    let (y, m, d) = due_date::civil_from_days((secs / 86_400) as i64);
+    let time = secs % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        y,
+        m,
+        d,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// A listed, enabled repo of the workspace.
+#[derive(Debug, Clone)]
+pub(crate) struct WorkspaceRepo {
+    /// The directory as listed in the TUI
+    pub(crate) dir: String,
+    pub(crate) path: PathBuf,
+    /// The project path, or the directory when the remote isn't recognized
+    pub(crate) project: String,
+}
+
+/// Every listed, enabled repo of the workspace, one per project since several checkouts of
+/// one project would list its merge requests twice. Also returns the roots that can't be read.
+pub(crate) fn workspace_repos(config: &Config) -> (Vec<WorkspaceRepo>, Vec<String>) {
+    let several_roots = !config.working_dirs.is_empty();
+    let mut seen = HashSet::new();
+    let mut repos = Vec::new();
+    let mut errors = Vec::new();
+    for root in config.roots() {
+        let Ok(dirs) = fs::read_dir(root) else {
+            errors.push(format!("cannot read {}", root.display()));
+            continue;
+        };
+        let mut paths: Vec<_> = dirs
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.join(".git").exists())
+            .collect();
+        paths.sort();
+        for path in paths {
+            let name = path
+                .file_name()
+                .map_or_else(String::new, |n| n.to_string_lossy().to_string());
+            let dir = if several_roots {
+                path.to_string_lossy().to_string()
+            } else {
+                name.clone()
+            };
+            if !config.is_listed(&name) || config.is_disabled(&dir) {
+                continue;
+            }
+            let project = gitlab::project_from_remote(&path).map_or(dir.clone(), |p| p.path);
+            if seen.insert(project.clone()) {
+                repos.push(WorkspaceRepo { dir, path, project });
+            }
+        }
+    }
+    (repos, errors)
+}
+
+/// The merge requests updated after `since` in the repos of the workspace, see
+/// [`workspace_repos`]. Also returns why projects could not be queried.
+pub(crate) fn collect(config: &Config, since: &str) -> (Vec<Entry>, Vec<String>) {
+    let (repos, mut errors) = workspace_repos(config);
+    let mut entries = Vec::new();
+    for repo in repos {
+        match gitlab::merge_requests_since(config.glab_for(&repo.dir), &repo.path, since) {
+            Ok(mrs) => entries.extend(mrs.into_iter().map(|mr| Entry {
+                project: repo.project.clone(),
+                mr,
+            })),
+            Err(e) => errors.push(format!("{}: {}", repo.dir, e)),
+        }
+    }
+    (entries, errors)
+}
+
+/// Whether the ISO 8601 timestamp `at` is at or after `since`, compared up to the second
+/// since GitLab adds milliseconds.
+fn is_after(at: Option<&str>, since: &str) -> bool {
+    let seconds = |t: &str| t.chars().take(19).collect::<String>();
+    at.is_some_and(|at| seconds(at) >= seconds(since))
+}
+
+/// The digest as markdown: the merge requests created, merged and closed since `since`.
+pub(crate) fn render(entries: &[Entry], since: &str) -> String {
+    let mut entries: Vec<&Entry> = entries.iter().collect();
+    entries.sort_by(|a, b| (&a.project, a.mr.iid).cmp(&(&b.project, b.mr.iid)));
+
+    let created: Vec<&Entry> = entries
+        .iter()
+        .copied()
+        .filter(|e| is_after(Some(&e.mr.created_at), since))
+        .collect();
+    let merged: Vec<&Entry> = entries
+        .iter()
+        .copied()
+        .filter(|e| is_after(e.mr.merged_at.as_deref(), since))
+        .collect();
+    let closed: Vec<&Entry> = entries
+        .iter()
+        .copied()
+        .filter(|e| e.mr.merged_at.is_none() && is_after(e.mr.closed_at.as_deref(), since))
+        .collect();
+
+    let date: String = since.chars().take(10).collect();
+    let mut out = format!("# Merge requests since {}\n", date);
+    for (heading, list) in [("Created", created), ("Merged", merged), ("Closed", closed)] {
+        out.push_str(&format!("\n## {} ({})\n\n", heading, list.len()));
+        if list.is_empty() {
+            out.push_str("None.\n");
+        }
+        for entry in list {
+            out.push_str(&format!(
+                "- {}!{} [{}]({}) by @{}\n",
+                entry.project,
+                entry.mr.iid,
+                entry.mr.title,
+                entry.mr.web_url,
+                entry.mr.author.username
+            ));
+        }
+    }
+    out
+}
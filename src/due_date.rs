@@ -0,0 +1,60 @@
+//! Due dates for the `/due` quick action, given as a date or as an offset from today like `+3 days`.
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Days since the Unix epoch, in UTC.
+pub(crate) fn today() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64 / 86_400)
+}
+
+/// Resolve `input` to a `YYYY-MM-DD` date, relative to `today` (days since the Unix epoch).
+/// Accepts a date as is, or an offset in days or weeks: `+3 days`, `+1 week`, `+2d`, `+1w`.
+pub(crate) fn resolve(input: &str, today: i64) -> Option<String> {
+    let input = input.trim();
+    if let Some(offset) = input.strip_prefix('+') {
+        let offset = offset.trim();
+        let digits = offset.chars().take_while(|c| c.is_ascii_digit()).count();
+        let amount: i64 = offset[..digits].parse().ok()?;
+        let days = match offset[digits..].trim() {
+            "d" | "day" | "days" => amount,
+            "w" | "week" | "weeks" => amount * 7,
+            _ => return None,
+        };
+        let (y, m, d) = civil_from_days(today + days);
+        return Some(format!("{:04}-{:02}-{:02}", y, m, d));
+    }
+
+    let mut parts = input.splitn(3, '-').map(|p| p.parse::<i64>().ok());
+    let (y, m, d) = (parts.next()??, parts.next()??, parts.next()??);
+    // Reject dates like 2024-02-30 by checking they survive the round trip
+    let valid = (1..=12).contains(&m)
+        && (1..=31).contains(&d)
+        && civil_from_days(days_from_civil(y, m, d)) == (y, m, d);
+    valid.then(|| format!("{:04}-{:02}-{:02}", y, m, d))
+}
+
+/// Year, month and day of a number of days since the Unix epoch, see
+/// <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+pub(crate) fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + i64::from(m <= 2), m, d)
+}
+
+/// Inverse of [`civil_from_days`].
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = if m > 2 { m - 3 } else { m + 9 };
+    let doy = (153 * mp + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
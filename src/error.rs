@@ -0,0 +1,51 @@
+//! Errors that can occur while preparing and creating merge requests.
+use std::io;
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub(crate) enum MultimrError {
+    #[error("GitLab CLI `{glab}` is not installed, please install it to use multimr")]
+    GlabNotInstalled { glab: String },
+    #[error("cannot resolve working directory {}: {source}", path.display())]
+    WorkingDir { path: PathBuf, source: io::Error },
+    #[error("cannot read working directory {}: {source}", path.display())]
+    Scan { path: PathBuf, source: io::Error },
+    #[error("failed to run `{command}`: {source}")]
+    Spawn { command: String, source: io::Error },
+    #[error("`{command}` failed: {message}")]
+    CommandFailed { command: String, message: String },
+    #[error("cannot find a place for the offline queue, set HOME or XDG_STATE_HOME")]
+    NoQueue,
+    #[error("cannot update the offline queue {}: {source}", path.display())]
+    Queue { path: PathBuf, source: io::Error },
+    #[error("invalid config {}: {message}", path.display())]
+    Config { path: PathBuf, message: String },
+    #[error("cannot write the config {}: {source}", path.display())]
+    ConfigWrite { path: PathBuf, source: io::Error },
+    #[error("invalid MULTIMR_* environment variable: {0}")]
+    Env(String),
+    #[error("there is no [profile.{name}] in the config, the profiles are: {available}")]
+    UnknownProfile { name: String, available: String },
+    #[error("cannot read the plan {}: {source}", path.display())]
+    Plan { path: PathBuf, source: io::Error },
+    #[error("cannot set up the tour sandbox {}: {source}", path.display())]
+    Sandbox { path: PathBuf, source: io::Error },
+    #[error("--no-git only opens merge requests for pushed branches, but the repo is on {branch}")]
+    NoGit { branch: String },
+    #[error(
+        "--group works on the projects without checkouts, so it only updates merge requests, add --update or create them from the checkouts"
+    )]
+    GroupWithoutUpdate,
+    #[error("no open merge request to update for branch {branch}")]
+    NoOpenMergeRequest { branch: String },
+    #[error("cannot show a desktop notification: {0}")]
+    Notification(#[from] notify_rust::error::Error),
+    #[error("git: {}", .0.message())]
+    Git(#[from] git2::Error),
+    #[error("unexpected response from the GitLab API: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+pub(crate) type Result<T, E = MultimrError> = std::result::Result<T, E>;
@@ -0,0 +1,549 @@
+//! Creates the merge requests on background threads, repo by repo unless `[limits]` allows
+//! more at once, so the TUI stays responsive and the batch can be paused in between repos.
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::error::Result;
+use crate::gitlab;
+use crate::limits::Throttle;
+use crate::merge_request::MergeRequest;
+use crate::queue::{self, QueuedRepo};
+use crate::utils;
+
+/// How often a paused worker checks whether it may continue.
+const PAUSE_POLL: Duration = Duration::from_millis(100);
+/// Starts the log line listing the reviewers GitLab set, see [`parse_confirmed_reviewers`].
+const CONFIRMED_REVIEWERS: &str = "Reviewers confirmed by GitLab:";
+/// Starts the log line with the URL of the merge request glab created or updated, see
+/// [`logged_mr_url`].
+const MERGE_REQUEST_URL: &str = "Merge request:";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RepoState {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+    /// The batch was stopped before this repo got its turn.
+    Skipped,
+    /// Committed locally while offline, pushing and creating the merge request are queued.
+    Queued,
+}
+
+impl RepoState {
+    /// Whether this repo has been handled, one way or another.
+    pub(crate) fn is_done(&self) -> bool {
+        matches!(
+            self,
+            RepoState::Succeeded | RepoState::Failed | RepoState::Skipped | RepoState::Queued
+        )
+    }
+}
+
+/// Progress and output of a single repo in the batch.
+#[derive(Debug, Clone)]
+pub(crate) struct RepoRun {
+    pub(crate) dir: String,
+    pub(crate) state: RepoState,
+    /// Output of every command run for this repo
+    pub(crate) log: String,
+    /// Web URL of the created merge request, parsed from glab's output
+    pub(crate) url: Option<String>,
+    /// Project-scoped ID of the created merge request, the number at the end of its URL
+    pub(crate) iid: Option<u64>,
+    /// Why creating the merge request failed
+    pub(crate) error: Option<String>,
+    /// How often this repo has been run, more than once when failures were retried
+    pub(crate) attempts: u32,
+    /// The reviewers asked for
+    pub(crate) requested_reviewers: Vec<String>,
+    /// The reviewers GitLab reports on the created merge request, `None` if not checked
+    pub(crate) confirmed_reviewers: Option<Vec<String>>,
+}
+
+impl RepoRun {
+    fn new(dir: String, requested_reviewers: Vec<String>) -> Self {
+        Self {
+            dir,
+            state: RepoState::Pending,
+            log: String::new(),
+            url: None,
+            iid: None,
+            error: None,
+            attempts: 0,
+            requested_reviewers,
+            confirmed_reviewers: None,
+        }
+    }
+
+    /// The requested reviewers GitLab didn't set, empty if they weren't checked.
+    pub(crate) fn dropped_reviewers(&self) -> Vec<&str> {
+        match &self.confirmed_reviewers {
+            Some(confirmed) => dropped_reviewers(&self.requested_reviewers, confirmed),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// The `requested` reviewers missing from the `confirmed` ones.
+pub(crate) fn dropped_reviewers<'a>(requested: &'a [String], confirmed: &[String]) -> Vec<&'a str> {
+    requested
+        .iter()
+        .map(|r| r.trim_start_matches('@'))
+        .filter(|r| !confirmed.iter().any(|c| c.eq_ignore_ascii_case(r)))
+        .collect()
+}
+
+/// Messages from the worker thread to the TUI.
+#[derive(Debug)]
+enum Event {
+    Started(usize),
+    Finished {
+        index: usize,
+        /// Whether the remote steps were queued rather than run
+        result: Result<bool>,
+        log: String,
+    },
+}
+
+/// A batch of merge requests being created.
+#[derive(Debug)]
+pub(crate) struct Execution {
+    pub(crate) repos: Vec<RepoRun>,
+    /// Pause after the first repo until the user had a look at its merge request
+    canary: bool,
+    paused: Arc<AtomicBool>,
+    stopped: Arc<AtomicBool>,
+    events: mpsc::Receiver<Event>,
+}
+
+impl Execution {
+    /// Start creating `mr` in every repo in `dirs`, one after the other unless `[limits]` says otherwise.
+    pub(crate) fn start(config: &Config, mr: &MergeRequest, dirs: Vec<String>) -> Self {
+        let canary = config.canary && dirs.len() > 1;
+        let indices = (0..dirs.len()).collect();
+        let (paused, stopped, events) = spawn_worker(config, mr, &dirs, indices, canary);
+
+        Self {
+            repos: dirs
+                .into_iter()
+                .map(|dir| {
+                    let reviewers = mr.for_dir(config, &dir).reviewers;
+                    RepoRun::new(dir, reviewers)
+                })
+                .collect(),
+            canary,
+            paused,
+            stopped,
+            events,
+        }
+    }
+
+    /// Run the repos that failed once more, leaving the others as they are.
+    /// Returns how many repos are retried.
+    pub(crate) fn retry_failed(&mut self, config: &Config, mr: &MergeRequest) -> usize {
+        let failed: Vec<usize> = (0..self.repos.len())
+            .filter(|&i| self.repos[i].state == RepoState::Failed)
+            .collect();
+        if failed.is_empty() {
+            return 0;
+        }
+
+        for &index in &failed {
+            let repo = &mut self.repos[index];
+            repo.state = RepoState::Pending;
+            repo.error = None;
+            repo.log.push_str("\n--- Retry ---\n");
+        }
+        let dirs: Vec<String> = self.repos.iter().map(|r| r.dir.clone()).collect();
+        let count = failed.len();
+        (self.paused, self.stopped, self.events) = spawn_worker(config, mr, &dirs, failed, false);
+        // The canary has been inspected the first time around
+        self.canary = false;
+        count
+    }
+
+    /// Apply the progress reported by the worker since the last call.
+    pub(crate) fn poll(&mut self) {
+        loop {
+            match self.events.try_recv() {
+                Ok(Event::Started(index)) => {
+                    let repo = &mut self.repos[index];
+                    repo.state = RepoState::Running;
+                    repo.attempts += 1;
+                }
+                Ok(Event::Finished { index, result, log }) => {
+                    let repo = &mut self.repos[index];
+                    repo.state = match result {
+                        Ok(false) => RepoState::Succeeded,
+                        Ok(true) => RepoState::Queued,
+                        Err(e) => {
+                            repo.error = Some(e.to_string());
+                            RepoState::Failed
+                        }
+                    };
+                    repo.url = logged_mr_url(&log);
+                    repo.iid = repo.url.as_deref().and_then(parse_mr_iid);
+                    repo.confirmed_reviewers = parse_confirmed_reviewers(&log);
+                    repo.log.push_str(&log);
+                }
+                Err(mpsc::TryRecvError::Empty) => return,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    // The worker is gone, either stopped by the user or it panicked,
+                    // nothing left will run.
+                    let state = if self.stopped.load(Ordering::Relaxed) {
+                        RepoState::Skipped
+                    } else {
+                        RepoState::Failed
+                    };
+                    for repo in &mut self.repos {
+                        if !repo.state.is_done() {
+                            repo.state = state;
+                            if state == RepoState::Failed {
+                                repo.error = Some("the worker stopped unexpectedly".to_string());
+                            }
+                        }
+                    }
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Hold the remaining repos after the one in flight, or continue with them.
+    pub(crate) fn toggle_pause(&self) {
+        self.paused.fetch_xor(true, Ordering::Relaxed);
+    }
+
+    pub(crate) fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Don't start any of the remaining repos, the one in flight still finishes.
+    pub(crate) fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the canary merge request is created and waits to be inspected.
+    pub(crate) fn awaiting_canary(&self) -> bool {
+        self.canary && self.is_paused() && self.completed() == 1
+    }
+
+    /// Whether every repo has been handled.
+    pub(crate) fn is_finished(&self) -> bool {
+        self.repos.iter().all(|r| r.state.is_done())
+    }
+
+    /// Number of repos that have been handled so far.
+    pub(crate) fn completed(&self) -> usize {
+        self.repos.iter().filter(|r| r.state.is_done()).count()
+    }
+}
+
+/// A repo to create the merge request in, with its index in the batch.
+type Job = (usize, PathBuf, MergeRequest);
+
+/// What every worker of a batch shares.
+struct Workers {
+    jobs: Mutex<VecDeque<Job>>,
+    paused: Arc<AtomicBool>,
+    stopped: Arc<AtomicBool>,
+    throttle: Throttle,
+    dry_run: bool,
+    restore_branch: bool,
+    offline: bool,
+    post_create: Vec<String>,
+}
+
+impl Workers {
+    /// Run jobs until there are none left or the batch is stopped.
+    /// Returns false once the TUI no longer listens.
+    fn work(&self, tx: &mpsc::Sender<Event>) -> bool {
+        loop {
+            while self.paused.load(Ordering::Relaxed) && !self.stopped.load(Ordering::Relaxed) {
+                thread::sleep(PAUSE_POLL);
+            }
+            if self.stopped.load(Ordering::Relaxed) {
+                return true;
+            }
+            let Some(job) = self
+                .jobs
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .pop_front()
+            else {
+                return true;
+            };
+            if !self.run(job, tx) {
+                return false;
+            }
+        }
+    }
+
+    /// Create the merge request of a single job, reporting its progress.
+    fn run(&self, (index, repo_dir, mr): Job, tx: &mpsc::Sender<Event>) -> bool {
+        if tx.send(Event::Started(index)).is_err() {
+            return false;
+        }
+        let (result, log) = run_repo(
+            &repo_dir,
+            &mr,
+            self.dry_run,
+            !self.dry_run && self.offline,
+            self.restore_branch,
+            &self.post_create,
+            &self.throttle,
+        );
+        tx.send(Event::Finished { index, result, log }).is_ok()
+    }
+}
+
+/// Create `mr` in the repos at `indices` of `dirs` on background threads, as many repos at
+/// the same time as the `[limits]` allow. A canary runs alone, the others wait for it.
+/// Returns the pause and stop flags of the workers, and the receiver of their progress.
+fn spawn_worker(
+    config: &Config,
+    mr: &MergeRequest,
+    dirs: &[String],
+    indices: Vec<usize>,
+    canary: bool,
+) -> (Arc<AtomicBool>, Arc<AtomicBool>, mpsc::Receiver<Event>) {
+    let (tx, rx) = mpsc::channel();
+    let paused = Arc::new(AtomicBool::new(false));
+    let stopped = Arc::new(AtomicBool::new(false));
+
+    let jobs: VecDeque<Job> = indices
+        .into_iter()
+        .map(|index| {
+            let dir = &dirs[index];
+            (index, config.working_dir.join(dir), mr.for_dir(config, dir))
+        })
+        .collect();
+    let parallel = config.limits.parallel().min(jobs.len().max(1));
+    let workers = Arc::new(Workers {
+        jobs: Mutex::new(jobs),
+        paused: Arc::clone(&paused),
+        stopped: Arc::clone(&stopped),
+        throttle: Throttle::new(&config.limits),
+        dry_run: config.dry_run,
+        restore_branch: config.restore_branch,
+        offline: config.offline,
+        post_create: config.post_create.clone(),
+    });
+
+    thread::spawn(move || {
+        if canary {
+            let first = workers
+                .jobs
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .pop_front();
+            if let Some(job) = first
+                && !workers.run(job, &tx)
+            {
+                return;
+            }
+            workers.paused.store(true, Ordering::Relaxed);
+        }
+        for _ in 1..parallel {
+            let workers = Arc::clone(&workers);
+            let tx = tx.clone();
+            thread::spawn(move || workers.work(&tx));
+        }
+        workers.work(&tx);
+    });
+
+    (paused, stopped, rx)
+}
+
+/// Find the web URL of a merge request in what `glab mr create` printed on stdout. Only a
+/// URL ending in the IID counts, not the `.../merge_requests/new?...` link a push prints.
+pub(crate) fn parse_mr_url(output: &str) -> Option<String> {
+    output
+        .split_whitespace()
+        .find(|word| {
+            word.starts_with("http")
+                && word
+                    .split_once("/-/merge_requests/")
+                    .is_some_and(|(_, iid)| {
+                        !iid.is_empty() && iid.bytes().all(|b| b.is_ascii_digit())
+                    })
+        })
+        .map(|url| url.to_string())
+}
+
+/// Log `url` as that of the merge request the repo's steps created or updated.
+pub(crate) fn log_mr_url(url: &str, log: &mut String) {
+    log.push_str(&format!("{} {}\n", MERGE_REQUEST_URL, url));
+}
+
+/// The URL logged by [`log_mr_url`], the last one if there are several. The rest of the log
+/// is not searched: commands and push output can hold other merge request links.
+pub(crate) fn logged_mr_url(log: &str) -> Option<String> {
+    log.lines()
+        .rev()
+        .find_map(|line| line.strip_prefix(MERGE_REQUEST_URL))
+        .map(|url| url.trim().to_string())
+}
+
+/// Run a step that isn't a git operation. For `glab mr create` and `glab mr update` the URL
+/// of the merge request is taken from their stdout and logged.
+pub(crate) fn run_command(cmd: &mut process::Command, log: &mut String) -> Result<()> {
+    let args: Vec<_> = cmd.get_args().take(2).collect();
+    let opens_merge_request =
+        args.len() == 2 && args[0] == "mr" && (args[1] == "create" || args[1] == "update");
+    let stdout = utils::run_checked_stdout(cmd, log)?;
+    if opens_merge_request && let Some(url) = parse_mr_url(&stdout) {
+        log_mr_url(&url, log);
+    }
+    Ok(())
+}
+
+/// The IID of a merge request from its web URL, e.g. 42 for `.../-/merge_requests/42`.
+pub(crate) fn parse_mr_iid(url: &str) -> Option<u64> {
+    let (_, rest) = url.split_once("/-/merge_requests/")?;
+    rest.split(|c: char| !c.is_ascii_digit())
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// The reviewers logged by [`confirm_reviewers`], the last time if there are several.
+pub(crate) fn parse_confirmed_reviewers(log: &str) -> Option<Vec<String>> {
+    let line = log
+        .lines()
+        .rev()
+        .find_map(|line| line.strip_prefix(CONFIRMED_REVIEWERS))?;
+    Some(
+        line.split(',')
+            .map(str::trim)
+            .filter(|r| !r.is_empty() && *r != "none")
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+/// Ask GitLab which reviewers ended up on the merge request created in `log`, and log them.
+fn confirm_reviewers(repo_dir: &Path, mr: &MergeRequest, log: &mut String) {
+    let Some(iid) = logged_mr_url(log).as_deref().and_then(parse_mr_iid) else {
+        return;
+    };
+    match gitlab::merge_request_reviewers(mr.glab(), repo_dir, mr.project.as_ref(), iid) {
+        Ok(reviewers) if reviewers.is_empty() => {
+            log.push_str(&format!("{} none\n", CONFIRMED_REVIEWERS))
+        }
+        Ok(reviewers) => log.push_str(&format!(
+            "{} {}\n",
+            CONFIRMED_REVIEWERS,
+            reviewers.join(", ")
+        )),
+        Err(e) => log.push_str(&format!("Could not check the reviewers: {}\n", e)),
+    }
+}
+
+/// Fill in the placeholders of a post-create command, each value quoted for the shell:
+/// `{iid}`, `{url}`, `{repo}` and `{project}`.
+pub(crate) fn render_hook(command: &str, iid: u64, url: &str, repo: &str, project: &str) -> String {
+    command
+        .replace("{iid}", &iid.to_string())
+        .replace("{url}", &utils::shell_quote(url))
+        .replace("{repo}", &utils::shell_quote(repo))
+        .replace("{project}", &utils::shell_quote(project))
+}
+
+/// Run the post-create commands for the merge request whose creation was logged in `log`.
+/// A failing command is logged, the merge request exists either way.
+fn run_post_create(repo_dir: &Path, mr: &MergeRequest, post_create: &[String], log: &mut String) {
+    let Some(url) = logged_mr_url(log) else {
+        log.push_str("No merge request URL in the output, skipping the post-create commands\n");
+        return;
+    };
+    let Some(iid) = parse_mr_iid(&url) else {
+        return;
+    };
+    let repo = repo_dir
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let project = mr
+        .project
+        .as_ref()
+        .map(|p| p.path.clone())
+        .unwrap_or_default();
+    for command in post_create {
+        let mut cmd = process::Command::new("sh");
+        cmd.arg("-c")
+            .arg(render_hook(command, iid, &url, &repo, &project));
+        if repo_dir.is_dir() {
+            cmd.current_dir(repo_dir);
+        }
+        if let Err(e) = utils::run_checked(&mut cmd, log) {
+            log.push_str(&format!("Post-create command failed: {}\n", e));
+        }
+    }
+}
+
+/// Create the merge request for a single repo, returning whether it succeeded and its log.
+/// Offline, the steps that need GitLab are queued and the result is whether any were.
+pub(crate) fn run_repo(
+    repo_dir: &Path,
+    mr: &MergeRequest,
+    dry_run: bool,
+    offline: bool,
+    restore_branch: bool,
+    post_create: &[String],
+    throttle: &Throttle,
+) -> (Result<bool>, String) {
+    let mut log = String::new();
+    let original_branch = mr.branch_detection.current_branch(repo_dir);
+    let result = mr
+        .create(repo_dir, dry_run, offline, throttle, &mut log)
+        .and_then(|queued| {
+            if queued.is_empty() {
+                return Ok(false);
+            }
+            let branch = if repo_dir.is_dir() {
+                mr.branch_detection.current_branch(repo_dir)?
+            } else {
+                String::new()
+            };
+            queue::add(QueuedRepo::new(repo_dir, &mr.title, branch, &queued))?;
+            log.push_str("Offline, run `multimr flush` to push and create the merge request.\n");
+            Ok(true)
+        });
+    match &result {
+        Err(e) => log.push_str(&format!("Failed to create merge request: {}\n", e)),
+        Ok(false) if !dry_run => {
+            if !mr.reviewers.is_empty() {
+                confirm_reviewers(repo_dir, mr, &mut log);
+            }
+            if !post_create.is_empty() && !mr.update {
+                run_post_create(repo_dir, mr, post_create, &mut log);
+            }
+        }
+        Ok(_) => {}
+    }
+
+    if let Ok(original_branch) = original_branch
+        && restore_branch
+        && mr
+            .branch_detection
+            .current_branch(repo_dir)
+            .is_ok_and(|b| b != original_branch)
+        && let Err(e) = utils::switch_branch(repo_dir, &original_branch, &mut log)
+    {
+        log.push_str(&format!(
+            "Failed to switch back to branch {}: {}\n",
+            original_branch, e
+        ));
+    }
+
+    (result, log)
+}
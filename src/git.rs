@@ -0,0 +1,673 @@
+//! The git operations multimr performs in a checkout, run in-process with git2 instead of a
+//! `git` binary. Each is still shown as the `git` command it does the same as, on the
+//! Finalize screen, in emitted scripts and in the offline queue.
+use std::cell::RefCell;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use git2::build::CheckoutBuilder;
+use git2::{
+    BranchType, Cred, CredentialType, Direction, FetchOptions, IndexAddOption, ProxyOptions,
+    PushOptions, Remote, RemoteCallbacks, Repository, StashFlags,
+};
+
+use crate::error::Result;
+use crate::gitlab;
+use crate::limits::Throttle;
+use crate::utils;
+
+/// One git operation on the repo it runs in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Git {
+    /// `git add .`, leaving out the paths under `exclude_paths`
+    Add { exclude_paths: Vec<String> },
+    /// `git switch`, with `-c` to create the branch from `HEAD`
+    Switch { branch: String, create: bool },
+    /// `git commit -m`, with `-a` to stage the changed tracked files first
+    Commit { message: String, all: bool },
+    /// `git push --set-upstream origin HEAD` with `git push -o` options
+    Push { options: Vec<String> },
+    /// `git ls-remote --exit-code --heads origin`, fails when `branch` isn't on origin
+    LsRemote { branch: String },
+    /// `git stash push --include-untracked`
+    StashPush { message: String },
+    /// `git stash pop`
+    StashPop,
+    /// `git pull --ff-only`
+    Pull,
+    /// `git branch -D`
+    DeleteBranch { branch: String },
+}
+
+impl Git {
+    /// The `git` command line doing the same.
+    pub(crate) fn command(&self) -> process::Command {
+        let mut cmd = process::Command::new("git");
+        match self {
+            Git::Add { exclude_paths } => {
+                cmd.arg("add");
+                if !exclude_paths.is_empty() {
+                    cmd.arg("--");
+                }
+                cmd.arg(".");
+                for path in exclude_paths {
+                    cmd.arg(format!(":(exclude){}", path));
+                }
+            }
+            Git::Switch { branch, create } => {
+                cmd.arg("switch");
+                if *create {
+                    cmd.arg("-c");
+                }
+                cmd.arg(branch);
+            }
+            Git::Commit { message, all } => {
+                cmd.arg("commit")
+                    .arg(if *all { "-am" } else { "-m" })
+                    .arg(message);
+            }
+            Git::Push { options } => {
+                cmd.arg("push");
+                for option in options {
+                    cmd.arg("-o").arg(option);
+                }
+                cmd.arg("--set-upstream").arg("origin").arg("HEAD");
+            }
+            Git::LsRemote { branch } => {
+                cmd.args(["ls-remote", "--exit-code", "--heads", "origin"])
+                    .arg(branch);
+            }
+            Git::StashPush { message } => {
+                cmd.args(["stash", "push", "--include-untracked", "-m"])
+                    .arg(message);
+            }
+            Git::StashPop => {
+                cmd.args(["stash", "pop"]);
+            }
+            Git::Pull => {
+                cmd.args(["pull", "--ff-only"]);
+            }
+            Git::DeleteBranch { branch } => {
+                cmd.args(["branch", "-D"]).arg(branch);
+            }
+        }
+        cmd
+    }
+
+    /// The operation of a command line made by [`Git::command`], without the `git`, as
+    /// stored in the offline queue. `None` for any other command.
+    pub(crate) fn parse(args: &[String]) -> Option<Git> {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        Some(match args.as_slice() {
+            ["add", "."] => Git::Add {
+                exclude_paths: Vec::new(),
+            },
+            ["add", "--", ".", excludes @ ..] => Git::Add {
+                exclude_paths: excludes
+                    .iter()
+                    .map(|e| e.strip_prefix(":(exclude)").map(str::to_string))
+                    .collect::<Option<_>>()?,
+            },
+            ["switch", "-c", branch] => Git::Switch {
+                branch: branch.to_string(),
+                create: true,
+            },
+            ["switch", branch] => Git::Switch {
+                branch: branch.to_string(),
+                create: false,
+            },
+            ["commit", flag @ ("-am" | "-m"), message] => Git::Commit {
+                message: message.to_string(),
+                all: *flag == "-am",
+            },
+            ["push", options @ .., "--set-upstream", "origin", "HEAD"] => Git::Push {
+                options: options
+                    .chunks(2)
+                    .map(|pair| match pair {
+                        ["-o", option] => Some(option.to_string()),
+                        _ => None,
+                    })
+                    .collect::<Option<_>>()?,
+            },
+            ["ls-remote", "--exit-code", "--heads", "origin", branch] => Git::LsRemote {
+                branch: branch.to_string(),
+            },
+            ["stash", "push", "--include-untracked", "-m", message] => Git::StashPush {
+                message: message.to_string(),
+            },
+            ["stash", "pop"] => Git::StashPop,
+            ["pull", "--ff-only"] => Git::Pull,
+            ["branch", "-D", branch] => Git::DeleteBranch {
+                branch: branch.to_string(),
+            },
+            _ => return None,
+        })
+    }
+
+    /// Run the operation in the repo at `repo_dir`, appending the equivalent command line
+    /// and what happened to `log` like [`utils::run_checked`] does for commands.
+    pub(crate) fn run(&self, repo_dir: &Path, log: &mut String) -> Result<()> {
+        self.run_throttled(repo_dir, &Throttle::default(), log)
+    }
+
+    /// Like [`Git::run`], with the hooks of the repo run at the priority `throttle` sets.
+    pub(crate) fn run_throttled(
+        &self,
+        repo_dir: &Path,
+        throttle: &Throttle,
+        log: &mut String,
+    ) -> Result<()> {
+        log.push_str(&format!("$ {}\n", utils::shell_line(&self.command())));
+        let result = self.apply(repo_dir, throttle, log);
+        if let Err(e) = &result {
+            log.push_str(&format!("{}\n", e));
+        }
+        result
+    }
+
+    fn apply(&self, repo_dir: &Path, throttle: &Throttle, log: &mut String) -> Result<()> {
+        let mut repo = utils::open_repo(repo_dir)?;
+        match self {
+            Git::Add { exclude_paths } => {
+                let mut index = repo.index()?;
+                let mut skip = |path: &Path, _: &[u8]| {
+                    i32::from(is_excluded(exclude_paths, &path.to_string_lossy()))
+                };
+                index.add_all(["*"], IndexAddOption::DEFAULT, Some(&mut skip))?;
+                // Also stages the deletions, like `git add .`
+                index.update_all(["*"], Some(&mut skip))?;
+                index.write()?;
+            }
+            Git::Switch {
+                branch,
+                create: true,
+            } => {
+                match repo.head() {
+                    // Nothing committed yet, like with git the branch is born with the first
+                    // commit on it
+                    Err(e) if e.code() == git2::ErrorCode::UnbornBranch => {}
+                    head => {
+                        repo.branch(branch, &head?.peel_to_commit()?, false)?;
+                    }
+                }
+                repo.set_head(&format!("refs/heads/{}", branch))?;
+            }
+            Git::Switch {
+                branch,
+                create: false,
+            } => switch(&repo, branch)?,
+            Git::Commit { message, all } => commit(&repo, message, *all, throttle, log)?,
+            Git::Push { options } => push(&repo, options, throttle, log)?,
+            Git::LsRemote { branch } => {
+                let heads = remote_heads(&repo)?;
+                if !heads.contains(branch) {
+                    return Err(git2::Error::from_str(&format!(
+                        "branch {} is not on origin",
+                        branch
+                    ))
+                    .into());
+                }
+            }
+            Git::StashPush { message } => {
+                let signature = repo.signature()?;
+                repo.stash_save(&signature, message, Some(StashFlags::INCLUDE_UNTRACKED))?;
+            }
+            Git::StashPop => repo.stash_pop(0, None)?,
+            Git::Pull => pull(&repo, log)?,
+            Git::DeleteBranch { branch } => {
+                repo.find_branch(branch, BranchType::Local)?.delete()?;
+                log.push_str(&format!("Deleted branch {}\n", branch));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether `git add -- . ':(exclude)<excluded>'` leaves out `path`: the path itself, any
+/// path below it if it is a directory, or a path matching it as a glob.
+fn is_excluded(exclude_paths: &[String], path: &str) -> bool {
+    exclude_paths.iter().any(|excluded| {
+        let dir = excluded.trim_end_matches('/');
+        path == dir || path.starts_with(&format!("{}/", dir)) || utils::glob_match(excluded, path)
+    })
+}
+
+/// Check out the existing local `branch`, refusing to overwrite uncommitted changes.
+fn switch(repo: &Repository, branch: &str) -> Result<()> {
+    let target = repo
+        .find_branch(branch, BranchType::Local)?
+        .get()
+        .peel_to_commit()?;
+    repo.checkout_tree(target.as_object(), Some(CheckoutBuilder::new().safe()))?;
+    repo.set_head(&format!("refs/heads/{}", branch))?;
+    Ok(())
+}
+
+/// Commit the index, with the `pre-commit`, `commit-msg` and `post-commit` hooks of the repo
+/// run the way `git commit` runs them.
+fn commit(
+    repo: &Repository,
+    message: &str,
+    all: bool,
+    throttle: &Throttle,
+    log: &mut String,
+) -> Result<()> {
+    let mut index = repo.index()?;
+    if all {
+        index.update_all(["*"], None)?;
+        index.write()?;
+    }
+    run_hook(repo, "pre-commit", &[], None, throttle, log)?;
+    // The hook may have staged what it formatted
+    index.read(true)?;
+
+    let mut message = git2::message_prettify(message, Some(b'#'))?;
+    let edit_message = repo.path().join("COMMIT_EDITMSG");
+    if hook_path(repo, "commit-msg").is_some() {
+        let io = |e: std::io::Error| {
+            git2::Error::from_str(&format!("{}: {}", edit_message.display(), e))
+        };
+        std::fs::write(&edit_message, &message).map_err(io)?;
+        run_hook(
+            repo,
+            "commit-msg",
+            &[edit_message.as_os_str()],
+            None,
+            throttle,
+            log,
+        )?;
+        let edited = std::fs::read_to_string(&edit_message).map_err(io)?;
+        message = git2::message_prettify(edited, Some(b'#'))?;
+    }
+    if message.is_empty() {
+        return Err(git2::Error::from_str("aborting commit due to empty commit message").into());
+    }
+
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    if parent
+        .as_ref()
+        .is_some_and(|parent| parent.tree_id() == tree.id())
+    {
+        return Err(git2::Error::from_str("nothing to commit").into());
+    }
+    let signature = repo.signature()?;
+    let parents: Vec<_> = parent.iter().collect();
+    let oid = repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        &message,
+        &tree,
+        &parents,
+    )?;
+    let branch = repo.head()?.shorthand().unwrap_or("HEAD").to_string();
+    log.push_str(&format!(
+        "[{} {:.7}] {}\n",
+        branch,
+        oid,
+        message.lines().next().unwrap_or_default()
+    ));
+    // Like with git, the commit is made whatever the hook does
+    if let Err(e) = run_hook(repo, "post-commit", &[], None, throttle, log) {
+        log.push_str(&format!("{}\n", e));
+    }
+    Ok(())
+}
+
+/// The executable hook `name` of the repo, in `core.hooksPath` if set.
+fn hook_path(repo: &Repository, name: &str) -> Option<std::path::PathBuf> {
+    let hooks = match repo.config().ok()?.get_path("core.hooksPath") {
+        Ok(dir) if dir.is_relative() => repo.workdir()?.join(dir),
+        Ok(dir) => dir,
+        Err(_) => repo.path().join("hooks"),
+    };
+    let hook = hooks.join(name);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&hook).ok()?.permissions().mode();
+        (mode & 0o111 != 0).then_some(hook)
+    }
+    #[cfg(not(unix))]
+    hook.is_file().then_some(hook)
+}
+
+/// Run the hook `name` if the repo has it with `args` and `input` on its stdin, a failing
+/// hook fails the operation.
+fn run_hook(
+    repo: &Repository,
+    name: &str,
+    args: &[&OsStr],
+    input: Option<&str>,
+    throttle: &Throttle,
+    log: &mut String,
+) -> Result<()> {
+    let Some(hook) = hook_path(repo, name) else {
+        return Ok(());
+    };
+    let mut cmd = process::Command::new(hook);
+    cmd.args(args)
+        .env("GIT_INDEX_FILE", repo.path().join("index"))
+        .current_dir(repo.workdir().unwrap_or(repo.path()));
+    let mut cmd = throttle.wrap(cmd);
+    match input {
+        Some(input) => utils::run_checked_input(&mut cmd, input, log),
+        None => utils::run_checked(&mut cmd, log),
+    }
+}
+
+/// A key to offer an SSH server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SshKey {
+    /// The keys the ssh agent holds
+    Agent,
+    /// A private key file without a passphrase
+    File(PathBuf),
+}
+
+/// The keys to offer the SSH server of the remote `url` in turn, like ssh does: the agent's
+/// if one is running, the `IdentityFile`s ~/.ssh/config sets for the host, then the default
+/// ~/.ssh/id_* keys that exist.
+fn ssh_keys(url: &str) -> Vec<SshKey> {
+    let mut keys = Vec::new();
+    if cfg!(windows) || std::env::var_os("SSH_AUTH_SOCK").is_some() {
+        keys.push(SshKey::Agent);
+    }
+    let Some(home) = std::env::var_os("HOME").map(PathBuf::from) else {
+        return keys;
+    };
+    let ssh_dir = home.join(".ssh");
+    let host = gitlab::Project::from_remote_url(url)
+        .map(|project| project.host)
+        .unwrap_or_default();
+    let config = std::fs::read_to_string(ssh_dir.join("config")).unwrap_or_default();
+    let files = identity_files(&config, &host, &home)
+        .into_iter()
+        .chain(["id_ed25519", "id_ecdsa", "id_rsa"].map(|name| ssh_dir.join(name)));
+    for file in files {
+        if file.is_file() && !keys.contains(&SshKey::File(file.clone())) {
+            keys.push(SshKey::File(file));
+        }
+    }
+    keys
+}
+
+/// The `IdentityFile`s an ssh config sets for `host`, in order, with `~` expanded to `home`.
+/// Only `Host` blocks are matched, `Match` blocks and `Include`s are skipped.
+pub(crate) fn identity_files(config: &str, host: &str, home: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut matches = true;
+    for line in config.lines() {
+        let line = line.trim();
+        let Some((keyword, value)) = line
+            .split_once(|c: char| c.is_whitespace() || c == '=')
+            .map(|(keyword, value)| (keyword, value.trim_start_matches([' ', '\t', '=']).trim()))
+        else {
+            continue;
+        };
+        if keyword.eq_ignore_ascii_case("host") {
+            let patterns: Vec<&str> = value.split_whitespace().collect();
+            matches = !patterns.iter().any(|p| {
+                p.strip_prefix('!')
+                    .is_some_and(|negated| utils::glob_match(negated, host))
+            }) && patterns
+                .iter()
+                .any(|p| !p.starts_with('!') && utils::glob_match(p, host));
+        } else if keyword.eq_ignore_ascii_case("match") {
+            matches = false;
+        } else if matches && keyword.eq_ignore_ascii_case("identityfile") {
+            let value = value.trim_matches('"');
+            files.push(match value.strip_prefix("~/") {
+                Some(rest) => home.join(rest),
+                None => PathBuf::from(value),
+            });
+        }
+    }
+    files
+}
+
+/// Authenticate like git does without a terminal: the [`ssh_keys`] in turn for SSH remotes
+/// and the configured credential helpers for HTTPS. What the server prints goes to `output`.
+fn callbacks<'a>(repo: &Repository, output: &'a RefCell<String>) -> Result<RemoteCallbacks<'a>> {
+    let config = repo.config()?;
+    let mut attempts = 0;
+    let mut keys: Option<std::vec::IntoIter<SshKey>> = None;
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(move |url, username, allowed| {
+        if allowed.contains(CredentialType::SSH_KEY) {
+            // libgit2 asks again after every rejected key, the next one is offered then
+            let username = username.unwrap_or("git");
+            return match keys.get_or_insert_with(|| ssh_keys(url).into_iter()).next() {
+                Some(SshKey::Agent) => Cred::ssh_key_from_agent(username),
+                Some(SshKey::File(key)) => Cred::ssh_key(username, None, &key, None),
+                None => Err(git2::Error::from_str(
+                    "authentication failed, no SSH key was accepted",
+                )),
+            };
+        }
+        // libgit2 asks again after every rejected credential
+        attempts += 1;
+        if attempts > 3 {
+            return Err(git2::Error::from_str("authentication failed"));
+        }
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            Cred::credential_helper(&config, url, username)
+        } else {
+            Cred::default()
+        }
+    });
+    callbacks.sideband_progress(|data| {
+        output
+            .borrow_mut()
+            .push_str(&String::from_utf8_lossy(data).replace('\r', "\n"));
+        true
+    });
+    Ok(callbacks)
+}
+
+/// Proxy settings from `http.proxy` and the proxy environment variables.
+fn proxy_options<'a>() -> ProxyOptions<'a> {
+    let mut proxy = ProxyOptions::new();
+    proxy.auto();
+    proxy
+}
+
+/// The remote `name`, with a path relative to the working tree made absolute the way git
+/// resolves it, libgit2 only understands absolute ones.
+fn remote<'r>(repo: &'r Repository, name: &str) -> Result<Remote<'r>> {
+    let remote = repo.find_remote(name)?;
+    let url = remote.url().unwrap_or_default();
+    if let Some(workdir) = repo.workdir()
+        && is_path(url)
+        && Path::new(url).is_relative()
+    {
+        let url = workdir.join(url).to_string_lossy().to_string();
+        return Ok(repo.remote_anonymous(&url)?);
+    }
+    Ok(remote)
+}
+
+/// Whether the remote `url` is a path rather than a URL or `host:path`.
+fn is_path(url: &str) -> bool {
+    !url.contains("://")
+        && url
+            .split('/')
+            .next()
+            .is_none_or(|first| !first.contains(':'))
+}
+
+/// Push the current branch to the same name on origin and track it from there, once the
+/// `pre-push` hook of the repo let it like it does for `git push`.
+fn push(
+    repo: &Repository,
+    options: &[String],
+    throttle: &Throttle,
+    log: &mut String,
+) -> Result<()> {
+    let branch = repo
+        .head()?
+        .shorthand()
+        .filter(|_| !repo.head_detached().unwrap_or_default())
+        .ok_or_else(|| git2::Error::from_str("not on a branch"))?
+        .to_string();
+    let mut origin = remote(repo, "origin")?;
+    let url = origin.url().unwrap_or_default();
+    let refname = format!("refs/heads/{}", branch);
+    let remote_oid = repo
+        .refname_to_id(&format!("refs/remotes/origin/{}", branch))
+        .unwrap_or(git2::Oid::zero());
+    run_hook(
+        repo,
+        "pre-push",
+        &[OsStr::new("origin"), OsStr::new(url)],
+        Some(&format!(
+            "{0} {1} {0} {2}\n",
+            refname,
+            repo.head()?.peel_to_commit()?.id(),
+            remote_oid
+        )),
+        throttle,
+        log,
+    )?;
+    let options = if !options.is_empty() && (is_path(url) || url.starts_with("file://")) {
+        // They are for GitLab, libgit2 can't pass them to a repo on disk
+        log.push_str("Leaving out the push options, the remote is a path\n");
+        &[]
+    } else {
+        options
+    };
+    let output = RefCell::new(String::new());
+    let rejected = RefCell::new(None);
+    let result = {
+        let mut callbacks = callbacks(repo, &output)?;
+        callbacks.push_update_reference(|refname, status| {
+            if let Some(status) = status {
+                *rejected.borrow_mut() = Some(format!("{} rejected: {}", refname, status));
+            }
+            Ok(())
+        });
+        let options: Vec<&str> = options.iter().map(String::as_str).collect();
+        let mut push_options = PushOptions::new();
+        push_options
+            .remote_callbacks(callbacks)
+            .proxy_options(proxy_options())
+            .remote_push_options(&options);
+        let refspec = format!("{0}:{0}", refname);
+        origin.push(&[refspec.as_str()], Some(&mut push_options))
+    };
+    log.push_str(&output.into_inner());
+    result?;
+    if let Some(rejected) = rejected.into_inner() {
+        return Err(git2::Error::from_str(&rejected).into());
+    }
+
+    // What git records of the push, libgit2 leaves it out for remotes given by path
+    let pushed = repo.head()?.peel_to_commit()?.id();
+    repo.reference(
+        &format!("refs/remotes/origin/{}", branch),
+        pushed,
+        true,
+        "push",
+    )?;
+    let mut config = repo.config()?;
+    config.set_str(&format!("branch.{}.remote", branch), "origin")?;
+    config.set_str(
+        &format!("branch.{}.merge", branch),
+        &format!("refs/heads/{}", branch),
+    )?;
+    log.push_str(&format!("Pushed {0} to origin/{0}\n", branch));
+    Ok(())
+}
+
+/// The branches on origin.
+fn remote_heads(repo: &Repository) -> Result<Vec<String>> {
+    let output = RefCell::new(String::new());
+    let mut remote = remote(repo, "origin")?;
+    let connection = remote.connect_auth(
+        Direction::Fetch,
+        Some(callbacks(repo, &output)?),
+        Some(proxy_options()),
+    )?;
+    Ok(connection
+        .list()?
+        .iter()
+        .filter_map(|head| head.name().strip_prefix("refs/heads/"))
+        .map(str::to_string)
+        .collect())
+}
+
+/// The branches on `origin` of the repo at `repo_dir`, like `git ls-remote --heads origin`.
+pub(crate) fn ls_remote_heads(repo_dir: &Path) -> Result<Vec<String>> {
+    remote_heads(&utils::open_repo(repo_dir)?)
+}
+
+/// Fetch the upstream of the current branch and fast-forward to it.
+fn pull(repo: &Repository, log: &mut String) -> Result<()> {
+    let head = repo.head()?;
+    let refname = head
+        .name()
+        .ok_or_else(|| git2::Error::from_str("not on a branch"))?
+        .to_string();
+    let remote_name = repo.branch_upstream_remote(&refname)?;
+    let remote_name = remote_name.as_str().unwrap_or("origin");
+    let output = RefCell::new(String::new());
+    let result = {
+        let mut fetch_options = FetchOptions::new();
+        fetch_options
+            .remote_callbacks(callbacks(repo, &output)?)
+            .proxy_options(proxy_options());
+        // Given explicitly, a remote given by path has none of its own
+        let refspecs: Vec<String> = repo
+            .find_remote(remote_name)?
+            .fetch_refspecs()?
+            .iter()
+            .flatten()
+            .map(str::to_string)
+            .collect();
+        remote(repo, remote_name)?.fetch(&refspecs, Some(&mut fetch_options), None)
+    };
+    log.push_str(&output.into_inner());
+    result?;
+
+    let local = head
+        .target()
+        .ok_or_else(|| git2::Error::from_str("no commits yet"))?;
+    let upstream = git2::Branch::wrap(head)
+        .upstream()?
+        .get()
+        .peel_to_commit()?;
+    if upstream.id() == local || repo.graph_descendant_of(local, upstream.id())? {
+        log.push_str("Already up to date.\n");
+        return Ok(());
+    }
+    if !repo.graph_descendant_of(upstream.id(), local)? {
+        return Err(git2::Error::from_str("not possible to fast-forward, aborting").into());
+    }
+    repo.checkout_tree(upstream.as_object(), Some(CheckoutBuilder::new().safe()))?;
+    repo.find_reference(&refname)?
+        .set_target(upstream.id(), "pull: fast-forward")?;
+    log.push_str(&format!("Fast-forward to {:.7}\n", upstream.id()));
+    Ok(())
+}
+
+/// The paths where merging `theirs` into the current branch conflicts, merged in memory
+/// like `git merge-tree`. Uncommitted changes aren't part of it.
+pub(crate) fn merge_conflicts(repo: &Repository, theirs: &str) -> Result<Vec<String>> {
+    let ours = repo.head()?.peel_to_commit()?;
+    let theirs = repo.find_reference(theirs)?.peel_to_commit()?;
+    let index = repo.merge_commits(&ours, &theirs, None)?;
+    let mut paths = Vec::new();
+    for conflict in index.conflicts()? {
+        let conflict = conflict?;
+        let entry = conflict.our.or(conflict.their).or(conflict.ancestor);
+        if let Some(entry) = entry {
+            let path = String::from_utf8_lossy(&entry.path).to_string();
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+    }
+    Ok(paths)
+}
@@ -0,0 +1,559 @@
+//! Queries the GitLab API through `glab api`, so authentication is handled by glab.
+//! Every query takes the glab executable to run, since it can be configured per repo.
+use std::collections::HashMap;
+use std::path::Path;
+use std::process;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::cache;
+use crate::config::LabelFetch;
+use crate::error::Result;
+use crate::utils;
+
+/// Minimum access level GitLab requires for a user to review merge requests (Developer).
+pub(crate) const DEVELOPER_ACCESS: u32 = 30;
+/// Access level of project maintainers, owners have a higher one.
+pub(crate) const MAINTAINER_ACCESS: u32 = 40;
+
+/// A member of a GitLab project, including inherited group members.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct Member {
+    pub username: String,
+    pub access_level: u32,
+}
+
+/// A label defined on a GitLab project or one of its groups.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct Label {
+    pub name: String,
+}
+
+/// A merge request, only the fields needed to see who reviews it.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct OpenMergeRequest {
+    #[serde(default)]
+    pub reviewers: Vec<User>,
+}
+
+/// A GitLab user as embedded in other API responses.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct User {
+    pub username: String,
+}
+
+/// A user account as listed by the users API.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct UserAccount {
+    pub username: String,
+    /// `active`, or e.g. `blocked` or `deactivated` for accounts that can't review
+    pub state: String,
+}
+
+/// A project as listed in a group, only the fields needed to work on it remotely.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct GroupProject {
+    pub path_with_namespace: String,
+    /// `None` for projects without any commits
+    pub default_branch: Option<String>,
+}
+
+/// A GitLab project, identified by the host it lives on and its full path.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Project {
+    pub host: String,
+    /// Full path including all (sub)groups, e.g. `group/subgroup/project`
+    pub path: String,
+}
+
+impl Project {
+    /// Parse a project from a git remote URL in any of the forms GitLab hands out:
+    /// `git@host:group/project.git`, `ssh://git@host:22/group/project.git` or
+    /// `https://host/group/project.git`.
+    pub(crate) fn from_remote_url(url: &str) -> Option<Project> {
+        let url = url.trim();
+        let (host, path) = if let Some((_, rest)) = url.split_once("://") {
+            let (authority, path) = rest.split_once('/')?;
+            let host = authority.rsplit('@').next()?;
+            let host = host.split(':').next()?;
+            (host, path)
+        } else {
+            // scp-like syntax: [user@]host:path
+            let (authority, path) = url.split_once(':')?;
+            (authority.rsplit('@').next()?, path)
+        };
+
+        let path = path.trim_matches('/');
+        let path = path.strip_suffix(".git").unwrap_or(path);
+        if host.is_empty() || !path.contains('/') {
+            return None;
+        }
+        Some(Project {
+            host: host.to_string(),
+            path: path.to_string(),
+        })
+    }
+
+    /// Parse a project given as `group/project` or `host/group/project` in the config.
+    pub(crate) fn from_config(project: &str, default_host: &str) -> Project {
+        Project::from_remote_url(&format!("https://{}", project))
+            .filter(|p| p.host.contains('.'))
+            .unwrap_or_else(|| Project {
+                host: default_host.to_string(),
+                path: project.trim_matches('/').to_string(),
+            })
+    }
+
+    /// A project on the host glab is logged in to by default.
+    pub(crate) fn on_default_host(path: &str) -> Project {
+        Project {
+            host: String::new(),
+            path: path.to_string(),
+        }
+    }
+
+    /// The value for glab's `--repo` flag.
+    pub(crate) fn repo_arg(&self) -> String {
+        if self.host.is_empty() {
+            self.path.clone()
+        } else {
+            format!("https://{}/{}", self.host, self.path)
+        }
+    }
+
+    /// The project ID as used in API endpoints, the URL-encoded full path.
+    pub(crate) fn api_id(&self) -> String {
+        self.path.replace('/', "%2F")
+    }
+}
+
+/// The project the `origin` remote of the repo points to.
+pub(crate) fn project_from_remote(repo_dir: &Path) -> Option<Project> {
+    Project::from_remote_url(&utils::origin_url(repo_dir).ok()?)
+}
+
+/// Call a paginated GitLab API endpoint for the project of `repo_dir`, `:id` in the endpoint
+/// is replaced by its project. Without a recognizable remote, glab resolves `:id` itself.
+fn api_list<T: DeserializeOwned>(glab: &str, repo_dir: &Path, endpoint: &str) -> Result<Vec<T>> {
+    let mut cmd = process::Command::new(glab);
+    cmd.arg("api").arg("--paginate").current_dir(repo_dir);
+    match project_from_remote(repo_dir) {
+        Some(project) => cmd
+            .arg("--hostname")
+            .arg(&project.host)
+            .arg(endpoint.replace(":id", &project.api_id())),
+        None => cmd.arg(endpoint),
+    };
+    run_api_list(cmd)
+}
+
+/// Call a paginated GitLab API endpoint on the host glab is logged in to by default,
+/// for endpoints that don't belong to a local checkout.
+fn api_list_remote<T: DeserializeOwned>(glab: &str, endpoint: &str) -> Result<Vec<T>> {
+    let mut cmd = process::Command::new(glab);
+    cmd.arg("api").arg("--paginate").arg(endpoint);
+    run_api_list(cmd)
+}
+
+/// Run a `glab api --paginate` command and collect the items of all pages.
+fn run_api_list<T: DeserializeOwned>(mut cmd: process::Command) -> Result<Vec<T>> {
+    let stdout = utils::run_output(&mut cmd)?;
+
+    // `--paginate` prints every page as its own JSON array, one after another.
+    let mut items = Vec::new();
+    for page in serde_json::Deserializer::from_slice(&stdout).into_iter::<Vec<T>>() {
+        items.extend(page?);
+    }
+    Ok(items)
+}
+
+/// Call a GitLab API endpoint returning a single object for `project`, `:id` in the
+/// endpoint is replaced by it. Without a project glab resolves `:id` from `repo_dir`.
+fn api_get<T: DeserializeOwned>(
+    glab: &str,
+    repo_dir: &Path,
+    project: Option<&Project>,
+    endpoint: &str,
+) -> Result<T> {
+    let mut cmd = process::Command::new(glab);
+    cmd.arg("api");
+    if repo_dir.is_dir() {
+        cmd.current_dir(repo_dir);
+    }
+    match project {
+        Some(project) => cmd
+            .arg("--hostname")
+            .arg(&project.host)
+            .arg(endpoint.replace(":id", &project.api_id())),
+        None => cmd.arg(endpoint),
+    };
+    Ok(serde_json::from_slice(&utils::run_output(&mut cmd)?)?)
+}
+
+/// The reviewers GitLab actually set on merge request `iid`, it silently drops usernames
+/// it doesn't know.
+pub(crate) fn merge_request_reviewers(
+    glab: &str,
+    repo_dir: &Path,
+    project: Option<&Project>,
+    iid: u64,
+) -> Result<Vec<String>> {
+    let mr: OpenMergeRequest = api_get(
+        glab,
+        repo_dir,
+        project,
+        &format!("projects/:id/merge_requests/{}", iid),
+    )?;
+    Ok(mr.reviewers.into_iter().map(|r| r.username).collect())
+}
+
+/// Whether glab gets an answer from `host`, through the proxy and host settings it uses for
+/// everything else. An empty host is the one glab uses by default.
+pub(crate) fn probe(glab: &str, host: &str) -> Result<()> {
+    let mut cmd = process::Command::new(glab);
+    cmd.arg("api");
+    if !host.is_empty() {
+        cmd.arg("--hostname").arg(host);
+    }
+    cmd.arg("version");
+    utils::run_output(&mut cmd).map(|_| ())
+}
+
+/// The user glab is logged in as on its default host.
+pub(crate) fn current_user(glab: &str) -> Result<User> {
+    let mut cmd = process::Command::new(glab);
+    cmd.arg("api").arg("user");
+    Ok(serde_json::from_slice(&utils::run_output(&mut cmd)?)?)
+}
+
+/// The account of `username` on glab's default host, `None` if there is none.
+pub(crate) fn user_account(glab: &str, username: &str) -> Result<Option<UserAccount>> {
+    let accounts: Vec<UserAccount> =
+        api_list_remote(glab, &format!("users?username={}", username))?;
+    Ok(accounts
+        .into_iter()
+        .find(|a| a.username.eq_ignore_ascii_case(username)))
+}
+
+/// All members of the project, including those inherited from parent groups.
+pub(crate) fn project_members(glab: &str, repo_dir: &Path) -> Result<Vec<Member>> {
+    api_list(glab, repo_dir, "projects/:id/members/all?per_page=100")
+}
+
+/// All labels available to the project, including group labels.
+pub(crate) fn project_labels(glab: &str, repo_dir: &Path) -> Result<Vec<Label>> {
+    api_list(glab, repo_dir, "projects/:id/labels?per_page=100")
+}
+
+/// All projects in the group and its subgroups, except archived ones.
+pub(crate) fn group_projects(glab: &str, group: &str) -> Result<Vec<GroupProject>> {
+    let mut projects: Vec<GroupProject> = api_list_remote(
+        glab,
+        &format!(
+            "groups/{}/projects?include_subgroups=true&archived=false&per_page=100",
+            group.trim_matches('/').replace('/', "%2F")
+        ),
+    )?;
+    projects.sort_by(|a, b| a.path_with_namespace.cmp(&b.path_with_namespace));
+    Ok(projects)
+}
+
+/// All open merge requests of the project.
+pub(crate) fn open_merge_requests(glab: &str, repo_dir: &Path) -> Result<Vec<OpenMergeRequest>> {
+    api_list(
+        glab,
+        repo_dir,
+        "projects/:id/merge_requests?state=opened&per_page=100",
+    )
+}
+
+/// A merge request as listed by the API, for the digest and to find the one to update.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct ListedMergeRequest {
+    pub iid: u64,
+    pub title: String,
+    pub web_url: String,
+    pub author: User,
+    /// All timestamps are ISO 8601 in UTC, e.g. `2024-05-02T09:30:00.000Z`
+    pub created_at: String,
+    pub merged_at: Option<String>,
+    pub closed_at: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub draft: bool,
+    /// Only returned for a single merge request, not in lists
+    #[serde(default)]
+    pub head_pipeline: Option<Pipeline>,
+    /// Whether it can be merged or what blocks it, e.g. `mergeable`, `not_approved`,
+    /// `ci_still_running` or `conflict`
+    #[serde(default)]
+    pub detailed_merge_status: Option<String>,
+    /// `opened`, `merged`, `closed` or `locked`
+    #[serde(default)]
+    pub state: String,
+    #[serde(default)]
+    pub source_branch: String,
+    #[serde(default)]
+    pub target_branch: String,
+    /// The last commit of the source branch, the one that was merged once it is merged
+    #[serde(default)]
+    pub sha: Option<String>,
+}
+
+/// A CI pipeline, only its outcome.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct Pipeline {
+    /// `success`, `failed`, `running`, `pending`, `canceled`, ...
+    pub status: String,
+}
+
+/// The merge requests of the project that were updated after `since`, an ISO 8601 timestamp.
+pub(crate) fn merge_requests_since(
+    glab: &str,
+    repo_dir: &Path,
+    since: &str,
+) -> Result<Vec<ListedMergeRequest>> {
+    api_list(
+        glab,
+        repo_dir,
+        &format!(
+            "projects/:id/merge_requests?state=all&updated_after={}&per_page=100",
+            since
+        ),
+    )
+}
+
+/// The open merge requests of the project created by the user glab is logged in as.
+pub(crate) fn my_open_merge_requests(
+    glab: &str,
+    repo_dir: &Path,
+) -> Result<Vec<ListedMergeRequest>> {
+    api_list(
+        glab,
+        repo_dir,
+        "projects/:id/merge_requests?state=opened&scope=created_by_me&per_page=100",
+    )
+}
+
+/// My open merge requests in the projects of `group` and its subgroups.
+pub(crate) fn my_open_group_merge_requests(
+    glab: &str,
+    group: &str,
+) -> Result<Vec<ListedMergeRequest>> {
+    api_list_remote(
+        glab,
+        &format!(
+            "groups/{}/merge_requests?state=opened&scope=created_by_me&per_page=100",
+            group.trim_matches('/').replace('/', "%2F")
+        ),
+    )
+}
+
+/// The merge requests of the project of `repo_dir` in any state whose description contains
+/// `text`, which must not need URL encoding.
+pub(crate) fn merge_requests_mentioning(
+    glab: &str,
+    repo_dir: &Path,
+    text: &str,
+) -> Result<Vec<ListedMergeRequest>> {
+    api_list(
+        glab,
+        repo_dir,
+        &format!(
+            "projects/:id/merge_requests?state=all&in=description&search={}&per_page=100",
+            text
+        ),
+    )
+}
+
+/// Merge request `iid` of `project`, or of the project of `repo_dir`, with its latest pipeline.
+pub(crate) fn merge_request(
+    glab: &str,
+    repo_dir: &Path,
+    project: Option<&Project>,
+    iid: u64,
+) -> Result<ListedMergeRequest> {
+    api_get(
+        glab,
+        repo_dir,
+        project,
+        &format!("projects/:id/merge_requests/{}", iid),
+    )
+}
+
+/// Who approved a merge request and how many approvals it needs.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub(crate) struct Approvals {
+    #[serde(default)]
+    pub approved_by: Vec<Approver>,
+    #[serde(default)]
+    pub approvals_required: u32,
+}
+
+/// An approval of a merge request.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct Approver {
+    pub user: User,
+}
+
+/// The approvals of merge request `iid` of `project`, or of the project of `repo_dir`.
+pub(crate) fn merge_request_approvals(
+    glab: &str,
+    repo_dir: &Path,
+    project: Option<&Project>,
+    iid: u64,
+) -> Result<Approvals> {
+    api_get(
+        glab,
+        repo_dir,
+        project,
+        &format!("projects/:id/merge_requests/{}/approvals", iid),
+    )
+}
+
+/// The open merge request from `branch` in `project`, or in the project of `repo_dir`.
+pub(crate) fn open_merge_request_for_branch(
+    glab: &str,
+    repo_dir: &Path,
+    project: Option<&Project>,
+    branch: &str,
+) -> Result<Option<ListedMergeRequest>> {
+    let mrs: Vec<ListedMergeRequest> = api_get(
+        glab,
+        repo_dir,
+        project,
+        &format!(
+            "projects/:id/merge_requests?state=opened&source_branch={}",
+            branch
+                .replace('%', "%25")
+                .replace('#', "%23")
+                .replace('&', "%26")
+        ),
+    )?;
+    Ok(mrs.into_iter().next())
+}
+
+/// Check that every label exists on the project and every reviewer can review on it.
+/// Returns a human readable description of each problem found.
+pub(crate) fn validate_labels_and_reviewers(
+    glab: &str,
+    repo_dir: &Path,
+    labels: &[String],
+    reviewers: &[String],
+) -> Result<Vec<String>> {
+    let mut problems = Vec::new();
+
+    if !labels.is_empty() {
+        let available = project_labels(glab, repo_dir)?;
+        for label in labels {
+            if !available.iter().any(|l| &l.name == label) {
+                problems.push(format!("label '{}' does not exist", label));
+            }
+        }
+    }
+
+    if !reviewers.is_empty() {
+        let members = project_members(glab, repo_dir)?;
+        for reviewer in reviewers {
+            match members.iter().find(|m| &m.username == reviewer) {
+                None => problems.push(format!("reviewer '{}' is not a member", reviewer)),
+                Some(m) if m.access_level < DEVELOPER_ACCESS => {
+                    problems.push(format!("reviewer '{}' lacks Developer access", reviewer))
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    Ok(problems)
+}
+
+/// The first maintainer of the project, skipping bot users, or `None` if there is none.
+pub(crate) fn first_maintainer(glab: &str, repo_dir: &Path) -> Result<Option<String>> {
+    Ok(project_members(glab, repo_dir)?
+        .into_iter()
+        .find(|m| m.access_level >= MAINTAINER_ACCESS && !m.username.contains("_bot"))
+        .map(|m| m.username))
+}
+
+/// Usernames of project members allowed to review, cached on disk for the next run.
+pub(crate) fn reviewer_candidates(glab: &str, repo_dir: &Path) -> Result<Vec<String>> {
+    let usernames: Vec<String> = project_members(glab, repo_dir)?
+        .into_iter()
+        .filter(|m| m.access_level >= DEVELOPER_ACCESS)
+        .map(|m| m.username)
+        .collect();
+    cache::store(&cache::repo_key("members", repo_dir), &usernames);
+    Ok(usernames)
+}
+
+/// Reviewer candidates from the last successful [`reviewer_candidates`] call, if any.
+pub(crate) fn cached_reviewer_candidates(repo_dir: &Path) -> Option<Vec<String>> {
+    cache::load(&cache::repo_key("members", repo_dir))
+}
+
+/// Names of all labels available to the project, cached on disk for the next run.
+pub(crate) fn label_names(glab: &str, repo_dir: &Path) -> Result<Vec<String>> {
+    let names: Vec<String> = project_labels(glab, repo_dir)?
+        .into_iter()
+        .map(|l| l.name)
+        .collect();
+    cache::store(&cache::repo_key("labels", repo_dir), &names);
+    Ok(names)
+}
+
+/// Label names from the last successful [`label_names`] call, if any.
+pub(crate) fn cached_label_names(repo_dir: &Path) -> Option<Vec<String>> {
+    cache::load(&cache::repo_key("labels", repo_dir))
+}
+
+/// Number of open merge requests in the project per reviewer, cached on disk for the next run.
+pub(crate) fn review_load(glab: &str, repo_dir: &Path) -> Result<HashMap<String, usize>> {
+    let mut load = HashMap::new();
+    for mr in open_merge_requests(glab, repo_dir)? {
+        for reviewer in mr.reviewers {
+            *load.entry(reviewer.username).or_default() += 1;
+        }
+    }
+    cache::store(&cache::repo_key("review-load", repo_dir), &load);
+    Ok(load)
+}
+
+/// Review load from the last successful [`review_load`] call, if any.
+pub(crate) fn cached_review_load(repo_dir: &Path) -> Option<HashMap<String, usize>> {
+    cache::load(&cache::repo_key("review-load", repo_dir))
+}
+
+/// Add up the review load of several projects.
+pub(crate) fn sum_review_load(
+    per_project: impl IntoIterator<Item = HashMap<String, usize>>,
+) -> HashMap<String, usize> {
+    let mut total = HashMap::new();
+    for load in per_project {
+        for (username, count) in load {
+            *total.entry(username).or_default() += count;
+        }
+    }
+    total
+}
+
+/// Combine the labels of several projects, keeping the order in which they first appear.
+pub(crate) fn combine_labels(per_project: Vec<Vec<String>>, mode: LabelFetch) -> Vec<String> {
+    let mut combined: Vec<String> = Vec::new();
+    for name in per_project.iter().flatten() {
+        if combined.contains(name) {
+            continue;
+        }
+        let keep = match mode {
+            LabelFetch::Off => false,
+            LabelFetch::Union => true,
+            LabelFetch::Intersection => per_project.iter().all(|labels| labels.contains(name)),
+        };
+        if keep {
+            combined.push(name.clone());
+        }
+    }
+    combined
+}
@@ -0,0 +1,62 @@
+//! Clickable links in terminals that support OSC 8 hyperlinks, for the MR URLs and repo names
+//! of the results screen and the summary printed afterwards.
+use ratatui::{buffer::Buffer, layout::Rect};
+
+use crate::config::Config;
+
+/// Whether the terminal is known to render OSC 8 hyperlinks, judged by the variables it sets.
+/// Terminals that don't support them might print the escape codes as garbage, so unknown
+/// ones are assumed not to.
+pub(crate) fn supported(var: impl Fn(&str) -> Option<String>) -> bool {
+    let program = var("TERM_PROGRAM").unwrap_or_default();
+    let term = var("TERM").unwrap_or_default();
+    ["iTerm.app", "WezTerm", "vscode", "ghostty", "Hyper"].contains(&program.as_str())
+        || term.contains("kitty")
+        || term.contains("ghostty")
+        || var("KITTY_WINDOW_ID").is_some()
+        || var("WT_SESSION").is_some()
+        // GNOME Terminal and other VTE based terminals since 0.50
+        || var("VTE_VERSION").and_then(|v| v.parse::<u32>().ok()) >= Some(5000)
+}
+
+/// Whether to emit hyperlinks: as configured by `hyperlinks`, detected otherwise.
+pub(crate) fn enabled(config: &Config) -> bool {
+    config
+        .hyperlinks
+        .unwrap_or_else(|| supported(|name| std::env::var(name).ok()))
+}
+
+/// `text` as a hyperlink to `url`.
+pub(crate) fn osc8(url: &str, text: &str) -> String {
+    format!("\x1B]8;;{}\x07{}\x1B]8;;\x07", url, text)
+}
+
+/// The web page of the project a merge request URL belongs to.
+pub(crate) fn project_url(mr_url: &str) -> &str {
+    mr_url.split("/-/").next().unwrap_or(mr_url)
+}
+
+/// Turn the first occurrence of `text` in the already rendered `row` into a hyperlink to `url`.
+/// ratatui counts the escape codes as wide text, so the link is written in chunks of two
+/// cells with the second one skipped, see <https://github.com/ratatui/ratatui/issues/902>.
+pub(crate) fn link_text(buf: &mut Buffer, row: Rect, text: &str, url: &str) {
+    let cells: Vec<(u16, String)> = (row.left()..row.right())
+        .map(|x| (x, buf[(x, row.y)].symbol().to_string()))
+        .collect();
+    let wanted: Vec<char> = text.chars().collect();
+    let Some(start) = cells.windows(wanted.len().max(1)).position(|window| {
+        window
+            .iter()
+            .zip(&wanted)
+            .all(|((_, symbol), c)| symbol.chars().eq(std::iter::once(*c)))
+    }) else {
+        return;
+    };
+    for chunk in cells[start..start + wanted.len()].chunks(2) {
+        let chunk_text: String = chunk.iter().map(|(_, symbol)| symbol.as_str()).collect();
+        buf[(chunk[0].0, row.y)].set_symbol(&osc8(url, &chunk_text));
+        if let Some((x, _)) = chunk.get(1) {
+            buf[(*x, row.y)].set_skip(true);
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! `multimr init`: asks for the basic settings on the terminal and writes a commented
+//! `multimr.toml`, so a first config doesn't have to be written from the README.
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use crate::gitlab;
+
+/// The settings asked for by [`ask`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct Answers {
+    pub working_dir: String,
+    pub assignee: Option<String>,
+    pub reviewers: Vec<String>,
+    /// Shortcuts shown in the label picker and the GitLab labels they stand for
+    pub labels: Vec<(String, String)>,
+}
+
+/// Ask `question` and return the trimmed answer, `default` if it is left empty.
+fn prompt(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    question: &str,
+    default: &str,
+) -> io::Result<String> {
+    if default.is_empty() {
+        write!(output, "{}: ", question)?;
+    } else {
+        write!(output, "{} [{}]: ", question, default)?;
+    }
+    output.flush()?;
+    let mut line = String::new();
+    input.read_line(&mut line)?;
+    let answer = line.trim();
+    Ok(if answer.is_empty() { default } else { answer }.to_string())
+}
+
+/// Ask a yes/no `question`, no unless answered with y or yes.
+pub(crate) fn confirm(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    question: &str,
+) -> io::Result<bool> {
+    let answer = prompt(input, output, &format!("{} [y/N]", question), "")?;
+    Ok(matches!(answer.to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Reviewer suggestions: the members of the first project found in `working_dir`.
+fn suggested_reviewers(glab: &str, working_dir: &Path) -> Option<(String, Vec<String>)> {
+    let mut dirs: Vec<_> = std::fs::read_dir(working_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.join(".git").exists())
+        .collect();
+    dirs.sort();
+    dirs.iter().find_map(|dir| {
+        let project = gitlab::project_from_remote(dir)?;
+        let members = gitlab::reviewer_candidates(glab, dir).ok()?;
+        Some((project.path, members))
+    })
+}
+
+/// Walk through the settings, suggesting the logged in glab user as assignee and the
+/// members of a project in the working directory as reviewers. `cwd` is where the config
+/// will be written, the working directory is relative to it.
+pub(crate) fn ask(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    glab: &str,
+    cwd: &Path,
+) -> io::Result<Answers> {
+    writeln!(
+        output,
+        "Creating a multimr.toml, leave an answer empty to skip it."
+    )?;
+    let working_dir = prompt(
+        input,
+        output,
+        "Directory holding the repositories, relative to this one",
+        ".",
+    )?;
+
+    let user = gitlab::current_user(glab)
+        .map(|u| u.username)
+        .unwrap_or_default();
+    let assignee = prompt(input, output, "Assign the MRs to (GitLab username)", &user)?;
+
+    if confirm(
+        input,
+        output,
+        "Look up reviewers among the members of a project in that directory?",
+    )? {
+        match suggested_reviewers(glab, &cwd.join(&working_dir)) {
+            Some((project, members)) => {
+                writeln!(output, "Members of {}: {}", project, members.join(", "))?
+            }
+            None => writeln!(output, "No GitLab project with members found there.")?,
+        }
+    }
+    let reviewers = prompt(input, output, "Reviewers, separated by commas", "")?
+        .split(',')
+        .map(|r| r.trim().trim_start_matches('@').to_string())
+        .filter(|r| !r.is_empty())
+        .collect();
+
+    writeln!(
+        output,
+        "Labels are picked by a shortcut, e.g. `fix type::fix` offers type::fix as fix."
+    )?;
+    let mut labels = Vec::new();
+    loop {
+        let answer = prompt(input, output, "Shortcut and label (empty to finish)", "")?;
+        if answer.is_empty() {
+            break;
+        }
+        // A single word is both the shortcut and the label
+        let (shortcut, label) = answer
+            .split_once(char::is_whitespace)
+            .unwrap_or((&answer, &answer));
+        labels.push((shortcut.to_string(), label.trim().to_string()));
+    }
+
+    Ok(Answers {
+        working_dir,
+        assignee: Some(assignee).filter(|a| !a.is_empty()),
+        reviewers,
+        labels,
+    })
+}
+
+/// `value` as a TOML string.
+fn string(value: &str) -> String {
+    toml::Value::String(value.to_string()).to_string()
+}
+
+/// `key` as a TOML key, quoted unless it is a bare key.
+fn key(key: &str) -> String {
+    if !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        key.to_string()
+    } else {
+        string(key)
+    }
+}
+
+/// The config file for `answers`, with a comment on each setting and the common optional
+/// ones commented out.
+pub(crate) fn render(answers: &Answers) -> String {
+    let mut lines = vec![
+        "# Configuration of multimr, see https://github.com/h0uter/multimr#features".to_string(),
+        "# Check it with `multimr config check`.".to_string(),
+        String::new(),
+        "# Directory whose git repositories are listed, relative to this file".to_string(),
+        format!("working_dir = {}", string(&answers.working_dir)),
+        String::new(),
+        "# GitLab user the merge requests are assigned to".to_string(),
+    ];
+    match &answers.assignee {
+        Some(assignee) => lines.push(format!("assignee = {}", string(assignee))),
+        None => lines.push("# assignee = \"your-username\"".to_string()),
+    }
+    lines.push(String::new());
+    lines.push("# Offered on the reviewer screen, next to the project members".to_string());
+    let reviewers: Vec<String> = answers.reviewers.iter().map(|r| string(r)).collect();
+    lines.push(format!("reviewers = [{}]", reviewers.join(", ")));
+    lines.push(String::new());
+    lines.push("# Branch the merge requests target instead of the default branch".to_string());
+    lines.push("# [repo_overrides.some-repo]".to_string());
+    lines.push("# target_branch = \"develop\"".to_string());
+    lines.push(String::new());
+    lines.push("# Label picker shortcuts and the GitLab labels they stand for".to_string());
+    lines.push("[labels]".to_string());
+    if answers.labels.is_empty() {
+        lines.push("# fix = \"type::fix\"".to_string());
+    }
+    for (shortcut, label) in &answers.labels {
+        lines.push(format!("{} = {}", key(shortcut), string(label)));
+    }
+    lines.join("\n") + "\n"
+}
@@ -0,0 +1,57 @@
+//! How often and how recently each label was picked, so the label picker offers the usual
+//! ones first instead of somewhere down a long list.
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils;
+
+/// How a label, or label preset, was used.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Usage {
+    /// Number of batches it was picked for
+    pub(crate) count: u32,
+    /// When it was last picked, in seconds since the Unix epoch
+    pub(crate) last_used: u64,
+}
+
+/// Usage keyed by the label or preset name as offered in the picker.
+pub(crate) type LabelUsage = HashMap<String, Usage>;
+
+/// The file holding the usage in the state directory.
+pub(crate) fn default_path() -> Option<PathBuf> {
+    utils::state_dir().map(|dir| dir.join("labels.json"))
+}
+
+/// The recorded usage, none if the file is missing or unreadable since it only orders labels.
+pub(crate) fn load(path: &Path) -> LabelUsage {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Count one more use of each of `labels` at `now`.
+pub(crate) fn record(path: &Path, labels: &[String], now: u64) -> std::io::Result<()> {
+    let mut usage = load(path);
+    for label in labels {
+        let entry = usage.entry(label.clone()).or_default();
+        entry.count += 1;
+        entry.last_used = now;
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(&usage)?)
+}
+
+/// Sort `labels` by how often they were used, the most recently used first among equally
+/// often used ones. Labels never used keep their order after the others.
+pub(crate) fn sort(labels: &mut [(String, String)], usage: &LabelUsage) {
+    labels.sort_by_key(|(name, _)| {
+        let used = usage.get(name).copied().unwrap_or_default();
+        (Reverse(used.count), Reverse(used.last_used))
+    });
+}
@@ -0,0 +1,35 @@
+//! The reviewers last picked for each repo, so routine batches start with them selected.
+//! Repo ownership rarely changes, the same people usually review the same repos.
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::utils;
+
+/// Reviewers keyed by the path of the repo they were picked for.
+pub(crate) type LastReviewers = HashMap<String, Vec<String>>;
+
+/// The file holding the picks in the state directory.
+pub(crate) fn default_path() -> Option<PathBuf> {
+    utils::state_dir().map(|dir| dir.join("reviewers.json"))
+}
+
+/// The remembered picks, none if the file is missing or unreadable since they are only a
+/// starting point.
+pub(crate) fn load(path: &Path) -> LastReviewers {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Remember `reviewers` for each of `repos`, keeping the picks of the other repos.
+pub(crate) fn save(path: &Path, repos: &[String], reviewers: &[String]) -> std::io::Result<()> {
+    let mut picks = load(path);
+    for repo in repos {
+        picks.insert(repo.clone(), reviewers.to_vec());
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(&picks)?)
+}
@@ -0,0 +1,143 @@
+//! Keeps a batch from taking over the machine: how many repos run at once, how many
+//! subprocesses and git hooks run at the same time, and at which priority the hooks run.
+use std::process;
+use std::sync::{Condvar, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+/// The `[limits]` of the config, every limit is off by default.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Limits {
+    /// Repos handled at the same time, one after the other when not set
+    pub parallel: Option<usize>,
+    /// Subprocesses running at the same time over all repos, git and glab alike
+    pub max_processes: Option<usize>,
+    /// git commands running at the same time, they run the pre-commit and pre-push hooks,
+    /// separately from the network-bound glab calls
+    pub max_parallel_hooks: Option<usize>,
+    /// Run the git hooks with this niceness, e.g. 10 to keep the machine usable while they run
+    pub nice: Option<i32>,
+    /// Run the git hooks in the idle I/O scheduling class with `ionice -c 3`, Linux only
+    #[serde(default)]
+    pub idle_io: bool,
+}
+
+impl Limits {
+    /// Number of repos to handle at the same time.
+    pub(crate) fn parallel(&self) -> usize {
+        self.parallel.unwrap_or(1).max(1)
+    }
+}
+
+/// A fixed number of slots that threads wait for, or no limit at all.
+#[derive(Debug, Default)]
+struct Slots {
+    /// Slots still free, `None` when unlimited
+    free: Option<Mutex<usize>>,
+    freed: Condvar,
+}
+
+impl Slots {
+    fn new(limit: Option<usize>) -> Self {
+        Self {
+            free: limit.map(|limit| Mutex::new(limit.max(1))),
+            freed: Condvar::new(),
+        }
+    }
+
+    /// Wait for a free slot.
+    fn take(&self) -> SlotGuard<'_> {
+        if let Some(free) = &self.free {
+            let mut free = self
+                .freed
+                .wait_while(free.lock().unwrap_or_else(|e| e.into_inner()), |free| {
+                    *free == 0
+                })
+                .unwrap_or_else(|e| e.into_inner());
+            *free -= 1;
+        }
+        SlotGuard(self)
+    }
+}
+
+/// A taken slot, given back when dropped.
+struct SlotGuard<'a>(&'a Slots);
+
+impl Drop for SlotGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(free) = &self.0.free {
+            *free.lock().unwrap_or_else(|e| e.into_inner()) += 1;
+            self.0.freed.notify_one();
+        }
+    }
+}
+
+/// The limits shared by every worker of a batch.
+#[derive(Debug, Default)]
+pub(crate) struct Throttle {
+    processes: Slots,
+    hooks: Slots,
+    nice: Option<i32>,
+    idle_io: bool,
+}
+
+/// Permission to run a command, the slots are given back when it is dropped.
+pub(crate) struct Permit<'a> {
+    _hook: Option<SlotGuard<'a>>,
+    _process: SlotGuard<'a>,
+}
+
+impl Throttle {
+    pub(crate) fn new(limits: &Limits) -> Self {
+        Self {
+            processes: Slots::new(limits.max_processes),
+            hooks: Slots::new(limits.max_parallel_hooks),
+            nice: limits.nice,
+            idle_io: limits.idle_io,
+        }
+    }
+
+    /// Wait until `cmd` may run. The hook slot is taken first, so a git command waiting for
+    /// one doesn't hold a process slot a glab call could use.
+    pub(crate) fn permit(&self, cmd: &process::Command) -> Permit<'_> {
+        let hook = is_git(cmd).then(|| self.hooks.take());
+        Permit {
+            _hook: hook,
+            _process: self.processes.take(),
+        }
+    }
+
+    /// `cmd` wrapped in `nice` and `ionice` as configured, for the hooks git operations run.
+    pub(crate) fn wrap(&self, cmd: process::Command) -> process::Command {
+        if self.nice.is_none() && !self.idle_io {
+            return cmd;
+        }
+        let mut prefix: Vec<String> = Vec::new();
+        if self.idle_io {
+            prefix.extend(["ionice", "-c", "3"].map(str::to_string));
+        }
+        if let Some(nice) = self.nice {
+            prefix.extend(["nice".to_string(), "-n".to_string(), nice.to_string()]);
+        }
+        let mut wrapped = process::Command::new(&prefix[0]);
+        wrapped
+            .args(&prefix[1..])
+            .arg(cmd.get_program())
+            .args(cmd.get_args());
+        if let Some(dir) = cmd.get_current_dir() {
+            wrapped.current_dir(dir);
+        }
+        for (key, value) in cmd.get_envs() {
+            match value {
+                Some(value) => wrapped.env(key, value),
+                None => wrapped.env_remove(key),
+            };
+        }
+        wrapped
+    }
+}
+
+fn is_git(cmd: &process::Command) -> bool {
+    cmd.get_program() == "git"
+}
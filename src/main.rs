@@ -1,10 +1,47 @@
 //! The main entry point for the Multi MR TUI application.
+use std::collections::HashSet;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
 use clap::Parser;
 
+mod announce;
 mod app;
+mod batch_merge;
+mod branch_detection;
+mod branch_name;
+mod cache;
+mod capabilities;
+mod clipboard;
 mod config;
+mod digest;
+mod due_date;
+mod error;
+mod execution;
+mod git;
+mod gitlab;
+mod hyperlinks;
+mod init;
+mod label_usage;
+mod last_reviewers;
+mod limits;
+mod merge_queue;
 mod merge_request;
+mod notification;
+mod pipeline_watch;
+mod plan;
+mod post_merge;
+mod preflight;
+mod prune;
+mod queue;
+mod report;
+mod runs;
+mod script;
+mod templates;
+mod tickets;
+mod tour;
 mod utils;
+mod warnings;
 
 /// CLI arguments
 #[derive(Parser, Debug)]
@@ -13,32 +50,256 @@ struct Cli {
     /// Run in dry-run mode (do not actually create MRs)
     #[arg(long)]
     dry_run: bool,
-    /// Overwrite the assignee specified in multimr.toml
+    /// Overwrite the assignee specified in multimr.toml, repeat to assign several users
+    #[arg(long)]
+    assignee: Vec<String>,
+    /// Preselect this reviewer, repeat for several; added for this run if not in multimr.toml
+    #[arg(long, value_name = "USERNAME")]
+    reviewer: Vec<String>,
+    /// Switch each repo back to its original branch after creating the MR
+    #[arg(long)]
+    restore_branch: bool,
+    /// Overwrite the working directory specified in multimr.toml
+    #[arg(long)]
+    working_dir: Option<PathBuf>,
+    /// Create the first MR only and wait for confirmation before creating the rest
+    #[arg(long)]
+    canary: bool,
+    /// List the projects of this GitLab group instead of the working directory, to update their
+    /// MRs with `--update` or show them with `status`, without local checkouts
+    #[arg(long)]
+    group: Option<String>,
+    /// Keep the screen still: toasts stay until the next key press instead of timing out
+    #[arg(long)]
+    reduced_motion: bool,
+    /// Write the plan to this shell script on confirmation instead of running it
+    #[arg(long, value_name = "PATH")]
+    emit_script: Option<PathBuf>,
+    /// Select the repos of this group from the `[groups]` in multimr.toml on start
+    #[arg(long, value_name = "NAME")]
+    repo_group: Option<String>,
+    /// Commit locally and queue pushing and creating the MRs for `multimr flush`
+    #[arg(long)]
+    offline: bool,
+    /// Never branch, commit or push, only create MRs for branches that already exist on the remote
+    #[arg(long)]
+    no_git: bool,
+    /// Keep the results screen updating the pipelines of the created MRs until they all passed or failed
     #[arg(long)]
-    assignee: Option<String>,
+    watch_pipelines: bool,
+    /// Update the open MRs of the current branches with the new title, description, labels and reviewers
+    #[arg(long)]
+    update: bool,
+    /// Confirm the batch even when the checks on the Finalize screen found blockers
+    #[arg(long)]
+    force: bool,
+    /// Use the settings of this `[profile.<NAME>]` in the config, asked on start when there are profiles
+    #[arg(long, value_name = "NAME")]
+    profile: Option<String>,
+    /// Path of the configuration file, by default the nearest multimr.toml in this or a parent
+    /// directory, layered over ~/.config/multimr/config.toml
+    #[arg(long)]
+    config: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// Commands run instead of the TUI
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Pick the MRs in the TUI as usual, but save them to a file instead of creating them
+    Plan {
+        /// File to write the plan to
+        #[arg(short, long, value_name = "PATH", default_value = "plan.json")]
+        output: PathBuf,
+    },
+    /// Create the MRs of a plan saved with `multimr plan`
+    Apply {
+        /// The plan to run
+        plan: PathBuf,
+    },
+    /// Push and create the MRs queued while offline
+    Flush,
+    /// List the recorded runs with their MRs, newest first
+    History,
+    /// Replace the description of every MR of a past run, `{repo}` and `{project}` are
+    /// filled in per MR
+    SyncDescription {
+        /// ID of the run, the last one by default
+        #[arg(long)]
+        run: Option<String>,
+        /// Read the description from this file instead of opening $EDITOR
+        #[arg(long)]
+        file: Option<PathBuf>,
+    },
+    /// Find the MRs of a run across the workspace by the run ID hidden in their descriptions
+    Find {
+        /// ID of the run, as listed by `multimr history`
+        #[arg(long)]
+        run: String,
+    },
+    /// Summarize the MRs created, merged and closed across the workspace as markdown, e.g.
+    /// for a standup
+    Digest {
+        /// How far back to look, in hours, days or weeks: `24h`, `7d`, `2w`
+        #[arg(long, default_value = "7d")]
+        since: String,
+        /// Only include the MRs created by recorded multimr runs
+        #[arg(long)]
+        from_runs: bool,
+        /// Write the report to this file instead of printing it
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<PathBuf>,
+    },
+    /// Approve and/or merge your open MRs across the workspace whose pipelines passed
+    Merge {
+        /// Approve the MRs before merging them
+        #[arg(long)]
+        approve: bool,
+        /// Only approve the MRs, don't merge them
+        #[arg(long)]
+        no_merge: bool,
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Show your open MRs across the workspace with their pipelines, approvals and whether
+    /// they can be merged
+    Status,
+    /// Once the MRs of a run are merged, switch their repos back to the branch merged into,
+    /// pull it and delete the merged local branch, as configured in `[post_merge]`
+    PostMerge {
+        /// ID of the run, the last one by default
+        #[arg(long)]
+        run: Option<String>,
+        /// Keep checking until every MR of the run is merged or closed
+        #[arg(long)]
+        watch: bool,
+    },
+    /// Walk through every screen with explanations, against throwaway repos and as a dry run
+    Tour,
+    /// Ask for the basic settings and write them to a new multimr.toml in this directory
+    Init,
+    /// Work with the configuration files
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+}
+
+/// Actions of `multimr config`
+#[derive(clap::Subcommand, Debug)]
+enum ConfigCommand {
+    /// Validate the config files and print the settings they resolve to, with the command
+    /// line options and `--profile` applied
+    Check,
+    /// Look up reviewers, labels and group members of multimr.toml that no longer exist,
+    /// and remove the picked ones from the file
+    Prune,
+}
+
+impl Cli {
+    /// The values given on the command line that take precedence over the config file.
+    fn overrides(&self) -> config::ConfigOverrides {
+        config::ConfigOverrides {
+            working_dir: self.working_dir.clone(),
+            assignees: self.assignee.clone(),
+            reviewers: self.reviewer.clone(),
+            dry_run: self.dry_run,
+            restore_branch: self.restore_branch,
+            canary: self.canary,
+            group: self.group.clone(),
+            reduced_motion: self.reduced_motion,
+            emit_script: self.emit_script.clone(),
+            emit_plan: match &self.command {
+                Some(Command::Plan { output }) => Some(output.clone()),
+                _ => None,
+            },
+            offline: self.offline,
+            no_git: self.no_git,
+            watch_pipelines: self.watch_pipelines,
+            update: self.update,
+            force: self.force,
+            repo_group: self.repo_group.clone(),
+        }
+    }
 }
 
 fn main() -> color_eyre::Result<()> {
     color_eyre::install()?; // setup error handling
 
-    let cli = Cli::parse(); // parse the cli first so the user can always run --help or --version
+    let mut cli = Cli::parse(); // parse the cli first so the user can always run --help or --version
+    // These pick the config that is loaded, so unlike the other settings they are read here
+    cli.config = cli
+        .config
+        .or_else(|| config::env_var("CONFIG").map(PathBuf::from));
+    cli.profile = cli.profile.or_else(|| config::env_var("PROFILE"));
 
-    utils::ensure_glab_installed(); // Without `glab-cli` installed we cannot create merge requests, crash early
+    match cli.command {
+        Some(Command::Flush) => return flush(),
+        Some(Command::History) => return history(),
+        Some(Command::Apply { plan }) => return apply(&plan),
+        Some(Command::SyncDescription { run, file }) => {
+            return sync_description(run.as_deref(), file.as_deref());
+        }
+        Some(Command::Find { ref run }) => return find(&cli, run),
+        Some(Command::Digest {
+            ref since,
+            from_runs,
+            ref output,
+        }) => return digest(&cli, since, from_runs, output.as_deref()),
+        Some(Command::Merge {
+            approve,
+            no_merge,
+            yes,
+        }) => return merge(&cli, approve || no_merge, !no_merge, yes),
+        Some(Command::Status) => return status(&cli),
+        Some(Command::PostMerge { ref run, watch }) => {
+            return post_merge(&cli, run.as_deref(), watch);
+        }
+        Some(Command::Tour) => return tour(),
+        Some(Command::Init) => return init(),
+        Some(Command::Config {
+            action: ConfigCommand::Check,
+        }) => return check_config(&cli),
+        Some(Command::Config {
+            action: ConfigCommand::Prune,
+        }) => return prune_config(&cli),
+        Some(Command::Plan { .. }) | None => {}
+    }
 
-    let mut cfg = config::load_config_from_toml();
+    let profile = match &cli.profile {
+        Some(profile) => Some(profile.clone()),
+        None => match pick_profile(cli.config.as_deref())? {
+            app::ProfilePick::Profile(profile) => Some(profile),
+            app::ProfilePick::NoProfile => None,
+            app::ProfilePick::Quit => {
+                println!("Exiting without creating merge requests.");
+                return Ok(());
+            }
+        },
+    };
 
     // Overwrite configuration if provided via CLI
-    if let Some(assignee) = cli.assignee {
-        cfg.assignee = Some(assignee);
+    let cfg =
+        config::load_config(cli.config.as_deref(), profile.as_deref())?.merge(cli.overrides())?;
+    if cfg.group.is_some() && !cfg.update {
+        return Err(error::MultimrError::GroupWithoutUpdate.into());
+    }
+
+    // Without `glab-cli` installed we cannot create merge requests, crash early
+    for glab in cfg.glab_binaries() {
+        utils::ensure_glab_installed(glab)?;
     }
-    cfg.dry_run = cli.dry_run; // Set dry_run mode based on CLI argument
 
     // The interactive TUI app
     let terminal = ratatui::init();
-    let app = app::App::new(cfg.clone());
-    let app = app.run(terminal)?;
+    let mut app = app::App::new(cfg);
+    app.start_user_fetch();
+    let result = app.run(terminal);
 
-    ratatui::restore(); // restore state of terminal to what it was before the app started
+    ratatui::restore(); // restore state of terminal to what it was before the app started, also on errors
+    let app = result?;
 
     // If the user exited early, we just exit without doing anything
     if !app.user_input_completed {
@@ -46,34 +307,578 @@ fn main() -> color_eyre::Result<()> {
         return Ok(());
     }
 
-    run_commands(cfg.dry_run, app);
+    if let Some(path) = &app.config.emit_script {
+        println!(
+            "Wrote the plan to {}, run it with `sh {}`.",
+            path.display(),
+            path.display()
+        );
+        return Ok(());
+    }
+
+    if let Some(path) = &app.config.emit_plan {
+        println!(
+            "Wrote the plan to {}, review it and run `multimr apply {}`.",
+            path.display(),
+            path.display()
+        );
+        return Ok(());
+    }
+
+    print_summary(&app);
 
     Ok(())
 }
 
-/// Runs the commands generated by the app
-fn run_commands(dry_run: bool, app: app::App) {
-    println!("Multi MR will now create merge requests for the following repositories:");
-    for dir_index in &app.selected_repos {
-        println!(" - {}", app.dirs[*dir_index]);
+/// Show the dashboard of the open MRs of the user until they quit it.
+fn status(cli: &Cli) -> color_eyre::Result<()> {
+    let cfg = config::load_config(cli.config.as_deref(), cli.profile.as_deref())?
+        .merge(cli.overrides())?;
+    for glab in cfg.glab_binaries() {
+        utils::ensure_glab_installed(glab)?;
     }
+    let mut terminal = ratatui::init();
+    let result = app::Dashboard::new(cfg).run(&mut terminal);
+    ratatui::restore();
+    result
+}
 
-    for dir_index in app.selected_repos {
-        let dir = app.dirs[dir_index].clone();
-        std::env::set_current_dir(app.config.working_dir.join(&dir))
-            .unwrap_or_else(|_| panic!("Failed to change directory to: {}", dir));
+/// Run the TUI on sandbox repos in dry-run mode with every screen explained, then clean up.
+fn tour() -> color_eyre::Result<()> {
+    let sandbox = tour::create_sandbox()?;
+    let terminal = ratatui::init();
+    let result = app::App::new(tour::config(&sandbox)).run(terminal);
+    ratatui::restore();
+    std::fs::remove_dir_all(&sandbox)?;
+    let app = result?;
+    if app.user_input_completed {
+        println!(
+            "That was the tour, nothing was pushed. Run `multimr init` to set up your own workspace."
+        );
+    } else {
+        println!("Left the tour early, run `multimr tour` to start again.");
+    }
+    Ok(())
+}
 
-        let cmd = app.mr.as_ref().expect("somehow no mr specified").create();
+/// Write a multimr.toml from the answers to `init::ask`, asking before replacing one.
+fn init() -> color_eyre::Result<()> {
+    let path = PathBuf::from(config::CONFIG_FILE);
+    let mut input = std::io::stdin().lock();
+    let mut output = std::io::stdout();
+    if path.exists()
+        && !init::confirm(
+            &mut input,
+            &mut output,
+            &format!("{} already exists, replace it?", path.display()),
+        )?
+    {
+        println!("Leaving {} as it is.", path.display());
+        return Ok(());
+    }
+    let answers = init::ask(
+        &mut input,
+        &mut output,
+        config::DEFAULT_GLAB,
+        Path::new("."),
+    )?;
+    std::fs::write(&path, init::render(&answers))?;
+    println!(
+        "Wrote {}, check it with `multimr config check`.",
+        path.display()
+    );
+    Ok(())
+}
 
-        if dry_run {
-            app.mr
-                .as_ref()
-                .expect("somehow no mr specified")
-                .dry_run(cmd);
+/// List the MRs of run `id` in the workspace, whatever their branches and labels are now.
+fn find(cli: &Cli, id: &str) -> color_eyre::Result<()> {
+    if !runs::is_run_id(id) {
+        return Err(color_eyre::eyre::eyre!(
+            "'{}' is not a run ID like 20250102-093000",
+            id
+        ));
+    }
+    let cfg = config::load_config(cli.config.as_deref(), cli.profile.as_deref())?
+        .merge(cli.overrides())?;
+    let (found, errors) = runs::search(&cfg, id);
+    for error in errors {
+        eprintln!("Warning: {}", error);
+    }
+    if found.is_empty() {
+        println!("No MRs of run {} found in the workspace.", id);
+    }
+    for (project, mr) in found {
+        let state = if mr.merged_at.is_some() {
+            "merged"
+        } else if mr.closed_at.is_some() {
+            "closed"
         } else {
-            app.mr.as_ref().expect("somehow no mr specified").run(cmd);
+            "open"
+        };
+        println!("{}!{} ({}) {}", project, mr.iid, state, mr.title);
+        println!("    {}", mr.web_url);
+    }
+    Ok(())
+}
+
+/// Sync the repos of a run whose MRs are merged, waiting for the others with `watch`.
+fn post_merge(cli: &Cli, run: Option<&str>, watch: bool) -> color_eyre::Result<()> {
+    let Some(path) = runs::default_path() else {
+        return Err(color_eyre::eyre::eyre!(
+            "no state directory, set HOME or XDG_STATE_HOME"
+        ));
+    };
+    let Some(record) = runs::find(&path, run)? else {
+        return Err(match run {
+            Some(id) => color_eyre::eyre::eyre!("there is no run {}", id),
+            None => color_eyre::eyre::eyre!("no runs recorded yet"),
+        });
+    };
+    let cfg = config::load_config(cli.config.as_deref(), cli.profile.as_deref())?
+        .merge(cli.overrides())?;
+
+    println!("Syncing the repos of run {}: {}", record.id, record.title);
+    let mut pending: Vec<&runs::RunRepo> =
+        record.repos.iter().filter(|r| r.iid.is_some()).collect();
+    let mut failed = 0;
+    let mut first_round = true;
+    loop {
+        let mut waiting = Vec::new();
+        for repo in pending {
+            let mut log = String::new();
+            match post_merge::sync(repo, &cfg.post_merge, &mut log) {
+                Ok(post_merge::Sync::Waiting(state)) => {
+                    if first_round {
+                        println!("== {} (not merged yet: {})", repo.dir, state);
+                    }
+                    waiting.push(repo);
+                }
+                Ok(post_merge::Sync::Closed) => {
+                    println!("== {} (closed without merging, left alone)", repo.dir)
+                }
+                Ok(post_merge::Sync::Synced) => print!("== {} (synced)\n{}", repo.dir, log),
+                Ok(post_merge::Sync::Left(why)) => {
+                    print!("== {} (merged, left alone: {})\n{}", repo.dir, why, log)
+                }
+                Err(e) => {
+                    failed += 1;
+                    print!("== {} (failed)\n{}Error: {}\n", repo.dir, log, e);
+                }
+            }
+        }
+        pending = waiting;
+        if !watch || pending.is_empty() {
+            break;
+        }
+        if first_round {
+            println!("Waiting for {} MRs to be merged...", pending.len());
+        }
+        first_round = false;
+        std::thread::sleep(pipeline_watch::POLL_INTERVAL);
+    }
+    if failed > 0 {
+        return Err(color_eyre::eyre::eyre!("{} repos were not synced", failed));
+    }
+    Ok(())
+}
+
+/// Print or write the digest of the MRs of the workspace updated in the last `since`.
+fn digest(
+    cli: &Cli,
+    since: &str,
+    from_runs: bool,
+    output: Option<&Path>,
+) -> color_eyre::Result<()> {
+    let Some(period) = digest::parse_since(since) else {
+        return Err(color_eyre::eyre::eyre!(
+            "cannot read --since {}, use hours, days or weeks like 24h, 7d or 2w",
+            since
+        ));
+    };
+    let cfg = config::load_config(cli.config.as_deref(), cli.profile.as_deref())?
+        .merge(cli.overrides())?;
+    let since = digest::timestamp(runs::now().saturating_sub(period));
+    let (mut entries, errors) = digest::collect(&cfg, &since);
+    for error in errors {
+        eprintln!("Warning: {}", error);
+    }
+    if from_runs {
+        let records = match runs::default_path() {
+            Some(path) => runs::load(&path)?,
+            None => Vec::new(),
+        };
+        let urls: HashSet<String> = records.iter().flat_map(|r| r.urls()).collect();
+        entries.retain(|entry| urls.contains(&entry.mr.web_url));
+    }
+    let report = digest::render(&entries, &since);
+    match output {
+        Some(path) => {
+            std::fs::write(path, report)?;
+            println!("Wrote the digest to {}.", path.display());
         }
+        None => print!("{}", report),
     }
+    Ok(())
+}
+
+/// Approve and/or merge the open MRs of the user across the workspace that are ready, after
+/// listing all of them and asking unless `yes`.
+fn merge(cli: &Cli, approve: bool, merge: bool, yes: bool) -> color_eyre::Result<()> {
+    let cfg = config::load_config(cli.config.as_deref(), cli.profile.as_deref())?
+        .merge(cli.overrides())?;
+    let (candidates, errors) = batch_merge::collect(&cfg);
+    for error in errors {
+        eprintln!("Warning: {}", error);
+    }
+    if candidates.is_empty() {
+        println!("You have no open MRs in the workspace.");
+        return Ok(());
+    }
+    for candidate in &candidates {
+        println!(
+            "{} {}!{} {} ({})",
+            if candidate.is_ready() { "✓" } else { " " },
+            candidate.name,
+            candidate.mr.iid,
+            candidate.mr.title,
+            candidate.status()
+        );
+    }
+    let ready: Vec<&batch_merge::Candidate> = candidates.iter().filter(|c| c.is_ready()).collect();
+    if ready.is_empty() {
+        println!("None of them is ready, their pipelines haven't passed yet.");
+        return Ok(());
+    }
+    let action = match (approve, merge) {
+        (true, true) => "Approve and merge",
+        (true, false) => "Approve",
+        _ => "Merge",
+    };
+    if !yes
+        && !init::confirm(
+            &mut std::io::stdin().lock(),
+            &mut std::io::stdout(),
+            &format!("{} the {} ready MRs?", action, ready.len()),
+        )?
+    {
+        println!("Leaving the MRs as they are.");
+        return Ok(());
+    }
+    let mut failed = 0;
+    for candidate in ready {
+        let mut log = String::new();
+        let result = candidate.run(approve, merge, &mut log);
+        println!("== {}!{}", candidate.name, candidate.mr.iid);
+        print!("{}", log);
+        if let Err(e) = result {
+            println!("Failed: {}", e);
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        return Err(color_eyre::eyre::eyre!(
+            "{} MRs could not be handled",
+            failed
+        ));
+    }
+    Ok(())
+}
+
+/// Load the config the way the TUI would and print which files it came from and the
+/// settings it resolves to.
+fn check_config(cli: &Cli) -> color_eyre::Result<()> {
+    let cwd = std::env::current_dir()?;
+    let show =
+        |file: Option<PathBuf>| file.map_or("none".to_string(), |path| path.display().to_string());
+    println!(
+        "Config: {}",
+        show(config::find_config(cli.config.as_deref(), &cwd))
+    );
+    println!("Global config: {}", show(config::global_config()));
+    if let Some(profile) = &cli.profile {
+        println!("Profile: {}", profile);
+    }
+    let mut env: Vec<String> = std::env::vars()
+        .map(|(name, _)| name)
+        .filter(|name| name.starts_with(config::ENV_PREFIX))
+        .collect();
+    if !env.is_empty() {
+        env.sort();
+        println!("Environment: {}", env.join(", "));
+    }
+    let cfg = config::load_config(cli.config.as_deref(), cli.profile.as_deref())?
+        .merge(cli.overrides())?;
+    println!("\n{}", toml::to_string_pretty(&cfg)?);
+    println!("The config is valid.");
+    Ok(())
+}
+
+/// Look up the stale entries of the config and remove the picked ones from the config file.
+fn prune_config(cli: &Cli) -> color_eyre::Result<()> {
+    let cwd = std::env::current_dir()?;
+    let Some(path) = config::find_config(cli.config.as_deref(), &cwd) else {
+        println!(
+            "There is no {} to prune, `multimr init` creates one.",
+            config::CONFIG_FILE
+        );
+        return Ok(());
+    };
+    let cfg = config::load_config(cli.config.as_deref(), cli.profile.as_deref())?
+        .merge(cli.overrides())?;
+    utils::ensure_glab_installed(cfg.glab_binary())?;
+    let mut terminal = ratatui::init();
+    let result = app::Prune::new(cfg).run(&mut terminal);
+    ratatui::restore();
+    let entries = result?;
+    if entries.is_empty() {
+        println!("Nothing removed from {}.", path.display());
+        return Ok(());
+    }
+    let missing = prune::write(&path, &entries)?;
+    println!(
+        "Removed {} stale entries from {}.",
+        entries.len() - missing.len(),
+        path.display()
+    );
+    for entry in missing {
+        println!(
+            "Not set in {}, remove it from the global config or profile instead: {}",
+            path.display(),
+            entry
+        );
+    }
+    Ok(())
+}
+
+/// Ask which profile to use when the config has any.
+fn pick_profile(config: Option<&Path>) -> color_eyre::Result<app::ProfilePick> {
+    let names = config::profile_names(config);
+    if names.is_empty() {
+        return Ok(app::ProfilePick::NoProfile);
+    }
+    let mut terminal = ratatui::init();
+    let pick = app::ProfilePicker::new(names).run(&mut terminal);
+    ratatui::restore();
+    pick
+}
+
+/// Print what happened per repo, so the output remains available after the TUI is gone
+fn print_summary(app: &app::App) {
+    let Some(execution) = &app.execution else {
+        return;
+    };
+    let links = app.hyperlinks && std::io::stdout().is_terminal();
+
+    for repo in &execution.repos {
+        let state = match repo.state {
+            execution::RepoState::Succeeded => "done",
+            execution::RepoState::Failed => "failed",
+            execution::RepoState::Skipped => "skipped",
+            execution::RepoState::Queued => "queued",
+            execution::RepoState::Pending | execution::RepoState::Running => "not run",
+        };
+        match &repo.url {
+            Some(url) if links => println!(
+                "== {} ({})",
+                hyperlinks::osc8(hyperlinks::project_url(url), &repo.dir),
+                state
+            ),
+            _ => println!("== {} ({})", repo.dir, state),
+        }
+        print!("{}", repo.log);
+        if let Some(url) = &repo.url {
+            if links {
+                println!("MR: {}", hyperlinks::osc8(url, url));
+            } else {
+                println!("MR: {}", url);
+            }
+        }
+        let dropped = repo.dropped_reviewers();
+        if !dropped.is_empty() {
+            println!(
+                "Warning: GitLab dropped the reviewers {}",
+                dropped.join(", ")
+            );
+        }
+    }
+}
+
+/// Print the recorded runs, newest first, with the MR of each repo.
+fn history() -> color_eyre::Result<()> {
+    let records = match runs::default_path() {
+        Some(path) => runs::load(&path)?,
+        None => Vec::new(),
+    };
+    if records.is_empty() {
+        println!("No runs recorded yet.");
+    }
+    for record in records.iter().rev() {
+        println!(
+            "{}  {}  {} ({})",
+            record.id,
+            record.started(),
+            record.title,
+            record.outcome()
+        );
+        for repo in &record.repos {
+            println!(
+                "    {} ({}) {}",
+                repo.dir,
+                repo.outcome,
+                repo.url.as_deref().unwrap_or("-")
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Replace the description of the MRs of a recorded run with one from `file` or the editor.
+fn sync_description(run: Option<&str>, file: Option<&Path>) -> color_eyre::Result<()> {
+    let Some(path) = runs::default_path() else {
+        return Err(color_eyre::eyre::eyre!(
+            "no state directory, set HOME or XDG_STATE_HOME"
+        ));
+    };
+    let Some(mut record) = runs::find(&path, run)? else {
+        return Err(match run {
+            Some(id) => color_eyre::eyre::eyre!("there is no run {}", id),
+            None => color_eyre::eyre::eyre!("no runs recorded yet"),
+        });
+    };
+
+    let description = match file {
+        Some(file) => std::fs::read_to_string(file)?,
+        None => edit(&record.description)?,
+    };
+    if description.trim() == record.description.trim() {
+        println!("Description unchanged, nothing to update.");
+        return Ok(());
+    }
+
+    println!("Updating the MRs of run {}: {}", record.id, record.title);
+    let results = runs::sync_description(&record, &description);
+    let failed = results.iter().filter(|(_, result)| result.is_err()).count();
+    for (dir, result) in &results {
+        match result {
+            Ok(()) => println!("== {} (updated)", dir),
+            Err(e) => println!("== {} (failed)\nError: {}", dir, e),
+        }
+    }
+    if failed == 0 {
+        record.description = description;
+        runs::save(&path, record)?;
+        return Ok(());
+    }
+    Err(color_eyre::eyre::eyre!(
+        "{} of {} MRs were not updated",
+        failed,
+        results.len()
+    ))
+}
+
+/// Let the user edit `text` in $VISUAL or $EDITOR, `vi` if neither is set.
+fn edit(text: &str) -> color_eyre::Result<String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let file = std::env::temp_dir().join(format!("multimr-description-{}.md", std::process::id()));
+    std::fs::write(&file, text)?;
+    // Through the shell, so an editor with arguments like `code --wait` works
+    let status = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg("sh")
+        .arg(&file)
+        .status()?;
+    let edited = std::fs::read_to_string(&file);
+    let _ = std::fs::remove_file(&file);
+    if !status.success() {
+        return Err(color_eyre::eyre::eyre!("{} exited with {}", editor, status));
+    }
+    Ok(edited?)
+}
+
+/// Create the MRs of a saved plan and report per repo.
+fn apply(path: &Path) -> color_eyre::Result<()> {
+    let plan = plan::load(path)?;
+    let glabs: std::collections::BTreeSet<&str> =
+        plan.repos.iter().map(|r| r.merge_request.glab()).collect();
+    for glab in glabs {
+        utils::ensure_glab_installed(glab)?;
+    }
+
+    println!("Applying {}: {}", path.display(), plan.title);
+    let applied = plan::apply(&plan);
+    let mut failed = 0;
+    for repo in &applied {
+        let state = if repo.result.is_ok() {
+            "done"
+        } else {
+            failed += 1;
+            "failed"
+        };
+        println!("== {} ({})", repo.dir, state);
+        print!("{}", repo.log);
+        if let Err(e) = &repo.result {
+            println!("Error: {}", e);
+        }
+        if let Some(url) = &repo.url {
+            println!("MR: {}", url);
+        }
+        if !repo.dropped_reviewers.is_empty() {
+            println!(
+                "Warning: GitLab dropped the reviewers {}",
+                repo.dropped_reviewers.join(", ")
+            );
+        }
+    }
+    if failed > 0 {
+        return Err(color_eyre::eyre::eyre!(
+            "{} of {} repositories failed",
+            failed,
+            applied.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Run the steps queued while offline and report per repo, leaving the failed ones queued.
+fn flush() -> color_eyre::Result<()> {
+    let Some(path) = queue::default_path() else {
+        return Err(error::MultimrError::NoQueue.into());
+    };
+    let flushed = queue::flush(&path)?;
+    if flushed.is_empty() {
+        println!("Nothing queued.");
+        return Ok(());
+    }
+
+    let mut failed = 0;
+    for repo in &flushed {
+        let state = match &repo.result {
+            Ok(()) => "done",
+            Err(_) => {
+                failed += 1;
+                "failed, still queued"
+            }
+        };
+        println!("== {} ({})", repo.repo.repo_dir.display(), state);
+        print!("{}", repo.log);
+        if let Err(e) = &repo.result {
+            println!("Error: {}", e);
+        }
+        if let Some(url) = &repo.url {
+            println!("MR: {}", url);
+        }
+    }
+    if failed > 0 {
+        return Err(color_eyre::eyre::eyre!(
+            "{} of {} queued repositories failed, run `multimr flush` again",
+            failed,
+            flushed.len()
+        ));
+    }
+    Ok(())
 }
 
 #[cfg(test)]
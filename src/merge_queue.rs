@@ -0,0 +1,70 @@
+//! Handing the created merge requests to a merge queue outside GitLab, for teams whose merge
+//! trains are run by another system. Each merge request is POSTed to the `[merge_queue]` URL
+//! as JSON once the batch finishes.
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::utils;
+
+/// The body POSTed when `body` is not configured.
+pub(crate) const DEFAULT_BODY: &str =
+    r#"{"url": "{url}", "project": "{project}", "iid": {iid}, "title": "{title}", "run": "{run}"}"#;
+
+/// The `[merge_queue]` of the config.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct MergeQueue {
+    /// Where each merge request is POSTed to
+    pub url: String,
+    /// JSON template of the request body, with `{url}`, `{iid}`, `{repo}`, `{project}`,
+    /// `{title}` and `{run}` filled in, [`DEFAULT_BODY`] if not set
+    pub body: Option<String>,
+    /// Extra headers like `Authorization: Bearer <token>`
+    #[serde(default)]
+    pub headers: Vec<String>,
+}
+
+/// A created merge request to enqueue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Entry {
+    /// The directory of its repo, as listed in the TUI
+    pub(crate) repo: String,
+    /// The project path, empty if the remote isn't recognized
+    pub(crate) project: String,
+    pub(crate) url: String,
+    pub(crate) iid: u64,
+    pub(crate) title: String,
+}
+
+impl MergeQueue {
+    /// The body POSTed for `entry` of the run `run`, the values are escaped as JSON strings.
+    pub(crate) fn render_body(&self, entry: &Entry, run: &str) -> String {
+        self.body
+            .as_deref()
+            .unwrap_or(DEFAULT_BODY)
+            .replace("{url}", &utils::json_escape(&entry.url))
+            .replace("{iid}", &entry.iid.to_string())
+            .replace("{repo}", &utils::json_escape(&entry.repo))
+            .replace("{project}", &utils::json_escape(&entry.project))
+            .replace("{title}", &utils::json_escape(&entry.title))
+            .replace("{run}", &utils::json_escape(run))
+    }
+
+    /// POST one entry to the queue.
+    pub(crate) fn enqueue(&self, entry: &Entry, run: &str) -> Result<()> {
+        utils::post_json(&self.url, &self.headers, &self.render_body(entry, run))
+    }
+
+    /// Enqueue every entry, returns the repos that were enqueued and why the others weren't.
+    pub(crate) fn enqueue_all(&self, entries: &[Entry], run: &str) -> (Vec<String>, Vec<String>) {
+        let mut enqueued = Vec::new();
+        let mut failed = Vec::new();
+        for entry in entries {
+            match self.enqueue(entry, run) {
+                Ok(()) => enqueued.push(entry.repo.clone()),
+                Err(e) => failed.push(format!("{}: {}", entry.repo, e)),
+            }
+        }
+        (enqueued, failed)
+    }
+}
@@ -1,30 +1,426 @@
 //! Provides functionality to create merge requests using the `glab` CLI.
-use std::{env, io, process};
+use std::collections::HashMap;
+use std::path::Path;
+use std::process;
 
-use color_eyre::Result;
+use serde::{Deserialize, Serialize};
 
 use super::utils;
-use crate::config;
+use crate::branch_detection::{self, BranchDetection};
+use crate::branch_name;
+use crate::config::{self, AssigneeMode, Config, LabelRule, RepoOverride};
+use crate::error::{MultimrError, Result};
+use crate::execution;
+use crate::git::Git;
+use crate::gitlab;
+use crate::limits::Throttle;
+use crate::runs;
+use crate::templates;
+use crate::tickets;
 
 /// Represents a merge request to be created.
-#[derive(Debug)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct MergeRequest {
     pub(crate) title: String,
     pub(crate) description: String,
+    /// Branch created for the merge request when on a default branch, see [`branch_name`].
+    pub(crate) branch: Option<String>,
     pub(crate) reviewers: Vec<String>,
+    /// Reviewers of single repos instead of `reviewers`, keyed by directory, see
+    /// [`crate::config::ReviewerStrategy`].
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub(crate) reviewers_by_dir: HashMap<String, Vec<String>>,
     pub(crate) labels: Vec<String>,
-    pub(crate) assignee: Option<String>,
+    pub(crate) assignees: Vec<String>,
+    /// Assign the merge request to the first maintainer of the project instead of `assignees`.
+    pub(crate) assign_maintainer: bool,
+    /// First maintainers looked up before confirming, keyed by directory, see
+    /// [`MergeRequest::for_dir`].
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub(crate) maintainers_by_dir: HashMap<String, String>,
+    /// The first maintainer of the project if it was looked up, otherwise it is looked up
+    /// when the merge request is created.
+    pub(crate) maintainer: Option<String>,
+    /// Branch to merge into, `None` lets GitLab use the project's default branch.
+    pub(crate) target_branch: Option<String>,
+    /// Push with `-o ci.skip` so no pipeline is triggered for this merge request.
+    pub(crate) skip_ci: bool,
+    /// Open the merge request as a draft.
+    pub(crate) draft: bool,
+    /// Squash the commits when the merge request is merged.
+    pub(crate) squash: bool,
+    /// Delete the source branch when the merge request is merged.
+    pub(crate) remove_source_branch: bool,
+    /// Set the merge request to merge as soon as its pipeline succeeds.
+    pub(crate) auto_merge: bool,
+    /// Title of the milestone to assign the merge request to.
+    pub(crate) milestone: Option<String>,
+    /// Name of the template the description came from, each repo that has a template by
+    /// this name gets its own version of it.
+    pub(crate) template: Option<String>,
+    /// Values of the `{{PLACEHOLDER}}`s in the template, filled into each repo's version of it.
+    #[serde(default)]
+    pub(crate) template_values: HashMap<String, String>,
+    /// Review deadline as `YYYY-MM-DD`, appended to the description as a `/due` quick action.
+    pub(crate) due_date: Option<String>,
+    /// Issues the merge request closes when merged: `123`, `#123` or `group/project#123`,
+    /// or `dir:123` to close it from the repo `dir` only, see [`MergeRequest::for_dir`].
+    pub(crate) closes_issues: Vec<String>,
+    /// Tracker URLs keyed by ticket prefix, with `{id}` for the ticket ID, see [`tickets`].
+    #[serde(default)]
+    pub(crate) ticket_links: HashMap<String, String>,
+    /// Extra `git push -o` options, e.g. `merge_request.label=...`.
+    pub(crate) push_options: Vec<String>,
+    /// Project passed to glab as `--repo`, instead of relying on the git context of the cwd.
+    pub(crate) project: Option<gitlab::Project>,
+    /// The glab executable to run, `None` for `glab` on the PATH.
+    pub(crate) glab: Option<String>,
+    /// Only open the merge request for an already pushed branch, no local git operations.
+    pub(crate) no_checkout: bool,
+    /// Branch to open the merge request for in no-checkout mode, the current branch by default.
+    pub(crate) source_branch: Option<String>,
+    /// Safe mode: never run git commands that change anything, implies `no_checkout`.
+    /// The source branch is checked to exist on the remote instead of being pushed.
+    pub(crate) no_git: bool,
+    /// Update the open merge request of the source branch instead of creating one, only
+    /// its title and the description, labels and reviewers that are given change.
+    pub(crate) update: bool,
+    /// How the current and the default branch of the repo are found out.
+    pub(crate) branch_detection: BranchDetection,
+    /// Default branch of the repo when [`BranchDetection::default`] found it, `main` and
+    /// `master` count as default branches otherwise.
+    pub(crate) default_branch: Option<String>,
+    /// Paths left out when committing everything on the new branch.
+    pub(crate) exclude_paths: Vec<String>,
+    /// ID of the batch, hidden in the description so `multimr find --run` finds the merge
+    /// request even after its branch or labels changed.
+    pub(crate) run_id: Option<String>,
+    /// What happens to the uncommitted changes of repos on a feature branch, keyed by
+    /// directory, see [`MergeRequest::for_dir`]. They are left as they are when not set.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub(crate) uncommitted_by_dir: HashMap<String, Uncommitted>,
+    /// What happens to the uncommitted changes of the repo when it is on a feature branch.
+    pub(crate) uncommitted: Option<Uncommitted>,
+}
+
+/// What happens to the uncommitted changes of a repo on a feature branch, asked per repo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Uncommitted {
+    /// Commit them on the branch, so they are part of the merge request
+    Commit,
+    /// `git stash push` them first and `git stash pop` them at the end, so they stay out
+    Stash,
+}
+
+/// A single command in the sequence that creates a merge request.
+#[derive(Debug)]
+pub(crate) struct Step {
+    /// The command, or the `git` command line [`Step::git`] does the same as
+    pub(crate) cmd: process::Command,
+    /// Run with git2 instead of running `cmd`
+    pub(crate) git: Option<Git>,
+    /// Stage again and retry once if this fails, this might happen if the pre-commit hook formats the code
+    pub(crate) restage_on_failure: bool,
+    /// Needs the GitLab server, so it is queued instead of run while offline
+    pub(crate) remote: bool,
+    /// Also runs when an earlier step failed, to put stashed changes back
+    pub(crate) always: bool,
+}
+
+impl Step {
+    fn new(cmd: process::Command) -> Self {
+        Self {
+            cmd,
+            git: None,
+            restage_on_failure: false,
+            remote: false,
+            always: false,
+        }
+    }
+
+    fn remote(cmd: process::Command) -> Self {
+        Self {
+            remote: true,
+            ..Self::new(cmd)
+        }
+    }
+
+    fn git(git: Git) -> Self {
+        Self {
+            git: Some(git.clone()),
+            ..Self::new(git.command())
+        }
+    }
 }
 
 impl MergeRequest {
-    /// Construct a command to create a merge request for the cwd repo using the `glab` CLI.
-    /// If the current branch is main or master, create a new branch
-    pub(crate) fn create(&self) -> process::Command {
-        let mut cmd = process::Command::new("glab");
+    /// Add the labels and reviewers of the rules keyed by the labels that are set.
+    /// Labels added by a rule don't trigger rules of their own.
+    pub(crate) fn apply_label_rules(&mut self, rules: &HashMap<String, LabelRule>) {
+        for rule in self.labels.clone().iter().filter_map(|l| rules.get(l)) {
+            for label in &rule.labels {
+                if !self.labels.contains(label) {
+                    self.labels.push(label.clone());
+                }
+            }
+            for reviewer in &rule.reviewers {
+                if !self.reviewers.contains(reviewer) {
+                    self.reviewers.push(reviewer.clone());
+                }
+            }
+        }
+    }
+
+    /// Give each of `dirs` one of the `picked` reviewers in turn, in the order of `order`.
+    /// The other reviewers, e.g. added by label rules, stay on every merge request.
+    pub(crate) fn share_reviewers(&mut self, picked: &[String], order: &[usize], dirs: &[String]) {
+        let picked: Vec<&String> = order
+            .iter()
+            .filter_map(|&i| picked.get(i))
+            .filter(|r| self.reviewers.contains(r))
+            .collect();
+        if picked.len() < 2 {
+            return;
+        }
+        let others: Vec<String> = self
+            .reviewers
+            .iter()
+            .filter(|r| !picked.contains(r))
+            .cloned()
+            .collect();
+        for (i, dir) in dirs.iter().enumerate() {
+            let mut reviewers = vec![picked[i % picked.len()].clone()];
+            reviewers.extend(others.iter().cloned());
+            self.reviewers_by_dir.insert(dir.clone(), reviewers);
+        }
+    }
+
+    /// Returns a copy of this merge request with the per-repo overrides applied.
+    pub(crate) fn for_repo(&self, overrides: Option<&RepoOverride>) -> MergeRequest {
+        let mut mr = self.clone();
+        if let Some(target_branch) = overrides.and_then(|o| o.target_branch.clone()) {
+            mr.target_branch = Some(target_branch);
+        }
+        if let Some(skip_ci) = overrides.and_then(|o| o.skip_ci) {
+            mr.skip_ci = skip_ci;
+        }
+        if let Some(no_checkout) = overrides.and_then(|o| o.no_checkout) {
+            mr.no_checkout = no_checkout;
+        }
+        if let Some(source_branch) = overrides.and_then(|o| o.source_branch.clone()) {
+            mr.source_branch = Some(source_branch);
+        }
+        if let Some(overrides) = overrides {
+            mr.exclude_paths
+                .extend(overrides.exclude_paths.iter().cloned());
+        }
+        mr
+    }
+
+    /// `git add .`, leaving out [`MergeRequest::exclude_paths`].
+    fn git_add(&self) -> Git {
+        Git::Add {
+            exclude_paths: self.exclude_paths.clone(),
+        }
+    }
+
+    /// Returns a copy of this merge request for the repo `dir` in the working directory,
+    /// with its overrides applied and its GitLab project resolved.
+    pub(crate) fn for_dir(&self, config: &Config, dir: &str) -> MergeRequest {
+        let overrides = config.repo_override(dir);
+        let mut mr = self.for_repo(overrides);
+        if let Some(reviewers) = self.reviewers_by_dir.get(dir) {
+            mr.reviewers = reviewers.clone();
+        }
+        mr.reviewers_by_dir.clear();
+        mr.uncommitted = self.uncommitted_by_dir.get(dir).copied();
+        mr.uncommitted_by_dir.clear();
+        mr.maintainer = self.maintainers_by_dir.get(dir).cloned();
+        mr.maintainers_by_dir.clear();
+        mr.closes_issues = self
+            .closes_issues
+            .iter()
+            .filter_map(|issue| match issue.split_once(':') {
+                Some((only, issue)) if only.trim() == dir => Some(issue.trim().to_string()),
+                Some(_) => None,
+                None => Some(issue.clone()),
+            })
+            .collect();
+        mr.glab = Some(config.glab_for(dir).to_string());
+        mr.branch_detection = config.branch_detection.clone();
+        mr.update = config.update;
+        if config.group.is_some() {
+            // `dir` is the project path, there is no checkout to work in or take the branch
+            // of the merge request to update from
+            mr.no_checkout = true;
+            if mr.source_branch.is_none() {
+                mr.source_branch = Some(mr.branch_name());
+            }
+        }
+        if config.no_git {
+            mr.no_git = true;
+            mr.no_checkout = true;
+        }
+        // An update leaves the assignees as they are
+        mr.assign_maintainer = !config.update
+            && overrides
+                .and_then(|o| o.assignee_mode)
+                .unwrap_or(config.assignee_mode)
+                == AssigneeMode::Maintainer;
+        let repo_dir = config.working_dir.join(dir);
+        if let Some(description) = mr
+            .template
+            .as_deref()
+            .and_then(|name| templates::load(&repo_dir, name))
+        {
+            mr.description = templates::fill(&description, &mr.template_values);
+        }
+        let from_remote = match config.group {
+            Some(_) => Some(gitlab::Project::on_default_host(dir)),
+            None => gitlab::project_from_remote(&repo_dir),
+        };
+        mr.project = match overrides.and_then(|o| o.project.as_deref()) {
+            Some(project) => {
+                let default_host = from_remote.map_or("gitlab.com".to_string(), |p| p.host);
+                Some(gitlab::Project::from_config(project, &default_host))
+            }
+            None => from_remote,
+        };
+        mr
+    }
+
+    /// The commands that create this merge request for a repo currently on `current_branch`.
+    /// If the current branch is the default branch, a new branch is created and all changes are committed.
+    /// On a feature branch the uncommitted changes are committed or stashed if [`Uncommitted`] says so.
+    /// In no-checkout mode only glab runs, for a branch that has already been pushed.
+    /// In update mode only glab runs, for the merge request of the current or source branch.
+    /// Nothing is run, so this is also what a dry run shows.
+    pub(crate) fn plan(&self, current_branch: &str) -> Vec<Step> {
+        if self.update {
+            let source_branch = self.source_branch.as_deref().unwrap_or(current_branch);
+            return vec![Step::remote(self.glab_update(source_branch))];
+        }
+        let mut steps = Vec::new();
+        let on_default_branch =
+            branch_detection::is_default(current_branch, self.default_branch.as_deref());
+        let push_options = self.all_push_options();
+
+        if self.no_checkout {
+            let source_branch = self.source_branch.clone().unwrap_or_else(|| {
+                if on_default_branch || current_branch.is_empty() {
+                    self.branch_name()
+                } else {
+                    current_branch.to_string()
+                }
+            });
+            if self.no_git {
+                // Read only, fails when the branch isn't on the remote
+                steps.push(Step {
+                    remote: true,
+                    ..Step::git(Git::LsRemote {
+                        branch: source_branch.clone(),
+                    })
+                });
+            }
+            let mut cmd = self.glab_create();
+            cmd.arg("--source-branch").arg(&source_branch);
+            steps.push(Step::remote(cmd));
+            if self.auto_merge {
+                steps.push(Step::remote(self.glab_auto_merge(&source_branch)));
+            }
+            return steps;
+        }
+
+        let stash = !on_default_branch && self.uncommitted == Some(Uncommitted::Stash);
+        if stash {
+            steps.push(Step::git(Git::StashPush {
+                message: format!("multimr: {}", self.title),
+            }));
+        }
+
+        let commit_changes = on_default_branch || self.uncommitted == Some(Uncommitted::Commit);
+        if on_default_branch {
+            steps.push(Step::git(Git::Switch {
+                branch: self.branch_name(),
+                create: true,
+            }));
+        }
+        if commit_changes {
+            steps.push(Step::git(self.git_add()));
+            steps.push(Step {
+                restage_on_failure: true,
+                ..Step::git(Git::Commit {
+                    message: self.title.clone(),
+                    // `-a` would stage the changes of excluded tracked files again
+                    all: self.exclude_paths.is_empty(),
+                })
+            });
+        }
+
+        // Pushed here rather than with `glab mr create --push`, which runs `git` and
+        // cannot forward push options
+        if commit_changes || !push_options.is_empty() {
+            steps.push(Step {
+                remote: true,
+                ..Step::git(Git::Push {
+                    options: push_options,
+                })
+            });
+        }
+
+        steps.push(Step::remote(self.glab_create()));
+
+        if self.auto_merge {
+            let source_branch = if on_default_branch {
+                self.branch_name()
+            } else {
+                current_branch.to_string()
+            };
+            steps.push(Step::remote(self.glab_auto_merge(&source_branch)));
+        }
+
+        if stash {
+            steps.push(Step {
+                always: true,
+                ..Step::git(Git::StashPop)
+            });
+        }
+
+        steps
+    }
+
+    /// Name of the branch created for this merge request, the slugified title unless set.
+    fn branch_name(&self) -> String {
+        self.branch
+            .clone()
+            .unwrap_or_else(|| branch_name::slugify(&self.title))
+    }
+
+    /// The glab executable to run.
+    pub(crate) fn glab(&self) -> &str {
+        self.glab.as_deref().unwrap_or(config::DEFAULT_GLAB)
+    }
+
+    /// The `glab mr create` command with all merge request fields.
+    fn glab_create(&self) -> process::Command {
+        let mut cmd = process::Command::new(self.glab());
         cmd.arg("mr").arg("create");
 
-        if let Some(assignee) = &self.assignee {
-            cmd.arg("--assignee").arg(assignee);
+        if let Some(project) = &self.project {
+            cmd.arg("--repo").arg(project.repo_arg());
+        }
+
+        if self.assign_maintainer {
+            // Left out until it is known, `create` looks it up if it wasn't before
+            if let Some(maintainer) = &self.maintainer {
+                cmd.arg("--assignee").arg(maintainer);
+            }
+        } else {
+            for assignee in &self.assignees {
+                cmd.arg("--assignee").arg(assignee);
+            }
         }
 
         if !self.reviewers.is_empty() {
@@ -39,84 +435,272 @@ impl MergeRequest {
             }
         }
 
-        let current_branch = utils::get_current_branch();
+        if let Some(target_branch) = &self.target_branch {
+            cmd.arg("--target-branch").arg(target_branch);
+        }
+
+        if let Some(milestone) = &self.milestone {
+            cmd.arg("--milestone").arg(milestone);
+        }
+
+        if self.draft {
+            cmd.arg("--draft");
+        }
+
+        if self.squash {
+            cmd.arg("--squash-before-merge");
+        }
+
+        if self.remove_source_branch {
+            cmd.arg("--remove-source-branch");
+        }
 
         cmd.arg("--title").arg(&self.title);
-        cmd.arg("--description").arg(&self.description);
-
-        if config::DEFAULT_BRANCHES.contains(&current_branch.as_str()) {
-            // If the current branch is main or master, create a new branch
-
-            println!();
-
-            process::Command::new("git")
-                .arg("switch")
-                .arg("-c")
-                .arg(self.title.replace(' ', "-"))
-                .status()
-                .expect("Failed to create new branch");
-
-            println!();
-
-            process::Command::new("git")
-                .arg("add")
-                .arg(".")
-                .status()
-                .expect("Failed to add changes");
-
-            process::Command::new("git")
-                .arg("commit")
-                .arg("-am")
-                .arg(&self.title)
-                .status()
-                .or_else(|_e| -> Result<process::ExitStatus, io::Error> {
-                    // Retry once if adding and committing fails, this might happen if the pre-commit hook formats the code
-                    // TODO: test this.
-                    process::Command::new("git")
-                        .arg("add")
-                        .arg(".")
-                        .status()
-                        .expect("Failed to add changes Second attempt");
-
-                    println!();
-
-                    let status = process::Command::new("git")
-                        .arg("commit")
-                        .arg("-am")
-                        .arg(&self.title)
-                        .status()
-                        .expect("Failed to commit changes second attempt");
-
-                    Ok(status)
-                })
-                .expect("Failed to commit changes twice.");
+        cmd.arg("--description").arg(self.full_description());
+        // There is no terminal to answer glab's confirmation prompt while the TUI runs.
+        cmd.arg("--yes");
 
-            cmd.arg("--push");
-        } else {
-            // If not, just use the current branch
-            cmd.arg("--yes");
+        cmd
+    }
+
+    /// `glab mr update` for the open merge request of `source_branch`. The labels are added to
+    /// the ones it has and the reviewers replace its reviewers.
+    fn glab_update(&self, source_branch: &str) -> process::Command {
+        let mut cmd = process::Command::new(self.glab());
+        cmd.arg("mr").arg("update").arg(source_branch);
+        if let Some(project) = &self.project {
+            cmd.arg("--repo").arg(project.repo_arg());
+        }
+        cmd.arg("--title").arg(&self.title);
+        if !self.description.is_empty() {
+            cmd.arg("--description").arg(self.full_description());
+        }
+        if !self.labels.is_empty() {
+            cmd.arg("--label").arg(self.labels.join(","));
+        }
+        if !self.reviewers.is_empty() {
+            cmd.arg("--reviewer").arg(self.reviewers.join(","));
         }
+        cmd
+    }
 
+    /// `glab mr merge` setting the merge request of `source_branch` to merge once its
+    /// pipeline succeeds, it can't be set on creation.
+    fn glab_auto_merge(&self, source_branch: &str) -> process::Command {
+        let mut cmd = process::Command::new(self.glab());
+        cmd.arg("mr").arg("merge").arg(source_branch);
+        if let Some(project) = &self.project {
+            cmd.arg("--repo").arg(project.repo_arg());
+        }
+        cmd.arg("--auto-merge").arg("--yes");
         cmd
     }
 
-    /// Run the command to create the merge request.
-    pub(crate) fn run(&self, mut cmd: process::Command) {
-        let status = cmd.status().expect("Failed to execute command");
-        if !status.success() {
-            eprintln!("Failed to create merge request: {:?}", status);
-        } else {
-            println!("Merge request created successfully.");
+    /// The description including the quick actions for the fields glab has no flag for,
+    /// with its ticket IDs linked and links to the tickets only the title mentions.
+    pub(crate) fn full_description(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.description.is_empty() {
+            parts.push(tickets::linkify(&self.description, &self.ticket_links));
+        }
+        let title_links = tickets::title_links(&self.title, &self.description, &self.ticket_links);
+        if !title_links.is_empty() {
+            parts.push(format!("Tickets: {}", title_links.join(", ")));
+        }
+        // Issues of other repos are only left when the repo isn't known, as in a preview
+        let closes: Vec<String> = self
+            .closes_issues
+            .iter()
+            .filter(|issue| !issue.contains(':'))
+            .map(|issue| {
+                let issue = issue.trim();
+                if issue.contains('#') {
+                    issue.to_string()
+                } else {
+                    format!("#{}", issue)
+                }
+            })
+            .collect();
+        if !closes.is_empty() {
+            parts.push(format!("Closes {}", closes.join(", ")));
+        }
+        if let Some(due_date) = &self.due_date {
+            parts.push(format!("/due {}", due_date));
+        }
+        if let Some(run_id) = &self.run_id {
+            parts.push(runs::marker(run_id));
+        }
+        parts.join("\n\n")
+    }
+
+    /// The configured push options, plus `ci.skip` when CI should be skipped.
+    pub(crate) fn all_push_options(&self) -> Vec<String> {
+        let mut options = self.push_options.clone();
+        if self.skip_ci {
+            options.push("ci.skip".to_string());
         }
+        options
     }
 
-    /// Print the command that would be run, useful for dry runs.
-    pub(crate) fn dry_run(&self, cmd: process::Command) {
-        println!(
-            "Current directory: {}",
-            env::current_dir().unwrap().display()
-        );
+    /// Create the merge request for the repo by running its [`MergeRequest::plan`] there.
+    /// A dry run only logs the commands. Stops at the first command that fails.
+    /// In no-checkout mode `repo_dir` does not need to exist.
+    /// While `offline` only the local steps run, the remote ones are returned to be queued.
+    /// Every command waits for the `throttle` to let it run.
+    pub(crate) fn create(
+        &self,
+        repo_dir: &Path,
+        dry_run: bool,
+        offline: bool,
+        throttle: &Throttle,
+        log: &mut String,
+    ) -> Result<Vec<Step>> {
+        let current_branch = if self.no_checkout && !repo_dir.is_dir() {
+            String::new()
+        } else {
+            self.branch_detection.current_branch(repo_dir)?
+        };
+        let mut mr = self.clone();
+        if mr.default_branch.is_none() && repo_dir.is_dir() {
+            mr.default_branch = self.branch_detection.default_branch(repo_dir)?;
+        }
+        // Set once GitLab turns out to be unreachable, the remote steps are queued from then on
+        let mut offline = offline;
+        if self.update && !dry_run && !offline {
+            let branch = self.source_branch.as_deref().unwrap_or(&current_branch);
+            match gitlab::open_merge_request_for_branch(
+                self.glab(),
+                repo_dir,
+                self.project.as_ref(),
+                branch,
+            ) {
+                Ok(Some(open)) => {
+                    log.push_str(&format!("Updating {}\n", open.web_url));
+                    execution::log_mr_url(&open.web_url, log);
+                }
+                Ok(None) => {
+                    return Err(MultimrError::NoOpenMergeRequest {
+                        branch: branch.to_string(),
+                    });
+                }
+                Err(e) if utils::is_network_error(&e.to_string()) => {
+                    log.push_str(&format!("GitLab could not be reached: {}\n", e));
+                    offline = true;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        if self.no_git
+            && self.source_branch.is_none()
+            && branch_detection::is_default(&current_branch, mr.default_branch.as_deref())
+        {
+            return Err(MultimrError::NoGit {
+                branch: current_branch,
+            });
+        }
 
-        println!("Dry run command: {:?}", cmd);
+        if let Some(maintainer) = self.maintainer.as_ref().filter(|_| self.assign_maintainer) {
+            mr.assign_maintainer = false;
+            log.push_str(&format!("Assigning maintainer {}\n", maintainer));
+            mr.assignees = vec![maintainer.clone()];
+        } else if self.assign_maintainer && offline {
+            mr.assign_maintainer = false;
+            log.push_str("Offline, keeping the chosen assignees instead of a maintainer\n");
+        } else if self.assign_maintainer {
+            mr.assign_maintainer = false;
+            match gitlab::first_maintainer(self.glab(), repo_dir) {
+                Ok(Some(maintainer)) => {
+                    log.push_str(&format!("Assigning maintainer {}\n", maintainer));
+                    mr.assignees = vec![maintainer];
+                }
+                Ok(None) => log.push_str("No maintainer found, keeping the chosen assignees\n"),
+                Err(e) if utils::is_network_error(&e.to_string()) => {
+                    log.push_str(&format!(
+                        "GitLab could not be reached, keeping the chosen assignees: {}\n",
+                        e
+                    ));
+                    offline = true;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        // `git stash pop` would pop an older stash when there was nothing to stash
+        if mr.uncommitted == Some(Uncommitted::Stash)
+            && !dry_run
+            && utils::is_clean(repo_dir).unwrap_or_default()
+        {
+            mr.uncommitted = None;
+        }
+
+        let mut queued = Vec::new();
+        let mut failure = None;
+        for mut step in mr.plan(&current_branch) {
+            if failure.is_some() && !step.always {
+                continue;
+            }
+            if dry_run {
+                log.push_str(&format!(
+                    "Dry run command: {}\n",
+                    utils::shell_line(&step.cmd)
+                ));
+                continue;
+            }
+            if offline && step.remote {
+                log.push_str(&format!(
+                    "Queued for `multimr flush`: {}\n",
+                    utils::shell_line(&step.cmd)
+                ));
+                queued.push(step);
+                continue;
+            }
+
+            if repo_dir.is_dir() {
+                step.cmd.current_dir(repo_dir);
+            }
+            // Kept to queue the step if it fails because GitLab can't be reached
+            let mut retry = process::Command::new(step.cmd.get_program());
+            retry.args(step.cmd.get_args());
+            let output_from = log.len();
+            let permit = throttle.permit(&step.cmd);
+            let mut result = match &step.git {
+                Some(git) => git.run_throttled(repo_dir, throttle, log),
+                None => execution::run_command(&mut step.cmd, log),
+            };
+            if result.is_err()
+                && step.restage_on_failure
+                && let Some(git) = &step.git
+            {
+                result = mr
+                    .git_add()
+                    .run_throttled(repo_dir, throttle, log)
+                    .and_then(|()| git.run_throttled(repo_dir, throttle, log));
+            }
+            drop(permit);
+            match result {
+                Err(_) if step.remote && utils::is_network_error(&log[output_from..]) => {
+                    log.push_str(&format!(
+                        "GitLab could not be reached, queued for `multimr flush`: {}\n",
+                        utils::shell_line(&retry)
+                    ));
+                    offline = true;
+                    queued.push(Step { cmd: retry, ..step });
+                }
+                Err(e) => {
+                    failure.get_or_insert(e);
+                }
+                Ok(()) => {}
+            }
+        }
+        if let Some(e) = failure {
+            return Err(e);
+        }
+
+        if !dry_run && queued.is_empty() && self.update {
+            log.push_str("Merge request updated successfully.\n");
+        } else if !dry_run && queued.is_empty() {
+            log.push_str("Merge request created successfully.\n");
+        }
+        Ok(queued)
     }
 }
@@ -0,0 +1,42 @@
+//! A desktop notification when a batch finishes, for batches left running in the background
+//! while doing something else. Enabled with `desktop_notification`.
+use notify_rust::Notification;
+
+use crate::error::Result;
+
+/// The summary and body of the notification for a finished batch titled `title`.
+pub(crate) fn batch_finished(
+    title: &str,
+    succeeded: usize,
+    failed: usize,
+    dry_run: bool,
+) -> (String, String) {
+    let summary = match (failed, dry_run) {
+        (0, true) => "multimr: dry run finished".to_string(),
+        (0, false) => "multimr: batch finished".to_string(),
+        (_, true) => "multimr: dry run finished with failures".to_string(),
+        (_, false) => "multimr: batch finished with failures".to_string(),
+    };
+    let body = format!("{}\n{} succeeded, {} failed", title, succeeded, failed);
+    (summary, body)
+}
+
+/// Whether notifications can be shown, reading the environment through `env`. On Linux and
+/// the BSDs they go over the D-Bus session bus, which a headless session doesn't have.
+pub(crate) fn available(env: impl Fn(&str) -> Option<String>) -> bool {
+    if cfg!(any(target_os = "macos", windows)) {
+        return true;
+    }
+    env("DBUS_SESSION_BUS_ADDRESS").is_some_and(|v| !v.is_empty())
+}
+
+/// Show a desktop notification, through D-Bus on Linux and the BSDs, the notification
+/// center on macOS and toasts on Windows.
+pub(crate) fn send(summary: &str, body: &str) -> Result<()> {
+    Notification::new()
+        .appname("multimr")
+        .summary(summary)
+        .body(body)
+        .show()?;
+    Ok(())
+}
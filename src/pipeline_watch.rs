@@ -0,0 +1,132 @@
+//! Watching the pipelines of the merge requests just created, so the results screen shows
+//! them pass or fail without opening each one.
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use crate::gitlab::{self, Project};
+
+/// How long to wait between looking up the pipelines.
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How many lookups in a row may find no pipeline before the project is assumed to have no CI.
+const MISSING_POLLS: u32 = 4;
+
+/// The pipeline of a merge request, as last looked up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum PipelineStatus {
+    /// There is no pipeline yet, it might still be created
+    Waiting,
+    /// `created`, `pending`, `running`, ...
+    Running(String),
+    Passed,
+    /// `failed`, `canceled`, or any other status it stopped in, e.g. `manual`
+    Failed(String),
+    /// No pipeline showed up, the project probably has no CI
+    Missing,
+    /// Looking it up failed
+    Unknown(String),
+}
+
+impl PipelineStatus {
+    /// The status of a pipeline as GitLab names it.
+    pub(crate) fn from_gitlab(status: &str) -> Self {
+        match status {
+            "success" => Self::Passed,
+            "created"
+            | "waiting_for_resource"
+            | "preparing"
+            | "pending"
+            | "running"
+            | "scheduled" => Self::Running(status.to_string()),
+            _ => Self::Failed(status.to_string()),
+        }
+    }
+
+    /// Whether it won't change anymore.
+    pub(crate) fn is_finished(&self) -> bool {
+        matches!(self, Self::Passed | Self::Failed(_) | Self::Missing)
+    }
+
+    /// How it is shown next to the merge request on the results screen.
+    pub(crate) fn label(&self) -> String {
+        match self {
+            Self::Waiting => "pipeline: waiting".to_string(),
+            Self::Running(status) => format!("pipeline: {}", status),
+            Self::Passed => "pipeline: passed".to_string(),
+            Self::Failed(status) => format!("pipeline: {}", status),
+            Self::Missing => "no pipeline".to_string(),
+            Self::Unknown(e) => format!("pipeline: unknown ({})", e),
+        }
+    }
+}
+
+/// A created merge request whose pipeline is watched.
+#[derive(Debug, Clone)]
+pub(crate) struct Watched {
+    /// The directory of its repo, as listed in the TUI
+    pub(crate) dir: String,
+    pub(crate) repo_dir: PathBuf,
+    pub(crate) glab: String,
+    pub(crate) project: Option<Project>,
+    pub(crate) iid: u64,
+}
+
+impl Watched {
+    fn lookup(&self) -> PipelineStatus {
+        match gitlab::merge_request(&self.glab, &self.repo_dir, self.project.as_ref(), self.iid) {
+            Ok(mr) => mr.head_pipeline.map_or(PipelineStatus::Waiting, |p| {
+                PipelineStatus::from_gitlab(&p.status)
+            }),
+            Err(e) => PipelineStatus::Unknown(e.to_string()),
+        }
+    }
+}
+
+/// Look up the pipelines of `watched` every `interval` in the background, sending the
+/// directory and status whenever one changes. Stops once all of them finished, or gave no
+/// answer [`MISSING_POLLS`] times in a row, or when the receiver is dropped.
+pub(crate) fn start(
+    watched: Vec<Watched>,
+    interval: Duration,
+) -> mpsc::Receiver<(String, PipelineStatus)> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut last: HashMap<String, PipelineStatus> = HashMap::new();
+        let mut misses: HashMap<String, u32> = HashMap::new();
+        let mut remaining = watched;
+        while !remaining.is_empty() {
+            let mut still_running = Vec::new();
+            for w in remaining {
+                let mut status = w.lookup();
+                let given_up =
+                    if matches!(status, PipelineStatus::Waiting | PipelineStatus::Unknown(_)) {
+                        let count = misses.entry(w.dir.clone()).or_default();
+                        *count += 1;
+                        *count >= MISSING_POLLS
+                    } else {
+                        misses.remove(&w.dir);
+                        false
+                    };
+                if given_up && status == PipelineStatus::Waiting {
+                    status = PipelineStatus::Missing;
+                }
+                if last.get(&w.dir) != Some(&status) {
+                    if tx.send((w.dir.clone(), status.clone())).is_err() {
+                        return;
+                    }
+                    last.insert(w.dir.clone(), status.clone());
+                }
+                if !status.is_finished() && !given_up {
+                    still_running.push(w);
+                }
+            }
+            remaining = still_running;
+            if !remaining.is_empty() {
+                std::thread::sleep(interval);
+            }
+        }
+    });
+    rx
+}
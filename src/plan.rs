@@ -0,0 +1,166 @@
+//! A batch resolved up front and saved as JSON with `multimr plan`, to be reviewed or handed
+//! to CI and run later with `multimr apply`.
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{MultimrError, Result};
+use crate::execution;
+use crate::limits::Throttle;
+use crate::merge_request::MergeRequest;
+
+/// Everything needed to create the merge requests of a batch, without the config or the TUI.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Plan {
+    pub(crate) title: String,
+    /// Switch each repo back to its original branch after creating the merge request
+    #[serde(default)]
+    pub(crate) restore_branch: bool,
+    /// Shell commands run in each repo after its merge request is created
+    #[serde(default)]
+    pub(crate) post_create: Vec<String>,
+    pub(crate) repos: Vec<PlannedRepo>,
+}
+
+/// The actions planned for one repo.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct PlannedRepo {
+    pub(crate) dir: String,
+    pub(crate) repo_dir: PathBuf,
+    /// Branch the repo was on when planned, applying refuses to run if it moved since.
+    /// Empty for projects without a local checkout.
+    pub(crate) branch: String,
+    /// The commands that will run, for reviewing the plan, applying runs `merge_request`
+    #[serde(default)]
+    pub(crate) commands: Vec<String>,
+    /// The merge request with the overrides of the repo applied and its project resolved
+    pub(crate) merge_request: MergeRequest,
+}
+
+/// What applying the plan did for one repo.
+#[derive(Debug)]
+pub(crate) struct Applied {
+    pub(crate) dir: String,
+    pub(crate) result: Result<()>,
+    pub(crate) log: String,
+    pub(crate) url: Option<String>,
+    /// Requested reviewers GitLab didn't set on the merge request
+    pub(crate) dropped_reviewers: Vec<String>,
+}
+
+/// Read a plan written by [`save`].
+pub(crate) fn load(path: &Path) -> Result<Plan> {
+    let error = |source| MultimrError::Plan {
+        path: path.to_path_buf(),
+        source,
+    };
+    let content = std::fs::read_to_string(path).map_err(error)?;
+    serde_json::from_str(&content).map_err(|e| error(e.into()))
+}
+
+/// Write `plan` to `path` as JSON.
+pub(crate) fn save(path: &Path, plan: &Plan) -> std::io::Result<()> {
+    std::fs::write(path, serde_json::to_string_pretty(plan)?)
+}
+
+/// Create the merge requests of the plan one repo after the other.
+/// A repo that is no longer on its planned branch is left alone, since its commands were
+/// resolved for that branch.
+pub(crate) fn apply(plan: &Plan) -> Vec<Applied> {
+    plan.repos
+        .iter()
+        .map(|repo| {
+            if let Err(e) = check_branch(repo) {
+                return Applied {
+                    dir: repo.dir.clone(),
+                    log: String::new(),
+                    result: Err(e),
+                    url: None,
+                    dropped_reviewers: Vec::new(),
+                };
+            }
+            let (result, log) = execution::run_repo(
+                &repo.repo_dir,
+                &repo.merge_request,
+                false,
+                false,
+                plan.restore_branch,
+                &plan.post_create,
+                &Throttle::default(),
+            );
+            Applied {
+                dir: repo.dir.clone(),
+                result: result.map(|_| ()),
+                url: execution::logged_mr_url(&log),
+                dropped_reviewers: execution::parse_confirmed_reviewers(&log)
+                    .map(|confirmed| {
+                        execution::dropped_reviewers(&repo.merge_request.reviewers, &confirmed)
+                            .into_iter()
+                            .map(str::to_string)
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                log,
+            }
+        })
+        .collect()
+}
+
+/// Fail if the repo moved to another branch since the plan was made.
+fn check_branch(repo: &PlannedRepo) -> Result<()> {
+    if repo.branch.is_empty() {
+        return Ok(());
+    }
+    let current = repo
+        .merge_request
+        .branch_detection
+        .current_branch(&repo.repo_dir)?;
+    if current != repo.branch {
+        return Err(MultimrError::CommandFailed {
+            command: "multimr apply".to_string(),
+            message: format!(
+                "{} was on branch '{}' when planned, but is on '{}' now",
+                repo.dir, repo.branch, current
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// How the commands about to run in each repo differ from those `approved` in a dry run,
+/// as the repo and what changed. Repos are compared by their first differing command.
+pub(crate) fn divergences(
+    approved: &[(String, Vec<String>)],
+    current: &[(String, Vec<String>)],
+) -> Vec<(String, String)> {
+    let mut found = Vec::new();
+    for (dir, commands) in current {
+        let Some((_, approved_commands)) = approved.iter().find(|(d, _)| d == dir) else {
+            found.push((dir.clone(), "was not part of the dry run".to_string()));
+            continue;
+        };
+        let message = if let Some((now, then)) = commands
+            .iter()
+            .zip(approved_commands)
+            .find(|(now, then)| now != then)
+        {
+            format!("runs `{}` where the dry run ran `{}`", now, then)
+        } else if let Some(now) = commands.get(approved_commands.len()) {
+            format!("also runs `{}`, unlike the dry run", now)
+        } else if let Some(then) = approved_commands.get(commands.len()) {
+            format!("no longer runs `{}` of the dry run", then)
+        } else {
+            continue;
+        };
+        found.push((dir.clone(), message));
+    }
+    for (dir, _) in approved {
+        if !current.iter().any(|(d, _)| d == dir) {
+            found.push((
+                dir.clone(),
+                "was part of the dry run, but is not in the batch anymore".to_string(),
+            ));
+        }
+    }
+    found
+}
@@ -0,0 +1,120 @@
+//! `multimr post-merge`: once the merge requests of a run are merged, bring their local repos
+//! back to where the next batch starts from, on the branch merged into, pulled, and without
+//! the merged branch.
+use git2::{BranchType, Oid};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::git::Git;
+use crate::gitlab::{self, Project};
+use crate::runs::RunRepo;
+use crate::utils;
+
+/// The `[post_merge]` of the config, everything is done by default.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct PostMerge {
+    /// `git pull --ff-only` the branch merged into after switching to it
+    pub pull: bool,
+    /// Delete the local branch once it is merged
+    pub delete_branch: bool,
+}
+
+impl Default for PostMerge {
+    fn default() -> Self {
+        Self {
+            pull: true,
+            delete_branch: true,
+        }
+    }
+}
+
+/// How syncing one repo went.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Sync {
+    /// The merge request isn't merged yet, with its state, e.g. `opened`
+    Waiting(String),
+    /// The merge request was closed without merging, the repo is left alone
+    Closed,
+    /// The repo was switched, pulled and the branch deleted, as far as configured
+    Synced,
+    /// The merge request is merged but the repo is left alone, and why
+    Left(String),
+}
+
+/// Look up the merge request of `repo`, and if it is merged switch the repo from its source
+/// branch to the branch it was merged into, pull that and delete the source branch. The
+/// commands and their output go to `log`.
+pub(crate) fn sync(repo: &RunRepo, post_merge: &PostMerge, log: &mut String) -> Result<Sync> {
+    let Some(iid) = repo.iid else {
+        return Ok(Sync::Left("no merge request was created".to_string()));
+    };
+    let project = repo
+        .project
+        .as_deref()
+        .map(|p| Project::from_remote_url(p).unwrap_or_else(|| Project::on_default_host(p)));
+    let mr = gitlab::merge_request(&repo.glab, &repo.repo_dir, project.as_ref(), iid)?;
+    match mr.state.as_str() {
+        "merged" => {}
+        "closed" => return Ok(Sync::Closed),
+        state => return Ok(Sync::Waiting(state.to_string())),
+    }
+    if !repo.repo_dir.is_dir() {
+        return Ok(Sync::Left("no checkout".to_string()));
+    }
+
+    let current = utils::get_current_branch(&repo.repo_dir)?;
+    if current == mr.source_branch {
+        if !utils::is_clean(&repo.repo_dir)? {
+            return Ok(Sync::Left(format!(
+                "uncommitted changes on {}",
+                mr.source_branch
+            )));
+        }
+        utils::switch_branch(&repo.repo_dir, &mr.target_branch, log)?;
+    } else if current != mr.target_branch {
+        return Ok(Sync::Left(format!(
+            "on {} now, neither the merged nor the target branch",
+            current
+        )));
+    }
+
+    if post_merge.pull {
+        Git::Pull.run(&repo.repo_dir, log)?;
+    }
+
+    if post_merge.delete_branch {
+        match merged_locally(repo, &mr.source_branch, mr.sha.as_deref()) {
+            Some(true) => {
+                // `-D` as `-d` refuses squashed branches, GitLab says this one is merged
+                Git::DeleteBranch {
+                    branch: mr.source_branch.clone(),
+                }
+                .run(&repo.repo_dir, log)?;
+            }
+            Some(false) => {
+                return Ok(Sync::Left(format!(
+                    "kept {}, it has commits that weren't merged",
+                    mr.source_branch
+                )));
+            }
+            None => {}
+        }
+    }
+    Ok(Sync::Synced)
+}
+
+/// Whether the local `branch` has nothing that wasn't merged: it points at the merged commit
+/// `sha` or one before it. `None` when there is no such local branch.
+fn merged_locally(repo: &RunRepo, branch: &str, sha: Option<&str>) -> Option<bool> {
+    let git = utils::open_repo(&repo.repo_dir).ok()?;
+    let tip = git
+        .find_branch(branch, BranchType::Local)
+        .ok()?
+        .get()
+        .target()?;
+    let Some(merged) = sha.and_then(|sha| Oid::from_str(sha).ok()) else {
+        return Some(false);
+    };
+    Some(tip == merged || git.graph_descendant_of(merged, tip).unwrap_or_default())
+}
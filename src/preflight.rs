@@ -0,0 +1,219 @@
+//! The checks run on every repo of the batch before the Finalize screen, for the problems
+//! that would otherwise only show up when a repo fails halfway through the batch.
+use std::path::PathBuf;
+use std::process;
+use std::sync::mpsc;
+
+use git2::BranchType;
+
+use crate::error::MultimrError;
+use crate::git;
+use crate::gitlab::Project;
+use crate::utils;
+
+/// What is checked, in the order shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Check {
+    /// glab is logged in to the host of the project
+    GlabAuth,
+    /// `origin` answers
+    Remote,
+    /// The repo is on a branch
+    Head,
+    /// The current branch merges into the target branch without conflicts
+    Conflicts,
+    /// The branch created for the merge request doesn't exist yet
+    Branch,
+}
+
+impl Check {
+    /// How the check is named in the checklist.
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Check::GlabAuth => "glab logged in",
+            Check::Remote => "origin reachable",
+            Check::Head => "on a branch",
+            Check::Conflicts => "merges cleanly",
+            Check::Branch => "branch name free",
+        }
+    }
+}
+
+/// How a check went.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Outcome {
+    Passed,
+    /// Why the repo would fail
+    Failed(String),
+    /// Why the check doesn't apply or couldn't be made, which doesn't hold up the batch
+    NotChecked(String),
+}
+
+/// The outcome of one check on one repo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CheckResult {
+    pub(crate) check: Check,
+    pub(crate) outcome: Outcome,
+}
+
+impl CheckResult {
+    fn new(check: Check, outcome: Outcome) -> Self {
+        Self { check, outcome }
+    }
+
+    /// Why the check failed, `None` if it didn't.
+    pub(crate) fn failure(&self) -> Option<&str> {
+        match &self.outcome {
+            Outcome::Failed(message) => Some(message),
+            _ => None,
+        }
+    }
+}
+
+/// A repo of the batch to check.
+#[derive(Debug, Clone)]
+pub(crate) struct Repo {
+    /// The directory of the repo, as listed in the TUI
+    pub(crate) dir: String,
+    pub(crate) repo_dir: PathBuf,
+    pub(crate) glab: String,
+    pub(crate) project: Option<Project>,
+    /// The branch created for the merge request, `None` when the current branch is used
+    pub(crate) new_branch: Option<String>,
+    /// The branch merged into, `None` for the default branch of `origin`
+    pub(crate) target_branch: Option<String>,
+}
+
+impl Repo {
+    /// Run every check, the network is only asked once for the branches of `origin`.
+    pub(crate) fn check(&self) -> Vec<CheckResult> {
+        let remote_heads = self.remote_heads();
+        vec![
+            CheckResult::new(Check::GlabAuth, self.check_glab_auth()),
+            CheckResult::new(
+                Check::Remote,
+                match &remote_heads {
+                    Ok(_) => Outcome::Passed,
+                    Err(e) => Outcome::Failed(e.clone()),
+                },
+            ),
+            CheckResult::new(Check::Head, self.check_head()),
+            CheckResult::new(Check::Conflicts, self.check_conflicts()),
+            CheckResult::new(Check::Branch, self.check_branch(remote_heads.ok())),
+        ]
+    }
+
+    fn check_glab_auth(&self) -> Outcome {
+        let mut status = process::Command::new(&self.glab);
+        status.arg("auth").arg("status");
+        let host = self
+            .project
+            .as_ref()
+            .map(|p| p.host.as_str())
+            .filter(|host| !host.is_empty());
+        if let Some(host) = host {
+            status.arg("--hostname").arg(host);
+        }
+        match utils::run_output(status.current_dir(&self.repo_dir)) {
+            Ok(_) => Outcome::Passed,
+            Err(MultimrError::CommandFailed { .. }) => Outcome::Failed(match host {
+                Some(host) => format!(
+                    "glab is not logged in to {}, run `{} auth login --hostname {}`",
+                    host, self.glab, host
+                ),
+                None => format!("glab is not logged in, run `{} auth login`", self.glab),
+            }),
+            Err(e) => Outcome::Failed(e.to_string()),
+        }
+    }
+
+    /// The branches on `origin`, or why it didn't answer.
+    fn remote_heads(&self) -> Result<Vec<String>, String> {
+        git::ls_remote_heads(&self.repo_dir).map_err(|e| format!("origin did not answer: {}", e))
+    }
+
+    fn check_head(&self) -> Outcome {
+        match utils::open_repo(&self.repo_dir) {
+            Ok(repo) if repo.head_detached().unwrap_or_default() => {
+                Outcome::Failed("detached HEAD, switch to a branch first".to_string())
+            }
+            Ok(_) => Outcome::Passed,
+            Err(e) => Outcome::Failed(e.to_string()),
+        }
+    }
+
+    /// Merge the current branch into the last fetched target branch in memory, like
+    /// `git merge-tree`. Uncommitted changes aren't part of it.
+    fn check_conflicts(&self) -> Outcome {
+        let Ok(repo) = utils::open_repo(&self.repo_dir) else {
+            return Outcome::NotChecked("not a git repository".to_string());
+        };
+        if repo.head_detached().unwrap_or_default() {
+            return Outcome::NotChecked("detached HEAD".to_string());
+        }
+        if repo.head().is_err() {
+            return Outcome::NotChecked("no commits yet".to_string());
+        }
+        let target = self.target_branch.clone().or_else(|| {
+            repo.find_reference("refs/remotes/origin/HEAD")
+                .ok()?
+                .symbolic_target()?
+                .strip_prefix("refs/remotes/origin/")
+                .map(str::to_string)
+        });
+        let Some(target) = target else {
+            return Outcome::NotChecked(
+                "default branch of origin unknown, run `git remote set-head origin --auto`"
+                    .to_string(),
+            );
+        };
+        let target_ref = format!("origin/{}", target);
+        if repo
+            .find_reference(&format!("refs/remotes/{}", target_ref))
+            .is_err()
+        {
+            return Outcome::NotChecked(format!("{} not fetched", target_ref));
+        }
+
+        match git::merge_conflicts(&repo, &format!("refs/remotes/{}", target_ref)) {
+            Ok(paths) if paths.is_empty() => Outcome::Passed,
+            Ok(paths) => Outcome::Failed(format!(
+                "conflicts with {} in {}",
+                target_ref,
+                paths.join(", ")
+            )),
+            Err(e) => Outcome::NotChecked(e.to_string()),
+        }
+    }
+
+    fn check_branch(&self, remote_heads: Option<Vec<String>>) -> Outcome {
+        let Some(branch) = &self.new_branch else {
+            return Outcome::NotChecked("the current branch is used".to_string());
+        };
+        let exists_locally = utils::open_repo(&self.repo_dir)
+            .is_ok_and(|repo| repo.find_branch(branch, BranchType::Local).is_ok());
+        if exists_locally {
+            return Outcome::Failed(format!("branch {} already exists locally", branch));
+        }
+        match remote_heads {
+            Some(heads) if heads.contains(branch) => {
+                Outcome::Failed(format!("branch {} already exists on origin", branch))
+            }
+            Some(_) => Outcome::Passed,
+            None => Outcome::NotChecked("origin did not answer".to_string()),
+        }
+    }
+}
+
+/// Check every repo on its own thread, sending the directory and the results of each as it
+/// finishes. The receiver disconnects once all of them are checked.
+pub(crate) fn start(repos: Vec<Repo>) -> mpsc::Receiver<(String, Vec<CheckResult>)> {
+    let (tx, rx) = mpsc::channel();
+    for repo in repos {
+        let tx = tx.clone();
+        std::thread::spawn(move || {
+            let _ = tx.send((repo.dir.clone(), repo.check()));
+        });
+    }
+    rx
+}
@@ -0,0 +1,185 @@
+//! `multimr config prune`: find the entries of the config that no longer point at anything,
+//! reviewers whose accounts are gone or blocked, labels none of the projects have and group
+//! members without a checkout, and remove the chosen ones from the config file. The file is
+//! edited in place, so its comments and layout stay as they are.
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+
+use toml_edit::DocumentMut;
+
+use crate::config::Config;
+use crate::digest;
+use crate::error::{MultimrError, Result};
+use crate::gitlab;
+
+/// An entry of the config that might be stale.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Entry {
+    /// A username in `reviewers`
+    Reviewer(String),
+    /// A shortcut in `labels` and the label it stands for
+    Label { key: String, label: String },
+    /// A repo in one of the `[groups]`
+    GroupMember { group: String, member: String },
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Entry::Reviewer(username) => write!(f, "reviewers: {}", username),
+            Entry::Label { key, label } => write!(f, "labels: {} = \"{}\"", key, label),
+            Entry::GroupMember { group, member } => write!(f, "groups.{}: {}", group, member),
+        }
+    }
+}
+
+/// An entry that no longer points at anything, and why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Stale {
+    pub(crate) entry: Entry,
+    pub(crate) reason: String,
+}
+
+impl Stale {
+    fn new(entry: Entry, reason: impl Into<String>) -> Self {
+        Self {
+            entry,
+            reason: reason.into(),
+        }
+    }
+}
+
+/// The stale entries of `config`, checked against GitLab and the working directories. Also
+/// returns what could not be checked, an entry is only reported when it surely is stale.
+pub(crate) fn find(config: &Config) -> (Vec<Stale>, Vec<String>) {
+    let mut stale = Vec::new();
+    let mut errors = Vec::new();
+
+    for reviewer in &config.reviewers {
+        match gitlab::user_account(config.glab_binary(), reviewer) {
+            Ok(None) => stale.push(Stale::new(
+                Entry::Reviewer(reviewer.clone()),
+                "no such user",
+            )),
+            Ok(Some(account)) if account.state != "active" => stale.push(Stale::new(
+                Entry::Reviewer(reviewer.clone()),
+                format!("account {}", account.state),
+            )),
+            Ok(Some(_)) => {}
+            Err(e) => errors.push(format!("reviewer {}: {}", reviewer, e)),
+        }
+    }
+
+    if !config.labels.is_empty() {
+        let (repos, repo_errors) = digest::workspace_repos(config);
+        errors.extend(repo_errors);
+        let mut known = HashSet::new();
+        let mut checked = 0;
+        for repo in &repos {
+            match gitlab::label_names(config.glab_for(&repo.dir), &repo.path) {
+                Ok(names) => {
+                    known.extend(names);
+                    checked += 1;
+                }
+                Err(e) => errors.push(format!("labels of {}: {}", repo.project, e)),
+            }
+        }
+        // Without any project to compare with, every label would look stale
+        if checked > 0 {
+            let mut labels: Vec<_> = config.labels.iter().collect();
+            labels.sort();
+            for (key, label) in labels {
+                if !known.contains(label) {
+                    stale.push(Stale::new(
+                        Entry::Label {
+                            key: key.clone(),
+                            label: label.clone(),
+                        },
+                        format!("none of the {} projects has this label", checked),
+                    ));
+                }
+            }
+        }
+    }
+
+    let mut groups: Vec<_> = config.repo_groups.iter().collect();
+    groups.sort();
+    for (group, members) in groups {
+        for member in members {
+            let on_disk = config
+                .roots()
+                .iter()
+                .any(|root| root.join(member).join(".git").exists());
+            if !on_disk {
+                stale.push(Stale::new(
+                    Entry::GroupMember {
+                        group: group.clone(),
+                        member: member.clone(),
+                    },
+                    "no such repository in the working directory",
+                ));
+            }
+        }
+    }
+
+    (stale, errors)
+}
+
+/// Remove `entries` from the config file `content`. Also returns the entries it doesn't
+/// have, e.g. because they come from the global config or a profile.
+pub(crate) fn remove(
+    path: &Path,
+    content: &str,
+    entries: &[Entry],
+) -> Result<(String, Vec<Entry>)> {
+    let mut doc: DocumentMut =
+        content
+            .parse()
+            .map_err(|e: toml_edit::TomlError| MultimrError::Config {
+                path: path.to_path_buf(),
+                message: e.message().to_string(),
+            })?;
+    let mut missing = Vec::new();
+    for entry in entries {
+        let removed = match entry {
+            Entry::Reviewer(username) => doc
+                .get_mut("reviewers")
+                .and_then(|item| item.as_array_mut())
+                .is_some_and(|reviewers| {
+                    let before = reviewers.len();
+                    reviewers.retain(|v| v.as_str() != Some(username));
+                    reviewers.len() < before
+                }),
+            Entry::Label { key, .. } => doc
+                .get_mut("labels")
+                .and_then(|item| item.as_table_like_mut())
+                .is_some_and(|labels| labels.remove(key).is_some()),
+            Entry::GroupMember { group, member } => doc
+                .get_mut("groups")
+                .and_then(|item| item.get_mut(group))
+                .and_then(|item| item.as_array_mut())
+                .is_some_and(|members| {
+                    let before = members.len();
+                    members.retain(|v| v.as_str() != Some(member));
+                    members.len() < before
+                }),
+        };
+        if !removed {
+            missing.push(entry.clone());
+        }
+    }
+    Ok((doc.to_string(), missing))
+}
+
+/// Remove `entries` from the config file at `path`, see [`remove`].
+pub(crate) fn write(path: &Path, entries: &[Entry]) -> Result<Vec<Entry>> {
+    let error = |source| MultimrError::ConfigWrite {
+        path: path.to_path_buf(),
+        source,
+    };
+    let content = std::fs::read_to_string(path).map_err(error)?;
+    let (content, missing) = remove(path, &content, entries)?;
+    std::fs::write(path, content).map_err(error)?;
+    Ok(missing)
+}
@@ -0,0 +1,197 @@
+//! Push and merge request steps queued while offline, run later with `multimr flush`.
+//! The local steps (branching and committing) already ran, so only the remote ones are kept.
+use std::path::{Path, PathBuf};
+use std::process;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{MultimrError, Result};
+use crate::execution;
+use crate::git::Git;
+use crate::merge_request::Step;
+use crate::utils;
+
+/// The steps still to run for one repo.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct QueuedRepo {
+    pub(crate) repo_dir: PathBuf,
+    /// Title of the merge request, to recognize the entry
+    pub(crate) title: String,
+    /// Branch the steps have to run on, the repo is switched to it if needed.
+    /// Empty for repos without a checkout.
+    pub(crate) branch: String,
+    /// Each command as its program followed by its arguments
+    pub(crate) commands: Vec<Vec<String>>,
+}
+
+impl QueuedRepo {
+    pub(crate) fn new(repo_dir: &Path, title: &str, branch: String, steps: &[Step]) -> Self {
+        Self {
+            repo_dir: repo_dir.to_path_buf(),
+            title: title.to_string(),
+            branch,
+            commands: steps
+                .iter()
+                .map(|step| {
+                    std::iter::once(step.cmd.get_program())
+                        .chain(step.cmd.get_args())
+                        .map(|arg| arg.to_string_lossy().to_string())
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+}
+
+/// What flushing a queued repo did.
+#[derive(Debug)]
+pub(crate) struct Flushed {
+    pub(crate) repo: QueuedRepo,
+    pub(crate) result: Result<()>,
+    pub(crate) log: String,
+    pub(crate) url: Option<String>,
+}
+
+/// The queue file in the state directory.
+pub(crate) fn default_path() -> Option<PathBuf> {
+    utils::state_dir().map(|dir| dir.join("queue.json"))
+}
+
+/// The queued repos, none if nothing was ever queued.
+pub(crate) fn load(path: &Path) -> std::io::Result<Vec<QueuedRepo>> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(serde_json::from_str(&content)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Replace the queue, an empty queue removes the file. Written next to it first and renamed,
+/// so a crash or a `multimr` running at the same time never sees half a queue.
+fn save(path: &Path, queue: &[QueuedRepo]) -> std::io::Result<()> {
+    if queue.is_empty() {
+        return match std::fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(format!(".{}.tmp", process::id()));
+    std::fs::write(&temp, serde_json::to_string_pretty(queue)?)?;
+    std::fs::rename(&temp, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp);
+    })
+}
+
+/// Take the advisory lock on the queue, released when the returned file is dropped. It
+/// serializes changes to the queue from the workers of a batch as well as from every other
+/// `multimr` running. The lock is on `<path>.lock` since [`save`] replaces the queue file.
+pub(crate) fn lock(path: &Path) -> std::io::Result<std::fs::File> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut lock_path = path.as_os_str().to_owned();
+    lock_path.push(".lock");
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(lock_path)?;
+    file.lock()?;
+    Ok(file)
+}
+
+/// Add a repo to the queue.
+pub(crate) fn push(path: &Path, repo: QueuedRepo) -> std::io::Result<()> {
+    let _lock = lock(path)?;
+    let mut queue = load(path)?;
+    queue.push(repo);
+    save(path, &queue)
+}
+
+/// Add a repo to the queue at its [`default_path`].
+pub(crate) fn add(repo: QueuedRepo) -> Result<()> {
+    let path = default_path().ok_or(MultimrError::NoQueue)?;
+    push(&path, repo).map_err(|source| MultimrError::Queue { path, source })
+}
+
+/// Run the queued steps repo by repo. Repos whose steps all succeed leave the queue, the
+/// others keep the steps from the one that failed onwards.
+pub(crate) fn flush(path: &Path) -> std::io::Result<Vec<Flushed>> {
+    let _lock = lock(path)?;
+    let mut flushed = Vec::new();
+    let mut remaining = Vec::new();
+    for repo in load(path)? {
+        let mut log = String::new();
+        let (result, left) = run(&repo, &mut log);
+        if !left.is_empty() {
+            remaining.push(QueuedRepo {
+                commands: left,
+                ..repo.clone()
+            });
+        }
+        flushed.push(Flushed {
+            url: execution::logged_mr_url(&log),
+            repo,
+            result,
+            log,
+        });
+    }
+    save(path, &remaining)?;
+    Ok(flushed)
+}
+
+/// Run the commands of a queued repo on its branch, switching back afterwards.
+/// Returns the commands that did not succeed, starting with the one that failed.
+fn run(repo: &QueuedRepo, log: &mut String) -> (Result<()>, Vec<Vec<String>>) {
+    let original_branch = if repo.branch.is_empty() {
+        String::new()
+    } else {
+        match utils::get_current_branch(&repo.repo_dir) {
+            Ok(branch) => branch,
+            Err(e) => return (Err(e), repo.commands.clone()),
+        }
+    };
+    if original_branch != repo.branch
+        && let Err(e) = utils::switch_branch(&repo.repo_dir, &repo.branch, log)
+    {
+        return (Err(e), repo.commands.clone());
+    }
+
+    let mut result = Ok(());
+    let mut left = Vec::new();
+    for (i, command) in repo.commands.iter().enumerate() {
+        let Some((program, args)) = command.split_first() else {
+            continue;
+        };
+        let ran = match Git::parse(args).filter(|_| program == "git") {
+            Some(git) => git.run(&repo.repo_dir, log),
+            None => {
+                let mut cmd = process::Command::new(program);
+                cmd.args(args);
+                if repo.repo_dir.is_dir() {
+                    cmd.current_dir(&repo.repo_dir);
+                }
+                execution::run_command(&mut cmd, log)
+            }
+        };
+        if let Err(e) = ran {
+            result = Err(e);
+            left = repo.commands[i..].to_vec();
+            break;
+        }
+    }
+
+    if original_branch != repo.branch
+        && let Err(e) = utils::switch_branch(&repo.repo_dir, &original_branch, log)
+    {
+        log.push_str(&format!(
+            "Failed to switch back to branch {}: {}\n",
+            original_branch, e
+        ));
+    }
+    (result, left)
+}
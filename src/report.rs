@@ -0,0 +1,228 @@
+//! A summary of a finished batch, published on GitLab as a snippet or as a comment on a
+//! tracking issue, so the audit trail lives next to the code. An umbrella issue or epic
+//! listing the merge requests can also be created, as one place to coordinate the change.
+use std::process;
+
+use crate::config::Config;
+use crate::error::{MultimrError, Result};
+use crate::execution::{RepoRun, RepoState};
+use crate::gitlab::Project;
+use crate::merge_request::MergeRequest;
+use crate::utils;
+
+/// Name of the file in the snippet.
+const SNIPPET_FILE: &str = "multimr-report.md";
+
+/// Markdown summary of the batch: the merge request and a table of every repo's outcome.
+pub(crate) fn render(mr: &MergeRequest, repos: &[RepoRun]) -> String {
+    let succeeded = repos
+        .iter()
+        .filter(|r| r.state == RepoState::Succeeded)
+        .count();
+    let mut report = format!(
+        "## multimr run: {}\n\n{} of {} merge requests created.\n\n| Repository | Status | Merge request | Reviewers |\n| --- | --- | --- | --- |\n",
+        mr.title,
+        succeeded,
+        repos.len()
+    );
+    for repo in repos {
+        let status = match repo.state {
+            RepoState::Failed => format!(
+                "failed: {}",
+                table_cell(repo.error.as_deref().unwrap_or_default())
+            ),
+            state => format!("{:?}", state).to_lowercase(),
+        };
+        report.push_str(&format!(
+            "| {} | {} | {} | {} |\n",
+            repo.dir,
+            status,
+            repo.url.as_deref().unwrap_or("-"),
+            reviewers(repo)
+        ));
+    }
+    report
+}
+
+/// `text` made fit for a markdown table cell: pipes would start a new column and line breaks
+/// a new row.
+fn table_cell(text: &str) -> String {
+    text.trim_end()
+        .replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace(['\n', '\r'], "<br>")
+}
+
+/// Description of the umbrella issue or epic: a checkbox per created merge request, to tick
+/// off as they are merged. `None` if no merge request was created.
+pub(crate) fn checklist(mr: &MergeRequest, repos: &[RepoRun]) -> Option<String> {
+    let items: Vec<String> = repos
+        .iter()
+        .filter_map(|repo| {
+            let url = repo.url.as_deref()?;
+            Some(format!("- [ ] {}: {}", repo.dir, url))
+        })
+        .collect();
+    if items.is_empty() {
+        return None;
+    }
+    let mut description = format!(
+        "Tracks the merge requests of the multimr run \"{}\", one per repository.\n\n{}\n",
+        mr.title,
+        items.join("\n")
+    );
+    if !mr.description.is_empty() {
+        description.push_str(&format!("\n## Description\n\n{}\n", mr.description));
+    }
+    Some(description)
+}
+
+/// The reviewers GitLab set on the merge request of `repo` and those it dropped, or the
+/// requested ones if that wasn't checked.
+fn reviewers(repo: &RepoRun) -> String {
+    let Some(confirmed) = &repo.confirmed_reviewers else {
+        if repo.requested_reviewers.is_empty() {
+            return "-".to_string();
+        }
+        return format!("{} (requested)", repo.requested_reviewers.join(", "));
+    };
+    let mut reviewers = if confirmed.is_empty() {
+        "none".to_string()
+    } else {
+        confirmed.join(", ")
+    };
+    let dropped = repo.dropped_reviewers();
+    if !dropped.is_empty() {
+        reviewers.push_str(&format!(", dropped by GitLab: {}", dropped.join(", ")));
+    }
+    reviewers
+}
+
+/// Split an issue reference like `group/project#42` into its project and IID.
+pub(crate) fn parse_issue(reference: &str) -> Option<(Project, u64)> {
+    let (project, iid) = reference.trim().rsplit_once('#')?;
+    if project.is_empty() {
+        return None;
+    }
+    Some((Project::from_config(project, ""), iid.parse().ok()?))
+}
+
+/// Publish the report wherever the config asks for it, and create the umbrella issue or
+/// epic holding the `checklist` when configured.
+/// Returns a message per place it was published to, e.g. the URL of the snippet.
+pub(crate) fn publish(
+    config: &Config,
+    title: &str,
+    report: &str,
+    checklist: Option<&str>,
+) -> Result<Vec<String>> {
+    let mut published = Vec::new();
+    if let Some(checklist) = checklist {
+        if let Some(project) = &config.umbrella_issue {
+            let url = create_umbrella_issue(config.glab_binary(), project, title, checklist)?;
+            published.push(format!("Umbrella issue created: {}", url));
+        }
+        if let Some(group) = &config.umbrella_epic {
+            let url = create_umbrella_epic(config.glab_binary(), group, title, checklist)?;
+            published.push(format!("Umbrella epic created: {}", url));
+        }
+    }
+    if config.report_snippet {
+        published.push(create_snippet(config.glab_binary(), title, report)?);
+    }
+    if let Some(reference) = &config.report_issue {
+        comment_on_issue(config.glab_binary(), reference, report)?;
+        published.push(format!("Run report added to {}", reference));
+    }
+    Ok(published)
+}
+
+/// Create a private personal snippet holding the report, returns its URL.
+fn create_snippet(glab: &str, title: &str, report: &str) -> Result<String> {
+    let mut cmd = process::Command::new(glab);
+    cmd.arg("snippet")
+        .arg("create")
+        .arg("--personal")
+        .arg("--visibility")
+        .arg("private")
+        .arg("--title")
+        .arg(format!("multimr run: {}", title))
+        .arg("--filename")
+        .arg(SNIPPET_FILE);
+    let output = utils::run_with_stdin(&mut cmd, report)?;
+    Ok(output
+        .split_whitespace()
+        .find(|word| word.starts_with("http"))
+        .map(|url| format!("Run report published: {}", url))
+        .unwrap_or_else(|| "Run report published as a snippet".to_string()))
+}
+
+/// Add the report as a comment to the issue referenced as `group/project#42`.
+fn comment_on_issue(glab: &str, reference: &str, report: &str) -> Result<()> {
+    let Some((project, iid)) = parse_issue(reference) else {
+        return Err(MultimrError::CommandFailed {
+            command: "glab issue note".to_string(),
+            message: format!(
+                "'{}' is not an issue reference like group/project#42",
+                reference
+            ),
+        });
+    };
+    let mut cmd = process::Command::new(glab);
+    cmd.arg("issue")
+        .arg("note")
+        .arg(iid.to_string())
+        .arg("--repo")
+        .arg(project.repo_arg())
+        .arg("--message")
+        .arg(report);
+    utils::run_output(&mut cmd).map(|_| ())
+}
+
+/// Create an issue in `project` with the checklist, returns its URL.
+fn create_umbrella_issue(
+    glab: &str,
+    project: &str,
+    title: &str,
+    checklist: &str,
+) -> Result<String> {
+    let mut cmd = process::Command::new(glab);
+    cmd.arg("issue")
+        .arg("create")
+        .arg("--repo")
+        .arg(Project::from_config(project, "").repo_arg())
+        .arg("--title")
+        .arg(title)
+        .arg("--description")
+        .arg(checklist)
+        .arg("--yes");
+    let output = String::from_utf8_lossy(&utils::run_output(&mut cmd)?).to_string();
+    Ok(output
+        .split_whitespace()
+        .find(|word| word.starts_with("http"))
+        .unwrap_or(project)
+        .to_string())
+}
+
+/// Create an epic in `group` with the checklist, returns its URL. glab has no command for
+/// epics, so this goes through the API.
+fn create_umbrella_epic(glab: &str, group: &str, title: &str, checklist: &str) -> Result<String> {
+    #[derive(serde::Deserialize)]
+    struct Epic {
+        web_url: String,
+    }
+    let mut cmd = process::Command::new(glab);
+    cmd.arg("api")
+        .arg("--method")
+        .arg("POST")
+        .arg(format!(
+            "groups/{}/epics",
+            group.trim_matches('/').replace('/', "%2F")
+        ))
+        .arg("--raw-field")
+        .arg(format!("title={}", title))
+        .arg("--raw-field")
+        .arg(format!("description={}", checklist));
+    let epic: Epic = serde_json::from_slice(&utils::run_output(&mut cmd)?)?;
+    Ok(epic.web_url)
+}
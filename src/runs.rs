@@ -0,0 +1,260 @@
+//! A record of every batch that created merge requests, so they can be found and updated
+//! together later, e.g. with `multimr sync-description`.
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::digest;
+use crate::due_date;
+use crate::error::{MultimrError, Result};
+use crate::execution::{RepoRun, RepoState};
+use crate::gitlab::{self, ListedMergeRequest};
+use crate::merge_request::MergeRequest;
+use crate::utils;
+
+/// One batch of merge requests.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct RunRecord {
+    /// When the batch started as `YYYYMMDD-HHMMSS` in UTC, unique enough per user
+    pub(crate) id: String,
+    /// Seconds since the Unix epoch
+    pub(crate) started_at: u64,
+    pub(crate) title: String,
+    /// The description as typed, placeholders like `{repo}` not filled in yet
+    pub(crate) description: String,
+    pub(crate) repos: Vec<RunRepo>,
+}
+
+/// The merge request of one repo in a batch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct RunRepo {
+    pub(crate) dir: String,
+    pub(crate) repo_dir: PathBuf,
+    /// The value for glab's `--repo` flag, if the project is known
+    pub(crate) project: Option<String>,
+    pub(crate) glab: String,
+    /// How the repo ended, e.g. `succeeded` or `failed`
+    pub(crate) outcome: String,
+    pub(crate) url: Option<String>,
+    pub(crate) iid: Option<u64>,
+}
+
+impl RunRecord {
+    /// The record of a batch creating `mr` in `repos`, started at `started_at`.
+    pub(crate) fn new(
+        config: &Config,
+        mr: &MergeRequest,
+        repos: &[RepoRun],
+        started_at: u64,
+    ) -> Self {
+        Self {
+            id: run_id(started_at),
+            started_at,
+            title: mr.title.clone(),
+            description: mr.description.clone(),
+            repos: repos
+                .iter()
+                .map(|repo| RunRepo {
+                    repo_dir: config.working_dir.join(&repo.dir),
+                    project: mr.for_dir(config, &repo.dir).project.map(|p| p.repo_arg()),
+                    glab: config.glab_for(&repo.dir).to_string(),
+                    outcome: outcome(repo.state).to_string(),
+                    url: repo.url.clone(),
+                    iid: repo.iid,
+                    dir: repo.dir.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl RunRecord {
+    /// When the run started, as `YYYY-MM-DD HH:MM` in UTC.
+    pub(crate) fn started(&self) -> String {
+        let id = run_id(self.started_at);
+        format!(
+            "{}-{}-{} {}:{} UTC",
+            &id[0..4],
+            &id[4..6],
+            &id[6..8],
+            &id[9..11],
+            &id[11..13]
+        )
+    }
+
+    /// How many repos ended how, e.g. `3 succeeded, 1 failed`.
+    pub(crate) fn outcome(&self) -> String {
+        let mut counts: Vec<(&str, usize)> = Vec::new();
+        for repo in &self.repos {
+            match counts.iter_mut().find(|(o, _)| *o == repo.outcome) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((&repo.outcome, 1)),
+            }
+        }
+        counts
+            .iter()
+            .map(|(outcome, count)| format!("{} {}", count, outcome))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// The URLs of the merge requests that were created.
+    pub(crate) fn urls(&self) -> Vec<String> {
+        self.repos.iter().filter_map(|r| r.url.clone()).collect()
+    }
+}
+
+/// How a repo ended, as stored in the record.
+fn outcome(state: RepoState) -> &'static str {
+    match state {
+        RepoState::Succeeded => "succeeded",
+        RepoState::Failed => "failed",
+        RepoState::Skipped => "skipped",
+        RepoState::Queued => "queued",
+        RepoState::Pending | RepoState::Running => "not run",
+    }
+}
+
+/// Seconds since the Unix epoch.
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// The ID of a run started at `secs` since the Unix epoch, `YYYYMMDD-HHMMSS` in UTC.
+pub(crate) fn run_id(secs: u64) -> String {
+    let (y, m, d) = due_date::civil_from_days((secs / 86_400) as i64);
+    let time = secs % 86_400;
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        y,
+        m,
+        d,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// The file holding the records in the state directory.
+pub(crate) fn default_path() -> Option<PathBuf> {
+    utils::state_dir().map(|dir| dir.join("runs.json"))
+}
+
+/// Every recorded run, oldest first.
+pub(crate) fn load(path: &Path) -> std::io::Result<Vec<RunRecord>> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(serde_json::from_str(&content)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Add the record, or replace the one with the same ID, e.g. after retrying failed repos.
+pub(crate) fn save(path: &Path, record: RunRecord) -> std::io::Result<()> {
+    let mut records = load(path)?;
+    match records.iter_mut().find(|r| r.id == record.id) {
+        Some(existing) => *existing = record,
+        None => records.push(record),
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(&records)?)
+}
+
+/// The run with the given ID, or the last one.
+pub(crate) fn find(path: &Path, id: Option<&str>) -> std::io::Result<Option<RunRecord>> {
+    let records = load(path)?;
+    Ok(match id {
+        Some(id) => records.into_iter().find(|r| r.id == id),
+        None => records.into_iter().last(),
+    })
+}
+
+/// The hidden HTML comment marking the description of every merge request of run `id`.
+pub(crate) fn marker(id: &str) -> String {
+    format!("<!-- multimr-run: {} -->", id)
+}
+
+/// Whether `id` looks like a run ID, so it can be searched for as is.
+pub(crate) fn is_run_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// The merge requests of run `id` in the repos of the workspace, see
+/// [`digest::workspace_repos`], found by the marker in their descriptions, with the project
+/// each belongs to. Also returns why projects could not be searched.
+pub(crate) fn search(
+    config: &Config,
+    id: &str,
+) -> (Vec<(String, ListedMergeRequest)>, Vec<String>) {
+    let (repos, mut errors) = digest::workspace_repos(config);
+    let marker = marker(id);
+    let mut found = Vec::new();
+    for repo in repos {
+        match gitlab::merge_requests_mentioning(config.glab_for(&repo.dir), &repo.path, id) {
+            Ok(mrs) => found.extend(
+                mrs.into_iter()
+                    .filter(|mr| {
+                        mr.description
+                            .as_deref()
+                            .is_some_and(|d| d.contains(&marker))
+                    })
+                    .map(|mr| (repo.project.clone(), mr)),
+            ),
+            Err(e) => errors.push(format!("{}: {}", repo.dir, e)),
+        }
+    }
+    (found, errors)
+}
+
+/// Fill in the per-repo placeholders of a description: `{repo}` and `{project}`.
+pub(crate) fn fill_in(description: &str, repo: &RunRepo) -> String {
+    description
+        .replace("{repo}", &repo.dir)
+        .replace("{project}", repo.project.as_deref().unwrap_or(&repo.dir))
+}
+
+/// The command replacing the description of the merge request of `repo`.
+/// `None` for repos whose merge request was not created.
+pub(crate) fn update_description(repo: &RunRepo, description: &str) -> Option<process::Command> {
+    let iid = repo.iid?;
+    let mut cmd = process::Command::new(&repo.glab);
+    cmd.arg("mr").arg("update").arg(iid.to_string());
+    match &repo.project {
+        Some(project) => cmd.arg("--repo").arg(project),
+        None => cmd.current_dir(&repo.repo_dir),
+    };
+    cmd.arg("--description").arg(fill_in(description, repo));
+    Some(cmd)
+}
+
+/// Replace the description of every merge request of the run, returning the outcome per repo.
+pub(crate) fn sync_description(record: &RunRecord, description: &str) -> Vec<(String, Result<()>)> {
+    record
+        .repos
+        .iter()
+        .filter_map(|repo| {
+            let mut cmd = update_description(repo, description)?;
+            let mut log = String::new();
+            // The log starts with the command line, glab's output follows
+            let result = utils::run_checked(&mut cmd, &mut log).map_err(|e| match e {
+                MultimrError::CommandFailed { command, .. } => MultimrError::CommandFailed {
+                    command,
+                    message: log
+                        .split_once('\n')
+                        .map_or("", |(_, output)| output)
+                        .trim()
+                        .to_string(),
+                },
+                e => e,
+            });
+            Some((repo.dir.clone(), result))
+        })
+        .collect()
+}
@@ -0,0 +1,61 @@
+//! Renders the plan as a standalone shell script, for running the commands by hand.
+use std::path::{Path, PathBuf};
+
+use crate::utils;
+
+/// Where the script is written from the Finalize screen unless `--emit-script` names a path.
+pub(crate) const DEFAULT_PATH: &str = "multimr.sh";
+
+/// The commands planned for one repo.
+#[derive(Debug, Clone)]
+pub(crate) struct RepoScript {
+    pub(crate) name: String,
+    /// Where the commands run, `None` for projects without a local checkout
+    pub(crate) dir: Option<PathBuf>,
+    /// Written as comments above the commands, e.g. the branch the repo was on
+    pub(crate) notes: Vec<String>,
+    pub(crate) commands: Vec<String>,
+}
+
+/// A POSIX shell script running the commands of every repo in its own subshell.
+/// A repo whose command fails is reported and the script continues with the next one,
+/// exiting unsuccessfully at the end.
+pub(crate) fn render(title: &str, repos: &[RepoScript]) -> String {
+    let mut script = format!(
+        "#!/bin/sh\n# Merge request: {}\n# Generated by multimr, every repository runs in a subshell that stops at its first failing command.\n\nfailed=0\n",
+        title.replace('\n', " ")
+    );
+    for repo in repos {
+        script.push_str(&format!("\n# {}\n", repo.name));
+        for note in &repo.notes {
+            script.push_str(&format!("# {}\n", note));
+        }
+        script.push_str("(\n  set -e\n");
+        if let Some(dir) = &repo.dir {
+            script.push_str(&format!(
+                "  cd {}\n",
+                utils::shell_quote(&dir.to_string_lossy())
+            ));
+        }
+        for command in &repo.commands {
+            script.push_str(&format!("  {}\n", command));
+        }
+        script.push_str(&format!(
+            ") || {{ echo {} >&2; failed=1; }}\n",
+            utils::shell_quote(&format!("multimr: {} failed", repo.name))
+        ));
+    }
+    script.push_str("\nexit $failed\n");
+    script
+}
+
+/// Write `script` to `path` and make it executable.
+pub(crate) fn write(path: &Path, script: &str) -> std::io::Result<()> {
+    std::fs::write(path, script)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(())
+}
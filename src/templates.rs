@@ -0,0 +1,83 @@
+//! Merge request description templates checked into the repos, as GitLab offers them in its UI.
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Where GitLab looks for merge request templates, relative to the repo root.
+pub(crate) const TEMPLATE_DIR: &str = ".gitlab/merge_request_templates";
+
+/// Names of the templates in the repo, without the `.md` extension, sorted.
+pub(crate) fn names(repo_dir: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(repo_dir.join(TEMPLATE_DIR)) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "md") {
+                path.file_stem().map(|s| s.to_string_lossy().to_string())
+            } else {
+                None
+            }
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+/// Content of the template called `name` in the repo, if it has one.
+pub(crate) fn load(repo_dir: &Path, name: &str) -> Option<String> {
+    std::fs::read_to_string(repo_dir.join(TEMPLATE_DIR).join(format!("{}.md", name)))
+        .ok()
+        .map(|content| content.trim_end().to_string())
+}
+
+/// Names of the `{{PLACEHOLDER}}`s in a template, in the order they first appear.
+/// Only letters, digits, `_` and `-` make a name, other braces are left alone.
+pub(crate) fn placeholders(content: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut rest = content;
+    while let Some(start) = rest.find("{{") {
+        rest = &rest[start + 2..];
+        let Some(end) = rest.find("}}") else {
+            break;
+        };
+        let name = rest[..end].trim();
+        if is_placeholder_name(name) && !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// Replace the placeholders of `content` that have a value, others stay as they are.
+pub(crate) fn fill(content: &str, values: &HashMap<String, String>) -> String {
+    let mut filled = String::with_capacity(content.len());
+    let mut rest = content;
+    while let Some(start) = rest.find("{{") {
+        filled.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match after
+            .find("}}")
+            .and_then(|end| Some((end, values.get(after[..end].trim())?)))
+        {
+            Some((end, value)) => {
+                filled.push_str(value);
+                rest = &after[end + 2..];
+            }
+            None => {
+                filled.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    filled.push_str(rest);
+    filled
+}
+
+fn is_placeholder_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
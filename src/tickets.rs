@@ -0,0 +1,90 @@
+//! Turns bare ticket IDs like `ABC-123` into markdown links to the issue tracker, so
+//! reviewers can click through from the merge request.
+use std::collections::HashMap;
+
+/// A ticket ID found in a text, with the byte range it takes up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Reference {
+    start: usize,
+    end: usize,
+    id: String,
+    url: String,
+}
+
+/// The ticket IDs in `text` whose prefix has a URL in `links`, e.g. `ABC` for `ABC-123`.
+/// IDs that are already part of a markdown link or a URL are left out.
+fn references(text: &str, links: &HashMap<String, String>) -> Vec<Reference> {
+    let bytes = text.as_bytes();
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let starts_word = i == 0 || !is_id_char(bytes[i - 1]) && !b"[/#".contains(&bytes[i - 1]);
+        if !starts_word || !bytes[i].is_ascii_uppercase() {
+            i += 1;
+            continue;
+        }
+        let prefix_end = i + bytes[i..]
+            .iter()
+            .take_while(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+            .count();
+        let digits = bytes.get(prefix_end + 1..).map_or(0, |rest| {
+            rest.iter().take_while(|b| b.is_ascii_digit()).count()
+        });
+        let end = prefix_end + 1 + digits;
+        let ends_word = bytes.get(end).is_none_or(|&b| !is_id_char(b) && b != b']');
+        match links.get(&text[i..prefix_end]) {
+            Some(url) if bytes.get(prefix_end) == Some(&b'-') && digits > 0 && ends_word => {
+                let id = &text[i..end];
+                found.push(Reference {
+                    start: i,
+                    end,
+                    id: id.to_string(),
+                    url: url.replace("{id}", id),
+                });
+                i = end;
+            }
+            _ => i = prefix_end.max(i + 1),
+        }
+    }
+    found
+}
+
+fn is_id_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'-' || b == b'_'
+}
+
+/// `text` with every ticket ID linked to its tracker, see [`references`].
+pub(crate) fn linkify(text: &str, links: &HashMap<String, String>) -> String {
+    let mut linked = String::with_capacity(text.len());
+    let mut last = 0;
+    for reference in references(text, links) {
+        linked.push_str(&text[last..reference.start]);
+        linked.push_str(&format!("[{}]({})", reference.id, reference.url));
+        last = reference.end;
+    }
+    linked.push_str(&text[last..]);
+    linked
+}
+
+/// Links to the tickets mentioned in `title` but not in `description`, since a title can't
+/// hold links. Empty if there are none.
+pub(crate) fn title_links(
+    title: &str,
+    description: &str,
+    links: &HashMap<String, String>,
+) -> Vec<String> {
+    let mentioned: Vec<String> = references(description, links)
+        .into_iter()
+        .map(|r| r.id)
+        .collect();
+    let mut added: Vec<String> = Vec::new();
+    let mut lines = Vec::new();
+    for reference in references(title, links) {
+        if mentioned.contains(&reference.id) || added.contains(&reference.id) {
+            continue;
+        }
+        lines.push(format!("[{}]({})", reference.id, reference.url));
+        added.push(reference.id);
+    }
+    lines
+}
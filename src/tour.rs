@@ -0,0 +1,75 @@
+//! `multimr tour`: walk through every screen against a few throwaway repos, explaining each
+//! one on the way. The batch at the end is always a dry run, nothing is pushed or created.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use git2::{Repository, RepositoryInitOptions, Signature};
+
+use crate::config::{Config, LabelFetch};
+use crate::error::{MultimrError, Result};
+
+/// The sandbox repos, each with a committed README and an uncommitted change to it.
+const REPOS: [&str; 3] = ["api", "web", "worker"];
+
+/// Create the sandbox repos in a fresh temporary directory and return it.
+pub(crate) fn create_sandbox() -> Result<PathBuf> {
+    let root = std::env::temp_dir().join(format!("multimr-tour-{}", std::process::id()));
+    if root.exists() {
+        fs::remove_dir_all(&root).map_err(|source| MultimrError::Sandbox {
+            path: root.clone(),
+            source,
+        })?;
+    }
+    for name in REPOS {
+        create_repo(&root.join(name), name)?;
+    }
+    Ok(root)
+}
+
+/// A repo on `main` with one commit and a change waiting to be committed.
+fn create_repo(dir: &Path, name: &str) -> Result<()> {
+    let sandbox_error = |source| MultimrError::Sandbox {
+        path: dir.to_path_buf(),
+        source,
+    };
+    fs::create_dir_all(dir).map_err(sandbox_error)?;
+    let repo = Repository::init_opts(dir, RepositoryInitOptions::new().initial_head("main"))?;
+    let readme = dir.join("README.md");
+    fs::write(&readme, format!("# {}\n", name)).map_err(sandbox_error)?;
+
+    let mut index = repo.index()?;
+    index.add_path(Path::new("README.md"))?;
+    index.write()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let signature = Signature::now("multimr tour", "tour@example.com")?;
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        "Initial commit",
+        &tree,
+        &[],
+    )?;
+
+    fs::write(
+        &readme,
+        format!("# {}\n\nMaintained by the platform team.\n", name),
+    )
+    .map_err(sandbox_error)?;
+    Ok(())
+}
+
+/// The settings of the tour: the sandbox as working directory, made-up reviewers and
+/// nothing fetched from GitLab.
+pub(crate) fn config(sandbox: &Path) -> Config {
+    Config {
+        working_dir: sandbox.to_path_buf(),
+        reviewers: vec!["alice".to_string(), "bob".to_string(), "carol".to_string()],
+        assignee: Some("you".to_string()),
+        dry_run: true,
+        fetch_reviewers: false,
+        fetch_labels: LabelFetch::Off,
+        tour: true,
+        ..Config::default()
+    }
+}
@@ -1,28 +1,512 @@
 //! Helper functions for the multimr application.
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use git2::{DiffOptions, Repository, Status, StatusOptions};
+
+use crate::error::{MultimrError, Result};
+use crate::git::Git;
+
+/// Open the git repository at `repo_dir`, which must be the top of its working tree.
+pub(crate) fn open_repo(repo_dir: &Path) -> Result<Repository> {
+    Ok(Repository::open(repo_dir)?)
+}
 
 /// Getting the current branch is needed to determine if a new branch should be created for the merge request.
-pub(crate) fn get_current_branch() -> String {
-    let current_branch_output = std::process::Command::new("git")
-        .arg("branch")
-        .arg("--show-current")
+/// Empty for a detached HEAD, like `git branch --show-current`.
+pub(crate) fn get_current_branch(repo_dir: &Path) -> Result<String> {
+    let repo = open_repo(repo_dir)?;
+    // HEAD itself rather than what it resolves to, so a branch without commits has a name too
+    let head = repo.find_reference("HEAD")?;
+    Ok(head
+        .symbolic_target()
+        .and_then(|target| target.strip_prefix("refs/heads/"))
+        .unwrap_or_default()
+        .to_string())
+}
+
+/// The uncommitted changes in the repo, including untracked files but not ignored ones.
+fn changes(repo: &Repository) -> Result<git2::Statuses<'_>> {
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .include_ignored(false)
+        .exclude_submodules(true);
+    Ok(repo.statuses(Some(&mut options))?)
+}
+
+/// Whether the repo has no uncommitted changes, including untracked files.
+pub(crate) fn is_clean(repo_dir: &Path) -> Result<bool> {
+    Ok(changes(&open_repo(repo_dir)?)?.is_empty())
+}
+
+/// How many commits the current branch is ahead of and behind its upstream branch.
+/// An error for branches without an upstream.
+pub(crate) fn ahead_behind(repo_dir: &Path) -> Result<(usize, usize)> {
+    let repo = open_repo(repo_dir)?;
+    let branch = git2::Branch::wrap(repo.head()?);
+    let upstream = branch.upstream()?;
+    match (branch.get().target(), upstream.get().target()) {
+        (Some(local), Some(upstream)) => Ok(repo.graph_ahead_behind(local, upstream)?),
+        _ => Ok((0, 0)),
+    }
+}
+
+/// The subject line of the last commit on the current branch.
+pub(crate) fn last_commit_subject(repo_dir: &Path) -> Result<String> {
+    let repo = open_repo(repo_dir)?;
+    let commit = repo.head()?.peel_to_commit()?;
+    Ok(commit.summary().unwrap_or_default().to_string())
+}
+
+/// The URL of the `origin` remote of the repo.
+pub(crate) fn origin_url(repo_dir: &Path) -> Result<String> {
+    let repo = open_repo(repo_dir)?;
+    let remote = repo.find_remote("origin")?;
+    Ok(remote.url().unwrap_or_default().to_string())
+}
+
+/// What changed in the repo without being committed: the status in the short format of
+/// `git status --short`, followed by the diffstat of the tracked files against `HEAD`.
+pub(crate) fn change_summary(repo_dir: &Path) -> Result<String> {
+    let repo = open_repo(repo_dir)?;
+    let mut status = String::new();
+    for entry in changes(&repo)?.iter() {
+        let (index, worktree) = status_codes(entry.status());
+        status.push_str(&format!(
+            "{}{} {}\n",
+            index,
+            worktree,
+            entry.path().unwrap_or_default()
+        ));
+    }
+    if status.is_empty() {
+        return Ok("Nothing to commit, working tree clean".to_string());
+    }
+    // Without any commit there is no HEAD to diff against, the status says it all
+    let diff_stat = repo
+        .head()
+        .and_then(|head| head.peel_to_tree())
+        .and_then(|tree| repo.diff_tree_to_workdir_with_index(Some(&tree), None))
+        .and_then(|diff| diff.stats())
+        .and_then(|stats| stats.to_buf(git2::DiffStatsFormat::FULL, 80))
+        .map(|buf| buf.as_str().unwrap_or_default().to_string())
+        .unwrap_or_default();
+    Ok(format!("{}\n{}", status.trim_end(), diff_stat.trim_end()))
+}
+
+/// How many lines committing everything with `git add .` would add and remove, counting
+/// untracked files too.
+pub(crate) fn changed_lines(repo_dir: &Path) -> Result<usize> {
+    let repo = open_repo(repo_dir)?;
+    // Without any commit everything is new
+    let head = repo.head().and_then(|head| head.peel_to_tree()).ok();
+    let mut options = DiffOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .show_untracked_content(true);
+    let stats = repo
+        .diff_tree_to_workdir_with_index(head.as_ref(), Some(&mut options))?
+        .stats()?;
+    Ok(stats.insertions() + stats.deletions())
+}
+
+/// The uncommitted paths `git add .` would pick up that match one of `patterns`, see
+/// [`crate::config::Config::suspicious_paths`]. A matching directory is listed once, as
+/// `dir/`, instead of every file in it.
+pub(crate) fn suspicious_paths(repo_dir: &Path, patterns: &[String]) -> Result<Vec<String>> {
+    let repo = open_repo(repo_dir)?;
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .include_ignored(false)
+        .exclude_submodules(true);
+    let mut found: Vec<String> = Vec::new();
+    for entry in repo.statuses(Some(&mut options))?.iter() {
+        let Some(path) = entry.path() else {
+            continue;
+        };
+        if let Some(matched) = patterns.iter().find_map(|p| path_match(p, path))
+            && !found.contains(&matched)
+        {
+            found.push(matched);
+        }
+    }
+    Ok(found)
+}
+
+/// The part of `path` matching `pattern`: up to the directory for `dir/`, which matches a
+/// directory anywhere, else the whole path if its file name or the path match the glob.
+fn path_match(pattern: &str, path: &str) -> Option<String> {
+    if let Some(dir) = pattern.strip_suffix('/') {
+        let mut prefix = String::new();
+        let mut components = path.split('/').peekable();
+        while let Some(component) = components.next() {
+            components.peek()?;
+            prefix.push_str(component);
+            prefix.push('/');
+            if glob_match(dir, component) {
+                return Some(prefix);
+            }
+        }
+        return None;
+    }
+    let name = path.rsplit('/').next().unwrap_or(path);
+    (glob_match(pattern, name) || glob_match(pattern, path)).then(|| path.to_string())
+}
+
+/// The two status letters `git status --short` shows for an entry: index and working tree.
+fn status_codes(status: Status) -> (char, char) {
+    if status.is_wt_new() && !status.intersects(Status::INDEX_NEW) {
+        return ('?', '?');
+    }
+    let index = if status.is_index_new() {
+        'A'
+    } else if status.is_index_modified() {
+        'M'
+    } else if status.is_index_deleted() {
+        'D'
+    } else if status.is_index_renamed() {
+        'R'
+    } else if status.is_index_typechange() {
+        'T'
+    } else {
+        ' '
+    };
+    let worktree = if status.is_wt_modified() {
+        'M'
+    } else if status.is_wt_deleted() {
+        'D'
+    } else if status.is_wt_renamed() {
+        'R'
+    } else if status.is_wt_typechange() {
+        'T'
+    } else if status.is_conflicted() {
+        'U'
+    } else {
+        ' '
+    };
+    (index, worktree)
+}
+
+/// Whether `name` matches the glob `pattern`, where `*` matches any run of characters
+/// and `?` a single one.
+pub(crate) fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Where to resume after the last `*` if the rest doesn't match: pattern and name position
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p + 1, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((after_star, start)) => {
+                    p = after_star;
+                    n = start + 1;
+                    backtrack = Some((after_star, start + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Directory for the user's configuration, following the XDG base directory spec.
+pub(crate) fn config_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|dir| dir.join("multimr"))
+}
+
+/// Directory for state that outlives a run, following the XDG base directory spec.
+pub(crate) fn state_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))
+        .map(|dir| dir.join("multimr"))
+}
+
+/// What glab and git2 report when the server can't be reached at all, as opposed to it
+/// answering with an error.
+const NETWORK_ERRORS: [&str; 11] = [
+    "no such host",
+    "connection refused",
+    "i/o timeout",
+    "context deadline exceeded",
+    "network is unreachable",
+    "could not resolve host",
+    "failed to connect to",
+    "connection timed out",
+    "temporary failure in name resolution",
+    "proxyconnect",
+    "failed to resolve address",
+];
+
+/// Whether the output of a failed glab or git command says the server couldn't be reached.
+pub(crate) fn is_network_error(output: &str) -> bool {
+    let output = output.to_lowercase();
+    NETWORK_ERRORS.iter().any(|error| output.contains(error))
+}
+
+/// Switch the repo to an existing branch, used to leave repos as they were found.
+pub(crate) fn switch_branch(repo_dir: &Path, branch: &str, log: &mut String) -> Result<()> {
+    Git::Switch {
+        branch: branch.to_string(),
+        create: false,
+    }
+    .run(repo_dir, log)
+}
+
+/// Like [`run_logged`], but a command that can't be started or exits unsuccessfully is an error.
+pub(crate) fn run_checked(cmd: &mut process::Command, log: &mut String) -> Result<()> {
+    run_checked_stdout(cmd, log).map(|_| ())
+}
+
+/// Like [`run_checked`], also returning what the command printed on stdout alone.
+pub(crate) fn run_checked_stdout(cmd: &mut process::Command, log: &mut String) -> Result<String> {
+    checked(cmd, None, log)
+}
+
+/// Like [`run_checked`], with `input` written to the command's stdin.
+pub(crate) fn run_checked_input(
+    cmd: &mut process::Command,
+    input: &str,
+    log: &mut String,
+) -> Result<()> {
+    checked(cmd, Some(input), log).map(|_| ())
+}
+
+fn checked(cmd: &mut process::Command, input: Option<&str>, log: &mut String) -> Result<String> {
+    let output = run_logged(cmd, input, log).map_err(|source| MultimrError::Spawn {
+        command: shell_line(cmd),
+        source,
+    })?;
+    if !output.status.success() {
+        return Err(MultimrError::CommandFailed {
+            command: shell_line(cmd),
+            message: output.status.to_string(),
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Run a command to completion, appending the command line and its output to `log`
+/// instead of writing to the terminal the TUI is drawing on. Its stdin is `input`, if any.
+fn run_logged(
+    cmd: &mut process::Command,
+    input: Option<&str>,
+    log: &mut String,
+) -> std::io::Result<process::Output> {
+    log.push_str(&format!("$ {}\n", shell_line(cmd)));
+    let output = match input {
+        None => cmd.stdin(process::Stdio::null()).output()?,
+        Some(input) => {
+            let mut child = cmd
+                .stdin(process::Stdio::piped())
+                .stdout(process::Stdio::piped())
+                .stderr(process::Stdio::piped())
+                .spawn()?;
+            if let Some(mut stdin) = child.stdin.take()
+                && let Err(e) = stdin.write_all(input.as_bytes())
+                // A command may exit without reading it
+                && e.kind() != std::io::ErrorKind::BrokenPipe
+            {
+                return Err(e);
+            }
+            child.wait_with_output()?
+        }
+    };
+    log.push_str(&String::from_utf8_lossy(&output.stdout));
+    log.push_str(&String::from_utf8_lossy(&output.stderr));
+    Ok(output)
+}
+
+/// Run a command to completion and return what it printed, a command that can't be started
+/// or exits unsuccessfully is an error with what it printed to stderr.
+pub(crate) fn run_output(cmd: &mut process::Command) -> Result<Vec<u8>> {
+    let output = cmd
+        .stdin(process::Stdio::null())
         .output()
-        .expect("Failed to get current branch");
+        .map_err(|source| MultimrError::Spawn {
+            command: shell_line(cmd),
+            source,
+        })?;
+    if !output.status.success() {
+        return Err(MultimrError::CommandFailed {
+            command: shell_line(cmd),
+            message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    Ok(output.stdout)
+}
 
-    String::from_utf8_lossy(&current_branch_output.stdout)
-        .trim()
-        .to_string()
+/// Run a command to completion with `input` on its stdin and return what it printed, a
+/// command that can't be started or exits unsuccessfully is an error with its stderr.
+pub(crate) fn run_with_stdin(cmd: &mut process::Command, input: &str) -> Result<String> {
+    let command = shell_line(cmd);
+    let spawn_error = |source| MultimrError::Spawn {
+        command: command.clone(),
+        source,
+    };
+    let mut child = cmd
+        .stdin(process::Stdio::piped())
+        .stdout(process::Stdio::piped())
+        .stderr(process::Stdio::piped())
+        .spawn()
+        .map_err(spawn_error)?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input.as_bytes()).map_err(spawn_error)?;
+    }
+    let output = child.wait_with_output().map_err(spawn_error)?;
+    if !output.status.success() {
+        return Err(MultimrError::CommandFailed {
+            command,
+            message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// POST the JSON `body` to `url` with curl, adding `headers` like `Authorization: Bearer ...`.
+/// The URL, headers and body are handed to curl as its config on stdin, so tokens in them
+/// don't show up in the process list. Errors name only the host for the same reason.
+pub(crate) fn post_json(url: &str, headers: &[String], body: &str) -> Result<()> {
+    let mut cmd = process::Command::new("curl");
+    cmd.arg("--config").arg("-");
+    let host = url_host(url);
+    run_with_stdin(&mut cmd, &curl_config(url, headers, body))
+        .map(|_| ())
+        .map_err(|e| match e {
+            MultimrError::Spawn { source, .. } => MultimrError::Spawn {
+                command: format!("curl {}", host),
+                source,
+            },
+            MultimrError::CommandFailed { message, .. } => MultimrError::CommandFailed {
+                command: format!("curl {}", host),
+                message: message.replace(url, host),
+            },
+            e => e,
+        })
+}
+
+/// The curl config POSTing the JSON `body` to `url` with `headers`, see [`post_json`].
+pub(crate) fn curl_config(url: &str, headers: &[String], body: &str) -> String {
+    // Quoted strings in a curl config only know these escapes
+    let quote = |value: &str| {
+        let escaped = value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r")
+            .replace('\t', "\\t");
+        format!("\"{}\"", escaped)
+    };
+    let mut config = String::from(
+        "silent\nshow-error\nfail\nmax-time = 30\nheader = \"Content-Type: application/json\"\n",
+    );
+    for header in headers {
+        config.push_str(&format!("header = {}\n", quote(header)));
+    }
+    config.push_str(&format!("data-binary = {}\n", quote(body)));
+    config.push_str(&format!("url = {}\n", quote(url)));
+    config
+}
+
+/// Whether `program` is an executable found on the `PATH`, without running it.
+pub(crate) fn on_path(program: &str) -> bool {
+    let Some(path) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&path).any(|dir| {
+        let candidate = dir.join(program);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::metadata(&candidate)
+                .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        }
+        #[cfg(not(unix))]
+        {
+            candidate.is_file() || candidate.with_extension("exe").is_file()
+        }
+    })
+}
+
+/// The scheme and host of `url`, leaving out the path and query that may hold a secret,
+/// like the token in a Slack webhook URL.
+fn url_host(url: &str) -> &str {
+    let start = url.find("://").map_or(0, |i| i + 3);
+    let end = url[start..]
+        .find(['/', '?', '#'])
+        .map_or(url.len(), |i| start + i);
+    &url[..end]
+}
+
+/// Escape `value` to be put between the quotes of a JSON string.
+pub(crate) fn json_escape(value: &str) -> String {
+    let quoted = serde_json::Value::from(value).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+/// Render a command as it would be typed in a shell, quoting arguments where needed.
+pub(crate) fn shell_line(cmd: &process::Command) -> String {
+    std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|arg| shell_quote(&arg.to_string_lossy()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Quote a single shell word, leaving it as is if it has no special characters.
+pub(crate) fn shell_quote(word: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "-_./:=@,+%".contains(c);
+    if !word.is_empty() && word.chars().all(is_safe) {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
+}
+
+/// Open a URL in the default browser without waiting for it.
+pub(crate) fn open_in_browser(url: &str) -> std::io::Result<()> {
+    let mut cmd = if cfg!(target_os = "macos") {
+        process::Command::new("open")
+    } else if cfg!(windows) {
+        let mut cmd = process::Command::new("cmd");
+        cmd.arg("/C").arg("start").arg("");
+        cmd
+    } else {
+        process::Command::new("xdg-open")
+    };
+    cmd.arg(url)
+        .stdin(process::Stdio::null())
+        .stdout(process::Stdio::null())
+        .stderr(process::Stdio::null())
+        .spawn()
+        .map(|_| ())
 }
 
 /// Ensure that the `glab` CLI is installed, since it's essential for running multimr.
-pub(crate) fn ensure_glab_installed() {
-    if std::process::Command::new("glab")
+/// `glab` is the configured executable, a path or a name looked up on the PATH.
+pub(crate) fn ensure_glab_installed(glab: &str) -> Result<()> {
+    process::Command::new(glab)
         .arg("--version")
         .output()
-        .is_err()
-    {
-        eprintln!(
-            "[Error] GitLab CLI `glab` is not installed. Please install it to use this application."
-        );
-        std::process::exit(1);
-    }
+        .map(|_| ())
+        .map_err(|_| MultimrError::GlabNotInstalled {
+            glab: glab.to_string(),
+        })
 }
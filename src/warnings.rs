@@ -0,0 +1,67 @@
+//! The outcome of the checks run before a batch, ranked by how much they matter, so the
+//! Finalize screen can show them in one place and refuse to start on blockers.
+use std::fmt;
+
+/// How much a warning matters, ordered from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Severity {
+    /// Worth knowing, e.g. that nothing will be pushed in a dry run
+    Info,
+    /// Likely a mistake, but the batch can run
+    Warning,
+    /// The batch can't run, unless started with `--force`
+    Blocker,
+}
+
+impl Severity {
+    /// The short tag shown in front of each warning.
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Blocker => "blocker",
+        }
+    }
+}
+
+/// A single finding of the pre-flight checks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Warning {
+    pub(crate) severity: Severity,
+    /// The repo it is about, `None` for the batch as a whole
+    pub(crate) repo: Option<String>,
+    pub(crate) message: String,
+}
+
+impl Warning {
+    pub(crate) fn new(severity: Severity, repo: Option<&str>, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            repo: repo.map(str::to_string),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.repo {
+            Some(repo) => write!(f, "[{}] {}: {}", self.severity.label(), repo, self.message),
+            None => write!(f, "[{}] {}", self.severity.label(), self.message),
+        }
+    }
+}
+
+/// Sort `warnings` with the most severe first, keeping the order of the checks within a severity.
+pub(crate) fn sorted(mut warnings: Vec<Warning>) -> Vec<Warning> {
+    warnings.sort_by_key(|w| std::cmp::Reverse(w.severity));
+    warnings
+}
+
+/// Number of blockers among `warnings`.
+pub(crate) fn blockers(warnings: &[Warning]) -> usize {
+    warnings
+        .iter()
+        .filter(|w| w.severity == Severity::Blocker)
+        .count()
+}
@@ -0,0 +1,176 @@
+//! Harness for running the multimr binary against throwaway git repositories, with a fake
+//! `glab` on the PATH that records how it was called instead of talking to GitLab.
+#![allow(dead_code)]
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// URL the fake glab prints for every merge request it "creates".
+pub const MR_URL: &str = "https://gitlab.example.com/group/project/-/merge_requests/7";
+
+/// A temporary directory holding the repos, the fake glab and what it recorded.
+/// Removed again when dropped.
+pub struct Sandbox {
+    pub dir: PathBuf,
+}
+
+impl Sandbox {
+    /// An empty sandbox, `name` keeps the directories of concurrently running tests apart.
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("multimr-it-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("bin")).unwrap();
+        let sandbox = Self { dir };
+        sandbox.install_fake_glab();
+        sandbox
+    }
+
+    /// Write the fake glab: every call appends its arguments as one tab separated line to
+    /// `glab.log`, `mr create` prints [`MR_URL`] and `api` what [`Sandbox::respond_to_api`] set.
+    fn install_fake_glab(&self) {
+        let glab = self.dir.join("bin").join("glab");
+        std::fs::write(
+            &glab,
+            format!(
+                "#!/bin/sh\n\
+                 (IFS=\"$(printf '\\t')\"; echo \"$*\") >> {log}\n\
+                 if [ \"$1 $2\" = \"mr create\" ]; then echo {url}; fi\n\
+                 if [ \"$1\" = api ] && [ -f {api} ]; then cat {api}; fi\n",
+                log = quote(&self.glab_log()),
+                api = quote(&self.dir.join("api.json")),
+                url = MR_URL
+            ),
+        )
+        .unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&glab, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+    }
+
+    /// Have every `glab api` call print `json`.
+    pub fn respond_to_api(&self, json: &str) {
+        std::fs::write(self.dir.join("api.json"), json).unwrap();
+    }
+
+    fn glab_log(&self) -> PathBuf {
+        self.dir.join("glab.log")
+    }
+
+    /// The arguments of every glab call so far, except the `--version` check.
+    pub fn glab_calls(&self) -> Vec<Vec<String>> {
+        std::fs::read_to_string(self.glab_log())
+            .unwrap_or_default()
+            .lines()
+            .map(|line| line.split('\t').map(str::to_string).collect::<Vec<_>>())
+            .filter(|args| args.first().map(String::as_str) != Some("--version"))
+            .collect()
+    }
+
+    /// A repo with one commit on `main` and an `origin` to push to.
+    pub fn repo(&self, name: &str) -> PathBuf {
+        let origin = self.dir.join("origins").join(format!("{}.git", name));
+        std::fs::create_dir_all(&origin).unwrap();
+        git(&origin, &["init", "--quiet", "--bare"]);
+        git(&origin, &["config", "receive.advertisePushOptions", "true"]);
+
+        let repo = self.dir.join("repos").join(name);
+        std::fs::create_dir_all(&repo).unwrap();
+        git(&repo, &["init", "--quiet", "--initial-branch", "main"]);
+        git(&repo, &["config", "user.name", "multimr test"]);
+        git(&repo, &["config", "user.email", "test@example.com"]);
+        git(&repo, &["config", "commit.gpgsign", "false"]);
+        std::fs::write(repo.join("README.md"), "# test\n").unwrap();
+        git(&repo, &["add", "."]);
+        git(&repo, &["commit", "--quiet", "-m", "Initial commit"]);
+        git(
+            &repo,
+            &["remote", "add", "origin", &origin.to_string_lossy()],
+        );
+        repo
+    }
+
+    /// Write a plan for `repos`, each on the branch it is on now, creating a merge request
+    /// titled `title`. `extra` is merged into each merge request, e.g. `"draft": true`.
+    pub fn plan(&self, title: &str, repos: &[&Path], extra: &str) -> PathBuf {
+        let repos: Vec<String> = repos
+            .iter()
+            .map(|repo| {
+                format!(
+                    r#"{{"dir": {dir}, "repo_dir": {repo_dir}, "branch": {branch}, "merge_request": {{"title": {title}{extra}}}}}"#,
+                    dir = json(&repo.file_name().unwrap().to_string_lossy()),
+                    repo_dir = json(&repo.to_string_lossy()),
+                    branch = json(&current_branch(repo)),
+                    title = json(title),
+                    extra = if extra.is_empty() {
+                        String::new()
+                    } else {
+                        format!(", {}", extra)
+                    },
+                )
+            })
+            .collect();
+        let path = self.dir.join("plan.json");
+        std::fs::write(
+            &path,
+            format!(
+                r#"{{"title": {}, "post_create": ["echo {{iid}} > created"], "repos": [{}]}}"#,
+                json(title),
+                repos.join(", ")
+            ),
+        )
+        .unwrap();
+        path
+    }
+
+    /// Run the multimr binary with `args`, the fake glab first on the PATH.
+    pub fn multimr(&self, args: &[&str]) -> Output {
+        let path = format!(
+            "{}:{}",
+            self.dir.join("bin").display(),
+            std::env::var("PATH").unwrap_or_default()
+        );
+        Command::new(env!("CARGO_BIN_EXE_multimr"))
+            .args(args)
+            .current_dir(&self.dir)
+            .env("PATH", path)
+            .env("XDG_STATE_HOME", self.dir.join("state"))
+            .env("XDG_CONFIG_HOME", self.dir.join("config"))
+            .output()
+            .unwrap()
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Run git in `dir`, panicking when it fails.
+pub fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "git {} failed: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+pub fn current_branch(repo: &Path) -> String {
+    git(repo, &["rev-parse", "--abbrev-ref", "HEAD"])
+}
+
+fn json(value: &str) -> String {
+    format!("{:?}", value)
+}
+
+fn quote(path: &Path) -> String {
+    format!("'{}'", path.to_string_lossy().replace('\'', r"'\''"))
+}
@@ -0,0 +1,146 @@
+//! Runs `multimr apply` end to end against temporary repos, checking the git state it leaves
+//! behind and the glab calls it makes. Run only these with `just smoke`.
+mod common;
+
+use common::{MR_URL, Sandbox, current_branch, git};
+
+fn stdout(output: &std::process::Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+fn assert_success(output: &std::process::Output) {
+    assert!(
+        output.status.success(),
+        "multimr failed\nstdout:\n{}\nstderr:\n{}",
+        stdout(output),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn default_branch_creates_branch_commits_and_pushes_before_glab() {
+    let sandbox = Sandbox::new("default-branch");
+    let repo = sandbox.repo("api");
+    std::fs::write(repo.join("README.md"), "# changed\n").unwrap();
+
+    let plan = sandbox.plan("Bump dependencies", &[&repo], r#""reviewers": ["alice"]"#);
+    sandbox.respond_to_api(r#"{"reviewers": [{"username": "alice"}]}"#);
+    let output = sandbox.multimr(&["apply", &plan.to_string_lossy()]);
+    assert_success(&output);
+
+    assert_eq!(current_branch(&repo), "bump-dependencies");
+    assert_eq!(
+        git(&repo, &["log", "-1", "--format=%s"]),
+        "Bump dependencies"
+    );
+    assert!(git(&repo, &["status", "--porcelain", "--untracked-files=no"]).is_empty());
+    let origin = sandbox.dir.join("origins").join("api.git");
+    assert_eq!(
+        git(&origin, &["log", "-1", "--format=%s", "bump-dependencies"]),
+        "Bump dependencies"
+    );
+    assert_eq!(
+        git(&repo, &["rev-parse", "--abbrev-ref", "@{upstream}"]),
+        "origin/bump-dependencies"
+    );
+    assert_eq!(
+        sandbox.glab_calls(),
+        vec![
+            [
+                "mr",
+                "create",
+                "--reviewer",
+                "alice",
+                "--title",
+                "Bump dependencies",
+                "--description",
+                "",
+                "--yes",
+            ]
+            .map(str::to_string)
+            .to_vec(),
+            // The reviewers GitLab set are checked on the created merge request
+            ["api", "projects/:id/merge_requests/7"]
+                .map(str::to_string)
+                .to_vec(),
+        ]
+    );
+    assert!(stdout(&output).contains("Reviewers confirmed by GitLab: alice"));
+    assert!(!stdout(&output).contains("dropped"));
+    assert!(stdout(&output).contains(&format!("MR: {}", MR_URL)));
+    assert_eq!(
+        std::fs::read_to_string(repo.join("created"))
+            .unwrap()
+            .trim(),
+        "7"
+    );
+}
+
+#[test]
+fn reviewers_dropped_by_gitlab_are_reported() {
+    let sandbox = Sandbox::new("dropped-reviewers");
+    let repo = sandbox.repo("api");
+    git(&repo, &["switch", "--quiet", "-c", "feature"]);
+
+    let plan = sandbox.plan("Fix login", &[&repo], r#""reviewers": ["alice", "@ghost"]"#);
+    sandbox.respond_to_api(r#"{"reviewers": [{"username": "alice"}]}"#);
+    let output = sandbox.multimr(&["apply", &plan.to_string_lossy()]);
+    assert_success(&output);
+    assert!(
+        stdout(&output).contains("Warning: GitLab dropped the reviewers ghost"),
+        "{}",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn feature_branch_only_opens_the_merge_request() {
+    let sandbox = Sandbox::new("feature-branch");
+    let repo = sandbox.repo("web");
+    git(&repo, &["switch", "--quiet", "-c", "feature"]);
+    let head = git(&repo, &["rev-parse", "HEAD"]);
+
+    let plan = sandbox.plan("Fix login", &[&repo], "");
+    let output = sandbox.multimr(&["apply", &plan.to_string_lossy()]);
+    assert_success(&output);
+
+    assert_eq!(current_branch(&repo), "feature");
+    assert_eq!(git(&repo, &["rev-parse", "HEAD"]), head);
+    let calls = sandbox.glab_calls();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0][..2], ["mr", "create"]);
+    assert!(!calls[0].contains(&"--push".to_string()));
+}
+
+#[test]
+fn push_options_push_with_git_before_glab() {
+    let sandbox = Sandbox::new("push-options");
+    let repo = sandbox.repo("docs");
+    std::fs::write(repo.join("README.md"), "# changed\n").unwrap();
+
+    let plan = sandbox.plan("Fix typo", &[&repo], r#""skip_ci": true"#);
+    let output = sandbox.multimr(&["apply", &plan.to_string_lossy()]);
+    assert_success(&output);
+
+    let origin = sandbox.dir.join("origins").join("docs.git");
+    assert_eq!(
+        git(&origin, &["log", "-1", "--format=%s", "fix-typo"]),
+        "Fix typo"
+    );
+    let calls = sandbox.glab_calls();
+    assert_eq!(calls.len(), 1);
+    assert!(!calls[0].contains(&"--push".to_string()));
+}
+
+#[test]
+fn repos_that_moved_branch_since_planning_are_left_alone() {
+    let sandbox = Sandbox::new("moved-branch");
+    let repo = sandbox.repo("cli");
+    let plan = sandbox.plan("Refactor", &[&repo], "");
+    git(&repo, &["switch", "--quiet", "-c", "elsewhere"]);
+
+    let output = sandbox.multimr(&["apply", &plan.to_string_lossy()]);
+    assert!(!output.status.success());
+    assert!(stdout(&output).contains("== cli (failed)"));
+    assert!(sandbox.glab_calls().is_empty());
+}